@@ -45,7 +45,7 @@ impl GetLogsRpcConfig {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Default, CandidType, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Default, CandidType, Deserialize, Serialize)]
 pub enum ConsensusStrategy {
     /// All providers must return the same non-error result.
     #[default]
@@ -220,12 +220,12 @@ impl RpcService {
                 chain_id: 1,
                 services: vec![api.clone()],
             },
-            RpcService::EthMainnet(service) => RpcServices::EthMainnet(Some(vec![service.clone()])),
-            RpcService::EthSepolia(service) => RpcServices::EthSepolia(Some(vec![service.clone()])),
+            RpcService::EthMainnet(service) => RpcServices::EthMainnet(Some(vec![*service])),
+            RpcService::EthSepolia(service) => RpcServices::EthSepolia(Some(vec![*service])),
             RpcService::ArbitrumOne(service)
             | RpcService::BaseMainnet(service)
             | RpcService::OptimismMainnet(service) => {
-                RpcServices::ArbitrumOne(Some(vec![service.clone()]))
+                RpcServices::ArbitrumOne(Some(vec![*service]))
             }
         }
     }
@@ -0,0 +1,321 @@
+use candid::Nat;
+use ic_canister_log::log;
+use ic_govmind_types::dao::{
+    Airdrop, AirdropArg, AirdropStatus, DistributionType, AIRDROP_SUBACCOUNT, HOLDER_SUBACCOUNT,
+};
+use icrc_ledger_types::icrc1::account::Account;
+
+use crate::{
+    distribution::{self, base_token_ledger, queue_payout, subaccount_account},
+    ic_log::INFO,
+    store,
+    utils::{current_time_secs, nat_to_be_bytes32, sha256},
+};
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// Moves the airdrop's tokens from the treasury into `AIRDROP_SUBACCOUNT`, along with a ledger
+/// fee per recipient and one to return the unclaimed tokens, and records the airdrop. The funding
+/// is a payout named after the proposal, so a retried execution resends the same transfer rather
+/// than funding the airdrop twice. Returns the airdrop id.
+pub async fn create_airdrop(arg: AirdropArg) -> Result<u64, String> {
+    decode_hash(&arg.merkle_root)?;
+    if arg.total_amount == 0u64 {
+        return Err("Airdrop amount must be positive".to_string());
+    }
+    if arg.recipients == 0 {
+        return Err("An airdrop needs at least one recipient".to_string());
+    }
+    if arg.claim_deadline <= current_time_secs() {
+        return Err("Claim deadline must be in the future".to_string());
+    }
+
+    let token_service = base_token_ledger()?;
+    let transfers = arg.recipients.saturating_add(1);
+    let funding_id = format!("airdrop-fund:{}", arg.proposal_id);
+    if store::distribution::get_payout(&funding_id).is_none() {
+        let fees = token_service.icrc1_fee().await? * Nat::from(transfers);
+        queue_payout(
+            funding_id.clone(),
+            DistributionType::Airdrop,
+            subaccount_account(AIRDROP_SUBACCOUNT).to_string(),
+            arg.total_amount.clone() + fees,
+            Some(HOLDER_SUBACCOUNT),
+        );
+        store::state::save();
+    }
+    let funded = distribution::pay_claim(&token_service, &funding_id)
+        .await
+        .map_err(|e| format!("Failed to fund the airdrop: {}", e))?;
+    // The fees are those of the first attempt, whatever the ledger charges now.
+    let fee_reserve = funded - arg.total_amount.clone();
+
+    let id = store::airdrops::add_airdrop(Airdrop {
+        id: 0,
+        merkle_root: arg.merkle_root.to_lowercase(),
+        total_amount: arg.total_amount,
+        recipients: arg.recipients,
+        claimed: Nat::from(0u64),
+        claims: 0,
+        fee: fee_reserve.clone() / Nat::from(transfers),
+        fee_reserve,
+        claim_deadline: arg.claim_deadline,
+        status: AirdropStatus::Active,
+        returned: None,
+        proposal_id: arg.proposal_id,
+        created_at: current_time_secs(),
+    });
+    store::state::save();
+    Ok(id)
+}
+
+/// Pays the caller's allocation of an airdrop once `proof` shows that the (caller, amount) leaf
+/// belongs to its Merkle root. Returns the amount paid.
+pub async fn claim(airdrop_id: u64, amount: Nat, proof: Vec<String>) -> Result<Nat, String> {
+    let account = Account::from(ic_cdk::api::msg_caller());
+    let airdrop = store::airdrops::get_airdrop(airdrop_id)
+        .ok_or_else(|| format!("Airdrop {} not found", airdrop_id))?;
+    if airdrop.status != AirdropStatus::Active || current_time_secs() > airdrop.claim_deadline {
+        return Err(format!("Airdrop {} has ended", airdrop_id));
+    }
+    if amount == 0u64 {
+        return Err("Claim amount must be positive".to_string());
+    }
+
+    let root = decode_hash(&airdrop.merkle_root)?;
+    let proof = proof
+        .iter()
+        .map(|node| decode_hash(node))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !verify_proof(&root, leaf_hash(&account, &amount)?, &proof) {
+        return Err("Invalid Merkle proof".to_string());
+    }
+
+    let token_service = base_token_ledger()?;
+    let payout_id = format!("airdrop:{}:{}", airdrop_id, account);
+    if store::distribution::get_payout(&payout_id).is_some() {
+        return Err(format!("Airdrop {} was already claimed", airdrop_id));
+    }
+    store::airdrops::update_airdrop(airdrop_id, |airdrop| {
+        if airdrop.claimed.clone() + amount.clone() > airdrop.total_amount {
+            return Err(format!("Airdrop {} has no tokens left", airdrop_id));
+        }
+        airdrop.claimed += amount.clone();
+        airdrop.claims += 1;
+        airdrop.fee_reserve = match airdrop.fee_reserve > airdrop.fee {
+            true => airdrop.fee_reserve.clone() - airdrop.fee.clone(),
+            false => Nat::from(0u64),
+        };
+        Ok(())
+    })?;
+    queue_payout(
+        payout_id.clone(),
+        DistributionType::Airdrop,
+        account.to_string(),
+        amount,
+        Some(AIRDROP_SUBACCOUNT),
+    );
+    store::state::save();
+
+    distribution::pay_claim(&token_service, &payout_id).await
+}
+
+/// Ends the airdrops past their claim deadline, returning their unclaimed tokens and the fees
+/// reserved for the missing claims to the treasury.
+pub fn plan_returns(now: u64) {
+    for airdrop in store::airdrops::list_airdrops() {
+        if airdrop.status != AirdropStatus::Active || now <= airdrop.claim_deadline {
+            continue;
+        }
+
+        let returned = store::airdrops::update_airdrop(airdrop.id, |airdrop| Ok(end(airdrop)));
+
+        match returned {
+            Ok(returned) if returned > 0u64 => {
+                queue_payout(
+                    format!("airdrop-return:{}", airdrop.id),
+                    DistributionType::Airdrop,
+                    subaccount_account(HOLDER_SUBACCOUNT).to_string(),
+                    returned,
+                    Some(AIRDROP_SUBACCOUNT),
+                );
+            }
+            Ok(_) => {}
+            Err(e) => log!(INFO, "Failed to end airdrop {}: {}", airdrop.id, e),
+        }
+    }
+}
+
+/// Expires an airdrop. Returns what goes back to the treasury: the unclaimed tokens and the fee
+/// reserve, less the fee of the return itself.
+fn end(airdrop: &mut Airdrop) -> Nat {
+    let unclaimed = match airdrop.total_amount > airdrop.claimed {
+        true => airdrop.total_amount.clone() - airdrop.claimed.clone(),
+        false => Nat::from(0u64),
+    };
+    let available = unclaimed + airdrop.fee_reserve.clone();
+    let returned = match available > airdrop.fee {
+        true => available - airdrop.fee.clone(),
+        false => Nat::from(0u64),
+    };
+    airdrop.status = AirdropStatus::Expired;
+    airdrop.returned = Some(returned.clone());
+    airdrop.fee_reserve = Nat::from(0u64);
+    returned
+}
+
+/// Reprices the fee reserves of the active airdrops after the ledger fee changed to `fee`. An
+/// airdrop whose reserve cannot pay its remaining claims and its return at the new fee is topped
+/// up from the treasury; surplus reserves are returned when the airdrop ends.
+pub fn reprice_fees(fee: &Nat, proposal_id: u64) {
+    let mut top_up = Nat::from(0u64);
+    for airdrop in store::airdrops::list_airdrops() {
+        if airdrop.status != AirdropStatus::Active {
+            continue;
+        }
+        let shortfall =
+            store::airdrops::update_airdrop(airdrop.id, |airdrop| Ok(reprice(airdrop, fee)));
+        top_up += shortfall.unwrap_or_default();
+    }
+
+    if top_up > 0u64 {
+        queue_payout(
+            format!("airdrop-fees:{}", proposal_id),
+            DistributionType::Airdrop,
+            subaccount_account(AIRDROP_SUBACCOUNT).to_string(),
+            top_up,
+            Some(HOLDER_SUBACCOUNT),
+        );
+    }
+}
+
+/// Sets the airdrop's fee to `fee`. Returns how much its reserve falls short of paying the
+/// remaining claims and the return at that fee, which is added to the reserve.
+fn reprice(airdrop: &mut Airdrop, fee: &Nat) -> Nat {
+    airdrop.fee = fee.clone();
+    let transfers_left = airdrop.recipients.saturating_sub(airdrop.claims) + 1;
+    let needed = fee.clone() * Nat::from(transfers_left);
+    if needed <= airdrop.fee_reserve {
+        return Nat::from(0u64);
+    }
+    let shortfall = needed.clone() - airdrop.fee_reserve.clone();
+    airdrop.fee_reserve = needed;
+    shortfall
+}
+
+/// Leaf of an allocation: sha256(0x00 || ICRC-1 account text || amount as 32 big-endian bytes).
+pub fn leaf_hash(account: &Account, amount: &Nat) -> Result<[u8; 32], String> {
+    let mut data = vec![LEAF_PREFIX];
+    data.extend_from_slice(account.to_string().as_bytes());
+    data.extend_from_slice(&nat_to_be_bytes32(amount)?);
+    Ok(to_hash(sha256(&data)))
+}
+
+/// Inner nodes hash their children in sorted order, sha256(0x01 || low || high), so proofs carry
+/// no left/right flags.
+fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    let mut data = vec![NODE_PREFIX];
+    data.extend_from_slice(low);
+    data.extend_from_slice(high);
+    to_hash(sha256(&data))
+}
+
+pub fn verify_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |hash, sibling| node_hash(&hash, sibling));
+    &computed == root
+}
+
+fn decode_hash(hex: &str) -> Result<[u8; 32], String> {
+    hex::decode(hex.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("Invalid 32-byte hash {}", hex))
+}
+
+fn to_hash(digest: Vec<u8>) -> [u8; 32] {
+    digest.try_into().expect("sha256 digests are 32 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+
+    fn account(id: u8) -> Account {
+        Account::from(Principal::from_slice(&[id]))
+    }
+
+    fn leaf(id: u8) -> [u8; 32] {
+        leaf_hash(&account(id), &Nat::from(id as u64 * 100)).unwrap()
+    }
+
+    #[test]
+    fn verifies_every_leaf_of_the_tree() {
+        let ab = node_hash(&leaf(1), &leaf(2));
+        let root = node_hash(&ab, &leaf(3));
+
+        assert!(verify_proof(&root, leaf(1), &[leaf(2), leaf(3)]));
+        assert!(verify_proof(&root, leaf(2), &[leaf(1), leaf(3)]));
+        assert!(verify_proof(&root, leaf(3), &[ab]));
+    }
+
+    #[test]
+    fn rejects_a_different_amount_or_account() {
+        let root = node_hash(&node_hash(&leaf(1), &leaf(2)), &leaf(3));
+        let proof = [leaf(2), leaf(3)];
+
+        let inflated = leaf_hash(&account(1), &Nat::from(101u64)).unwrap();
+        assert!(!verify_proof(&root, inflated, &proof));
+        let stranger = leaf_hash(&account(4), &Nat::from(100u64)).unwrap();
+        assert!(!verify_proof(&root, stranger, &proof));
+    }
+
+    #[test]
+    fn decodes_only_32_byte_hashes() {
+        assert!(decode_hash(&format!("0x{}", "ab".repeat(32))).is_ok());
+        assert!(decode_hash("0x1234").is_err());
+        assert!(decode_hash("not hex").is_err());
+    }
+
+    fn airdrop() -> Airdrop {
+        Airdrop {
+            id: 1,
+            merkle_root: "ab".repeat(32),
+            total_amount: Nat::from(1_000u64),
+            recipients: 4,
+            claimed: Nat::from(300u64),
+            claims: 2,
+            fee: Nat::from(10u64),
+            fee_reserve: Nat::from(30u64),
+            claim_deadline: 100,
+            status: AirdropStatus::Active,
+            returned: None,
+            proposal_id: 1,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn ending_returns_the_unclaimed_tokens_and_unused_fees() {
+        let mut airdrop = airdrop();
+        // Two claims were not made: their fees and the one left for the return, less its fee.
+        assert_eq!(end(&mut airdrop), Nat::from(700u64 + 20));
+        assert_eq!(airdrop.status, AirdropStatus::Expired);
+        assert_eq!(airdrop.fee_reserve, Nat::from(0u64));
+    }
+
+    #[test]
+    fn a_higher_ledger_fee_is_topped_up() {
+        let mut airdrop = airdrop();
+        assert_eq!(reprice(&mut airdrop, &Nat::from(25u64)), Nat::from(45u64));
+        assert_eq!(airdrop.fee_reserve, Nat::from(75u64));
+
+        // A lower fee leaves the surplus in the reserve until the airdrop ends.
+        assert_eq!(reprice(&mut airdrop, &Nat::from(5u64)), Nat::from(0u64));
+        assert_eq!(end(&mut airdrop), Nat::from(700u64 + 70));
+    }
+}
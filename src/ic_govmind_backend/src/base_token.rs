@@ -0,0 +1,384 @@
+use evm_rpc_types::{Hex32, TransactionReceipt};
+use ic_canister_log::log;
+use ic_govmind_types::{
+    chain::{EvmTransaction, EvmTxStatus, TokenConfig, TokenStandard},
+    dao::{
+        BaseToken, CreateEvmBaseTokenArg, Dao, EvmTokenDeployment, TokenDeploymentStatus,
+        TokenLocation,
+    },
+};
+
+use crate::{
+    chain::ethereum::keccak256, distribution::validate_recipients, emission::validate_schedule,
+    ic_log::INFO, store, timer::setup_token_distribution_timer, utils::current_time_secs,
+    wallet::WalletBlockchainConfig,
+};
+
+/// Sends the deployment transaction of an ERC-20 base token. The token becomes the DAO's base
+/// token once the transaction is mined, see `complete_deployment`. Returns the transaction hash.
+pub async fn deploy_evm_base_token(arg: CreateEvmBaseTokenArg) -> Result<String, String> {
+    store::state::get_dao_info().ok_or("DAO not initialized")?;
+    if let Some(model) = &arg.distribution_model {
+        validate_recipients(model, &arg.chain_type)?;
+        validate_schedule(model)?;
+    }
+    if let Some(deployment) = store::state::get_base_token_deployment() {
+        if deployment.status == TokenDeploymentStatus::Pending {
+            return Err(format!(
+                "Base token deployment {} is still pending",
+                deployment.tx_hash
+            ));
+        }
+    }
+
+    let chain_config = store::state::get_chain_config(&arg.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", arg.chain_type))?;
+    let chain_id = chain_config
+        .chain_id()
+        .ok_or_else(|| format!("No chain id configured for {:?}", arg.chain_type))?;
+    if chain_config.get_token_config(&arg.name).is_ok() {
+        return Err(format!(
+            "Token {} already exists on {:?}",
+            arg.name, arg.chain_type
+        ));
+    }
+    let factory_address = match arg.bytecode {
+        Some(_) => None,
+        None => Some(
+            arg.factory_address
+                .clone()
+                .unwrap_or_else(|| crate::ETH_CREATE_TOKEN_CONTRACT.to_string()),
+        ),
+    };
+
+    let tx_hash = WalletBlockchainConfig(chain_config)
+        .deploy_erc20_token(
+            &arg.wallet_address,
+            arg.name.clone(),
+            arg.symbol.clone(),
+            &arg.total_supply,
+            factory_address.as_deref(),
+            arg.bytecode.as_deref(),
+            arg.proposal_id,
+        )
+        .await?;
+    let tx = store::transactions::find_transaction_by_hash(&tx_hash)
+        .ok_or_else(|| format!("Transaction {} not found", tx_hash))?;
+
+    let now = current_time_secs();
+    store::state::set_base_token_deployment(EvmTokenDeployment {
+        chain_type: arg.chain_type,
+        chain_id,
+        name: arg.name,
+        symbol: arg.symbol,
+        decimals: arg.decimals,
+        total_supply: arg.total_supply,
+        distribution_model: arg.distribution_model,
+        owner: arg.wallet_address,
+        factory_address,
+        evm_tx_id: tx.id,
+        tx_hash: tx_hash.clone(),
+        status: TokenDeploymentStatus::Pending,
+        created_at: now,
+        updated_at: now,
+    });
+    store::state::save();
+    Ok(tx_hash)
+}
+
+/// Settles the pending base token deployment when `tx`, or a replacement of its transaction,
+/// reaches a final status. A mined deployment is registered as the DAO's base token and as an
+/// ERC-20 token of its chain.
+pub async fn complete_deployment(
+    tx: &EvmTransaction,
+    receipt: Option<&TransactionReceipt>,
+) -> Result<(), String> {
+    let Some(deployment) = store::state::get_base_token_deployment() else {
+        return Ok(());
+    };
+    if deployment.status != TokenDeploymentStatus::Pending
+        || !replacement_chain(deployment.evm_tx_id).contains(&tx.id)
+    {
+        return Ok(());
+    }
+
+    let status = match (&tx.status, receipt) {
+        (EvmTxStatus::Failed(reason), _) => {
+            TokenDeploymentStatus::Failed(format!("Deployment reverted: {}", reason))
+        }
+        (EvmTxStatus::Dropped, _) if tx.replaced_by.is_none() => {
+            TokenDeploymentStatus::Failed("Deployment transaction was dropped".to_string())
+        }
+        (EvmTxStatus::Confirmed, Some(receipt)) => {
+            let original = store::transactions::get_transaction(deployment.evm_tx_id);
+            if original.is_some_and(|original| original.data != tx.data) {
+                TokenDeploymentStatus::Failed("Deployment transaction was cancelled".to_string())
+            } else {
+                match deployed_address(&deployment, receipt) {
+                    Some(address) => TokenDeploymentStatus::Deployed(address),
+                    None => TokenDeploymentStatus::Failed(
+                        "No token address found in the deployment receipt".to_string(),
+                    ),
+                }
+            }
+        }
+        _ => return Ok(()),
+    };
+
+    let status = match status {
+        TokenDeploymentStatus::Deployed(address) => {
+            match register_base_token(&deployment, &address).await {
+                Ok(()) => TokenDeploymentStatus::Deployed(address),
+                Err(e) => TokenDeploymentStatus::Failed(format!(
+                    "Token deployed at {} but not registered: {}",
+                    address, e
+                )),
+            }
+        }
+        status => status,
+    };
+    log!(
+        INFO,
+        "Base token deployment {} finished with status {:?}",
+        deployment.tx_hash,
+        status
+    );
+    store::state::set_base_token_deployment(EvmTokenDeployment {
+        tx_hash: tx.tx_hash.clone(),
+        status,
+        updated_at: current_time_secs(),
+        ..deployment
+    });
+    store::state::save();
+    Ok(())
+}
+
+/// The ids of a transaction and of the transactions that replaced it, in order.
+fn replacement_chain(tx_id: u64) -> Vec<u64> {
+    let mut ids = vec![tx_id];
+    let mut next = store::transactions::get_transaction(tx_id).and_then(|tx| tx.replaced_by);
+    while let Some(id) = next.filter(|id| !ids.contains(id)) {
+        ids.push(id);
+        next = store::transactions::get_transaction(id).and_then(|tx| tx.replaced_by);
+    }
+    ids
+}
+
+/// A direct deployment reports the contract in the receipt. A factory deployment is found by
+/// the initial mint: the first `Transfer` log from the zero address.
+fn deployed_address(
+    deployment: &EvmTokenDeployment,
+    receipt: &TransactionReceipt,
+) -> Option<String> {
+    if deployment.factory_address.is_none() {
+        return receipt
+            .contract_address
+            .as_ref()
+            .map(|address| address.to_string());
+    }
+
+    let transfer_topic = Hex32::from(keccak256(b"Transfer(address,address,uint256)"));
+    let zero_topic = Hex32::from([0u8; 32]);
+    receipt
+        .logs
+        .iter()
+        .find(|entry| {
+            entry.topics.first() == Some(&transfer_topic)
+                && entry.topics.get(1) == Some(&zero_topic)
+        })
+        .map(|entry| entry.address.to_string())
+}
+
+async fn register_base_token(
+    deployment: &EvmTokenDeployment,
+    contract_address: &str,
+) -> Result<(), String> {
+    let chain_config = store::state::get_chain_config(&deployment.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", deployment.chain_type))?;
+    // Factories may not honour the requested decimals, so trust the contract when it answers.
+    let decimals = match WalletBlockchainConfig(chain_config.clone())
+        .read_erc20_metadata(contract_address)
+        .await
+    {
+        Ok((_, decimals)) => decimals,
+        Err(e) => {
+            log!(
+                INFO,
+                "Failed to read metadata of {}: {}",
+                contract_address,
+                e
+            );
+            deployment.decimals
+        }
+    };
+
+    let old_dao = store::state::get_dao_info().ok_or("DAO not initialized")?;
+    store::state::update_org_info(Dao {
+        base_token: BaseToken {
+            name: deployment.name.clone(),
+            symbol: deployment.symbol.clone(),
+            decimals,
+            total_supply: deployment.total_supply.clone(),
+            distribution_model: deployment.distribution_model.clone(),
+            token_location: TokenLocation {
+                chain: deployment.chain_type.clone(),
+                canister_id: None,
+                contract_address: Some(contract_address.to_string()),
+            },
+            supply_cap: None,
+        },
+        ..old_dao
+    });
+
+    let token_cfg = TokenConfig {
+        token_name: deployment.name.clone(),
+        symbol: deployment.symbol.clone(),
+        contract_address: Some(contract_address.to_string()),
+        decimal: decimals,
+        chain_name: chain_config
+            .native_token()
+            .map(|token| token.chain_name.clone())
+            .unwrap_or_else(|| format!("{:?}", deployment.chain_type)),
+        standard: TokenStandard::ERC20,
+        ..Default::default()
+    };
+    store::state::update_chain_config(deployment.chain_type.clone(), |chain| {
+        chain.add_token_config(token_cfg);
+    })?;
+
+    if deployment.distribution_model.is_some() {
+        setup_token_distribution_timer();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Nat;
+    use evm_rpc_types::{Hex20, LogEntry};
+    use ic_govmind_types::dao::ChainType;
+
+    fn deployment(factory_address: Option<&str>) -> EvmTokenDeployment {
+        EvmTokenDeployment {
+            chain_type: ChainType::Ethereum,
+            chain_id: 1,
+            name: "Gov".to_string(),
+            symbol: "GOV".to_string(),
+            decimals: 18,
+            total_supply: Nat::from(1_000u64),
+            distribution_model: None,
+            owner: "0x00000000000000000000000000000000000000da".to_string(),
+            factory_address: factory_address.map(str::to_string),
+            evm_tx_id: 0,
+            tx_hash: String::new(),
+            status: TokenDeploymentStatus::Pending,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn log(address: u8, from: [u8; 32]) -> LogEntry {
+        LogEntry {
+            address: Hex20::from([address; 20]),
+            topics: vec![
+                Hex32::from(keccak256(b"Transfer(address,address,uint256)")),
+                Hex32::from(from),
+                Hex32::from([0x11; 32]),
+            ],
+            data: vec![].into(),
+            block_number: None,
+            transaction_hash: None,
+            transaction_index: None,
+            block_hash: None,
+            log_index: None,
+            removed: false,
+        }
+    }
+
+    fn receipt(contract_address: Option<Hex20>, logs: Vec<LogEntry>) -> TransactionReceipt {
+        TransactionReceipt {
+            block_hash: [0u8; 32].into(),
+            block_number: 1u64.into(),
+            effective_gas_price: 1u64.into(),
+            gas_used: 21_000u64.into(),
+            status: Some(1u64.into()),
+            transaction_hash: [0u8; 32].into(),
+            contract_address,
+            from: [0u8; 20].into(),
+            logs,
+            logs_bloom: [0u8; 256].into(),
+            to: None,
+            transaction_index: 0u64.into(),
+            tx_type: 2u8.into(),
+        }
+    }
+
+    fn transaction(replaced_by: Option<u64>) -> EvmTransaction {
+        EvmTransaction {
+            id: 0,
+            chain_type: ChainType::Ethereum,
+            chain_id: 1,
+            from: "0xa".to_string(),
+            to: None,
+            nonce: 0,
+            value: Nat::from(0u64),
+            raw_tx: String::new(),
+            tx_hash: format!("0x{:064x}", replaced_by.unwrap_or_default()),
+            proposal_id: None,
+            status: EvmTxStatus::Replaced,
+            created_at: 0,
+            updated_at: 0,
+            block_number: None,
+            gas_used: None,
+            effective_gas_price: None,
+            data: None,
+            gas_limit: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            replaced_by,
+        }
+    }
+
+    #[test]
+    fn direct_deployment_takes_the_receipt_contract_address() {
+        let contract = Hex20::from([0xcc; 20]);
+        assert_eq!(
+            deployed_address(&deployment(None), &receipt(Some(contract.clone()), vec![])),
+            Some(contract.to_string())
+        );
+        assert_eq!(
+            deployed_address(
+                &deployment(None),
+                &receipt(None, vec![log(0xee, [0u8; 32])])
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn factory_deployment_takes_the_initial_mint_emitter() {
+        let factory = deployment(Some("0x00000000000000000000000000000000000000fa"));
+        let logs = vec![log(0xaa, [0x22; 32]), log(0xee, [0u8; 32])];
+        assert_eq!(
+            deployed_address(&factory, &receipt(Some(Hex20::from([0xcc; 20])), logs)),
+            Some(Hex20::from([0xee; 20]).to_string())
+        );
+        assert_eq!(
+            deployed_address(&factory, &receipt(None, vec![log(0xaa, [0x22; 32])])),
+            None
+        );
+    }
+
+    #[test]
+    fn replacement_chain_follows_replacements_and_stops_on_cycles() {
+        let first = store::transactions::add_transaction(transaction(None));
+        let second = store::transactions::add_transaction(transaction(Some(first + 2)));
+        let third = store::transactions::add_transaction(transaction(Some(second)));
+        assert_eq!(third, first + 2);
+
+        assert_eq!(replacement_chain(first), vec![first]);
+        assert_eq!(replacement_chain(second), vec![second, third]);
+        assert_eq!(replacement_chain(999), vec![999]);
+    }
+}
@@ -0,0 +1,351 @@
+use candid::{Nat, Principal};
+use ic_govmind_types::{
+    chain::{EvmTransaction, EvmTxStatus, TokenStandard},
+    cketh::{
+        BridgeDirection, BridgeOperation, BridgeStatus, MinterInfo, RetrieveEthStatus,
+        TxFinalizedStatus, WithdrawErc20Arg, WithdrawalArg,
+    },
+    dao::ChainType,
+    nat_to_u64,
+};
+use icrc_ledger_types::icrc1::account::Account;
+
+use crate::{
+    services::{cketh_minter::CkEthMinter, token_icrc1::TokenICRC1},
+    store,
+    types::{BridgeDepositArg, BridgeWithdrawArg},
+    utils::current_time_secs,
+    wallet::WalletBlockchainConfig,
+};
+
+/// Deposits ETH or an ERC-20 token from the DAO's EVM address into the ckETH helper contract,
+/// crediting the DAO canister's principal. Returns the id of the tracked bridge operation.
+pub async fn deposit_to_ic(arg: BridgeDepositArg) -> Result<u64, String> {
+    let config = store::bridge::get_cketh_config()?;
+    let chain_config = store::state::get_chain_config(&arg.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", arg.chain_type))?;
+
+    let tx_hashes = WalletBlockchainConfig(chain_config)
+        .deposit_to_cketh(
+            &arg.token_name,
+            &arg.wallet_address,
+            &config.helper_contract_address,
+            ic_cdk::api::canister_self(),
+            &arg.amount,
+            Some(arg.proposal_id),
+        )
+        .await?;
+
+    let evm_tx_ids = tx_hashes
+        .iter()
+        .filter_map(|tx_hash| store::transactions::find_transaction_by_hash(tx_hash))
+        .map(|tx| tx.id)
+        .collect();
+
+    let now = current_time_secs();
+    Ok(store::bridge::add_operation(BridgeOperation {
+        id: 0,
+        direction: BridgeDirection::ToIc,
+        chain_type: arg.chain_type,
+        token_name: arg.token_name,
+        amount: arg.amount,
+        evm_address: arg.wallet_address,
+        proposal_id: arg.proposal_id,
+        evm_tx_ids,
+        ledger_block_index: None,
+        eth_tx_hash: tx_hashes.last().cloned(),
+        status: BridgeStatus::Pending,
+        created_at: now,
+        updated_at: now,
+    }))
+}
+
+/// Withdraws ckETH or a ckERC20 token held by the DAO canister to an Ethereum address through
+/// the minter. Returns the id of the tracked bridge operation.
+pub async fn withdraw_to_evm(arg: BridgeWithdrawArg) -> Result<u64, String> {
+    let config = store::bridge::get_cketh_config()?;
+    let icp_chain = store::state::get_chain_config(&ChainType::InternetComputer)
+        .ok_or("Chain config not found for InternetComputer")?;
+    let token = icp_chain.get_token_config(&arg.token_name)?;
+    if !matches!(token.standard, TokenStandard::ICRC1 | TokenStandard::ICRC2) {
+        return Err(format!("Token {} is not an ICRC token", arg.token_name));
+    }
+    let ledger_id = token
+        .contract_address
+        .as_deref()
+        .ok_or("Invalid token ledger id")
+        .and_then(|id| Principal::from_text(id).map_err(|_| "Invalid token ledger id"))?;
+
+    let minter = CkEthMinter::new(config.minter_id);
+    let block_index = if ledger_id == config.cketh_ledger_id {
+        approve_minter(ledger_id, config.minter_id, arg.amount.clone()).await?;
+
+        let withdrawal = WithdrawalArg {
+            recipient: arg.recipient.clone(),
+            amount: arg.amount.clone(),
+            from_subaccount: None,
+        };
+        match minter.withdraw_eth(withdrawal).await? {
+            Ok(request) => request.block_index,
+            Err(e) => return Err(format!("ckETH withdrawal rejected: {:?}", e)),
+        }
+    } else {
+        // The minter burns ckETH from the same account to pay for the Ethereum transaction.
+        let max_cketh_fee = arg
+            .max_cketh_fee
+            .clone()
+            .ok_or("max_cketh_fee is required for ckERC20 withdrawals")?;
+        approve_minter(config.cketh_ledger_id, config.minter_id, max_cketh_fee).await?;
+        approve_minter(ledger_id, config.minter_id, arg.amount.clone()).await?;
+
+        let withdrawal = WithdrawErc20Arg {
+            amount: arg.amount.clone(),
+            ckerc20_ledger_id: ledger_id,
+            recipient: arg.recipient.clone(),
+            from_cketh_subaccount: None,
+            from_ckerc20_subaccount: None,
+        };
+        match minter.withdraw_erc20(withdrawal).await? {
+            Ok(request) => request.cketh_block_index,
+            Err(e) => return Err(format!("ckERC20 withdrawal rejected: {:?}", e)),
+        }
+    };
+
+    let now = current_time_secs();
+    Ok(store::bridge::add_operation(BridgeOperation {
+        id: 0,
+        direction: BridgeDirection::ToEvm,
+        chain_type: store::state::get_env().get_eth_chain_type(),
+        token_name: arg.token_name,
+        amount: arg.amount,
+        evm_address: arg.recipient,
+        proposal_id: arg.proposal_id,
+        evm_tx_ids: vec![],
+        ledger_block_index: Some(block_index),
+        eth_tx_hash: None,
+        status: BridgeStatus::WithdrawalQueued,
+        created_at: now,
+        updated_at: now,
+    }))
+}
+
+async fn approve_minter(
+    ledger_id: Principal,
+    minter_id: Principal,
+    amount: Nat,
+) -> Result<(), String> {
+    let ledger = TokenICRC1 {
+        principal: ledger_id,
+    };
+    let spender = Account {
+        owner: minter_id,
+        subaccount: None,
+    };
+
+    match ledger
+        .icrc2_approve(None, spender, amount, None, None, None, None, None)
+        .await?
+    {
+        (Ok(_),) => Ok(()),
+        (Err(e),) => Err(format!(
+            "Failed to approve the minter on {}: {:?}",
+            ledger_id, e
+        )),
+    }
+}
+
+/// Advances a bridge operation using the Ethereum receipts and the minter's view of it.
+pub async fn check_bridge_operation(op: BridgeOperation) -> Result<BridgeStatus, String> {
+    let (status, eth_tx_hash) = match op.direction {
+        BridgeDirection::ToIc => check_deposit(&op).await?,
+        BridgeDirection::ToEvm => check_withdrawal(&op).await?,
+    };
+
+    if status != op.status || eth_tx_hash != op.eth_tx_hash {
+        store::bridge::update_operation(op.id, |stored| {
+            stored.status = status.clone();
+            stored.eth_tx_hash = eth_tx_hash;
+        })?;
+    }
+
+    Ok(status)
+}
+
+async fn check_deposit(op: &BridgeOperation) -> Result<(BridgeStatus, Option<String>), String> {
+    let mut txs: Vec<EvmTransaction> = vec![];
+    for id in &op.evm_tx_ids {
+        let Some(original) = store::transactions::get_transaction(*id) else {
+            continue;
+        };
+        let tx = latest_replacement(original.clone());
+        if tx.to != original.to {
+            return Ok((
+                BridgeStatus::Failed(format!("Transaction {} was cancelled", original.tx_hash)),
+                op.eth_tx_hash.clone(),
+            ));
+        }
+        txs.push(tx);
+    }
+
+    let Some(deposit_tx) = txs.last() else {
+        return Ok((
+            BridgeStatus::Failed("Deposit transaction not found".to_string()),
+            op.eth_tx_hash.clone(),
+        ));
+    };
+    let deposit_hash = Some(deposit_tx.tx_hash.clone());
+
+    if let Some(tx) = txs
+        .iter()
+        .find(|tx| matches!(tx.status, EvmTxStatus::Failed(_) | EvmTxStatus::Dropped))
+    {
+        return Ok((
+            BridgeStatus::Failed(format!("Transaction {} is {:?}", tx.tx_hash, tx.status)),
+            deposit_hash,
+        ));
+    }
+
+    let block_number = match (&deposit_tx.status, deposit_tx.block_number) {
+        (EvmTxStatus::Confirmed, Some(block_number)) => block_number,
+        _ => return Ok((BridgeStatus::Pending, deposit_hash)),
+    };
+
+    let config = store::bridge::get_cketh_config()?;
+    let info = CkEthMinter::new(config.minter_id).get_minter_info().await?;
+    // An ERC-20 deposit is preceded by the approval of the helper contract.
+    if deposit_scraped(&info, txs.len() > 1, block_number) {
+        Ok((BridgeStatus::Completed, deposit_hash))
+    } else {
+        Ok((BridgeStatus::AwaitingMinter, deposit_hash))
+    }
+}
+
+/// Whether the minter has scraped the logs of `block_number`, which is when it credits a deposit.
+fn deposit_scraped(info: &MinterInfo, erc20: bool, block_number: u64) -> bool {
+    let scraped = info
+        .last_deposit_with_subaccount_scraped_block_number
+        .clone()
+        .or(if erc20 {
+            info.last_erc20_scraped_block_number.clone()
+        } else {
+            info.last_eth_scraped_block_number.clone()
+        });
+
+    matches!(scraped, Some(scraped) if nat_to_u64(&scraped) >= block_number)
+}
+
+async fn check_withdrawal(op: &BridgeOperation) -> Result<(BridgeStatus, Option<String>), String> {
+    let block_index = op
+        .ledger_block_index
+        .as_ref()
+        .ok_or("Withdrawal has no ledger block index")?;
+
+    let config = store::bridge::get_cketh_config()?;
+    let status = CkEthMinter::new(config.minter_id)
+        .retrieve_eth_status(nat_to_u64(block_index))
+        .await?;
+
+    Ok(withdrawal_status(status))
+}
+
+fn withdrawal_status(status: RetrieveEthStatus) -> (BridgeStatus, Option<String>) {
+    match status {
+        RetrieveEthStatus::NotFound | RetrieveEthStatus::Pending | RetrieveEthStatus::TxCreated => {
+            (BridgeStatus::WithdrawalQueued, None)
+        }
+        RetrieveEthStatus::TxSent(tx) => (BridgeStatus::WithdrawalSent, Some(tx.transaction_hash)),
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Success {
+            transaction_hash, ..
+        }) => (BridgeStatus::Completed, Some(transaction_hash)),
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::PendingReimbursement(tx)) => {
+            (BridgeStatus::WithdrawalSent, Some(tx.transaction_hash))
+        }
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Reimbursed {
+            transaction_hash, ..
+        }) => (BridgeStatus::Reimbursed, Some(transaction_hash)),
+    }
+}
+
+/// Follows speed-up and cancel replacements to the transaction that was or may still be mined.
+pub fn latest_replacement(mut tx: EvmTransaction) -> EvmTransaction {
+    while matches!(tx.status, EvmTxStatus::Replaced | EvmTxStatus::Dropped) {
+        match tx
+            .replaced_by
+            .and_then(store::transactions::get_transaction)
+        {
+            Some(next) => tx = next,
+            None => break,
+        }
+    }
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_govmind_types::cketh::EthTransaction;
+
+    fn minter_info(
+        with_subaccount: Option<u64>,
+        eth: Option<u64>,
+        erc20: Option<u64>,
+    ) -> MinterInfo {
+        MinterInfo {
+            last_deposit_with_subaccount_scraped_block_number: with_subaccount.map(Nat::from),
+            last_eth_scraped_block_number: eth.map(Nat::from),
+            last_erc20_scraped_block_number: erc20.map(Nat::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn deposits_are_credited_once_their_block_is_scraped() {
+        assert!(deposit_scraped(
+            &minter_info(Some(100), None, None),
+            true,
+            100
+        ));
+        assert!(!deposit_scraped(
+            &minter_info(Some(99), Some(200), Some(200)),
+            false,
+            100
+        ));
+
+        // Without the subaccount helper, ETH and ERC-20 deposits are scraped separately.
+        let info = minter_info(None, Some(100), Some(90));
+        assert!(deposit_scraped(&info, false, 100));
+        assert!(!deposit_scraped(&info, true, 100));
+        assert!(!deposit_scraped(&minter_info(None, None, None), false, 0));
+    }
+
+    #[test]
+    fn withdrawal_status_follows_the_minter() {
+        let tx = |hash: &str| EthTransaction {
+            transaction_hash: hash.to_string(),
+        };
+        assert_eq!(
+            withdrawal_status(RetrieveEthStatus::TxCreated),
+            (BridgeStatus::WithdrawalQueued, None)
+        );
+        assert_eq!(
+            withdrawal_status(RetrieveEthStatus::TxSent(tx("0x1"))),
+            (BridgeStatus::WithdrawalSent, Some("0x1".to_string()))
+        );
+        assert_eq!(
+            withdrawal_status(RetrieveEthStatus::TxFinalized(
+                TxFinalizedStatus::PendingReimbursement(tx("0x2"))
+            )),
+            (BridgeStatus::WithdrawalSent, Some("0x2".to_string()))
+        );
+        assert_eq!(
+            withdrawal_status(RetrieveEthStatus::TxFinalized(
+                TxFinalizedStatus::Reimbursed {
+                    transaction_hash: "0x3".to_string(),
+                    reimbursed_amount: Nat::from(1u64),
+                    reimbursed_in_block: Nat::from(2u64),
+                }
+            )),
+            (BridgeStatus::Reimbursed, Some("0x3".to_string()))
+        );
+    }
+}
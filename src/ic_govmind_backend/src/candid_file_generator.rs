@@ -3,20 +3,31 @@ use crate::{
     init::CanisterArgs,
     store::State,
     types::{
-        Addresses, BalanceResult, QueryBalanceArg, StatusRequest, StatusResponse, TokenTransferArg,
+        Addresses, BalanceResult, BridgeDepositArg, BridgeWithdrawArg, Erc20Approval,
+        Erc20ApproveArg, Erc20RevokeArg, EvmContractCallArg, EvmNonceReport, ProposalAction,
+        QueryBalanceArg, StatusRequest, StatusResponse, TokenTransferArg,
     },
 };
-use candid::{export_service, Principal};
+use candid::{export_service, Nat, Principal};
 use ic_cdk::query;
 use ic_govmind_types::{
-    chain::BlockchainConfig,
+    chain::{
+        BlockchainConfig, ChainConfigChange, EvmDeposit, EvmTransaction, EvmTxPreview, FeeConfig,
+        RpcConfig, RpcProviderHealth, TokenConfig,
+    },
+    cketh::{BridgeOperation, CkEthConfig},
     dao::{
-        ChainType, CommitteeArg, Committee, CreateBaseTokenArg, Dao, DistributionRecord, Proposal,
-        ProposalStatus,
+        Airdrop, AirdropArg, ChainType, Committee, CommitteeArg, CreateBaseTokenArg,
+        CreateEvmBaseTokenArg, Dao, DistributionPayout, DistributionRecipient, DistributionRecord,
+        DistributionRecordFilter, DistributionTotal, EvmTokenDeployment, MintTokensArg, Proposal,
+        ProposalStatus, RewardEvent, StakePositionStatus, StakeReconciliation, StakingConfig,
+        SupplyChange, SupplyProjection, SupplyReconciliation, VestingGrantArg, VestingGrantStatus,
+        VoteChoice, VotingRewardsConfig,
     },
-    icrc::CreateCanisterArg,
+    icrc::{CreateCanisterArg, LedgerUpgrade, LedgerUpgradeArg},
+    price::{PriceFeedConfig, TokenPrice, TreasuryValuation},
 };
-use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::{icrc::generic_metadata_value::MetadataValue, icrc1::account::Account};
 
 #[query(name = "__get_candid_interface_tmp_hack")]
 fn export_candid() -> String {
@@ -0,0 +1,327 @@
+//! EVM transaction encoding and signing with the canister's threshold ECDSA key.
+
+use ethers_core::types::{H160, H256, U256};
+use libsecp256k1::{Message, RecoveryId, Signature};
+
+use crate::{chain::ethereum::keccak256, signer::ecdsa::ecdsa_sign, store};
+
+/// An address and storage slots a transaction declares it will access (EIP-2930).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListItem {
+    pub address: H160,
+    pub storage_keys: Vec<H256>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvmTxFees {
+    /// Pre-EIP-2718 transaction with EIP-155 replay protection.
+    Legacy { gas_price: u128 },
+    /// Type 1 transaction.
+    Eip2930 {
+        gas_price: u128,
+        access_list: Vec<AccessListItem>,
+    },
+    /// Type 2 transaction.
+    Eip1559 {
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+        access_list: Vec<AccessListItem>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmTxRequest {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub gas_limit: u128,
+    pub to: Option<H160>, // None deploys a contract
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub fees: EvmTxFees,
+}
+
+impl EvmTxRequest {
+    /// The payload whose hash is signed.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut fields = self.fields();
+        if let EvmTxFees::Legacy { .. } = self.fees {
+            // EIP-155: the chain id takes the place of `v`, with empty `r` and `s`.
+            rlp_uint(&mut fields, U256::from(self.chain_id));
+            rlp_bytes(&mut fields, &[]);
+            rlp_bytes(&mut fields, &[]);
+        }
+        self.envelope(&fields)
+    }
+
+    pub fn signing_hash(&self) -> [u8; 32] {
+        keccak256(&self.signing_payload())
+    }
+
+    /// The raw transaction, ready for `eth_sendRawTransaction`.
+    pub fn encode_signed(&self, recovery_id: u8, r: &[u8; 32], s: &[u8; 32]) -> Vec<u8> {
+        let mut fields = self.fields();
+        let v = match self.fees {
+            EvmTxFees::Legacy { .. } => self.chain_id * 2 + 35 + recovery_id as u64,
+            _ => recovery_id as u64,
+        };
+        rlp_uint(&mut fields, U256::from(v));
+        rlp_uint(&mut fields, U256::from_big_endian(r));
+        rlp_uint(&mut fields, U256::from_big_endian(s));
+        self.envelope(&fields)
+    }
+
+    /// Signs the transaction with the canister's ECDSA key and returns the raw transaction.
+    pub async fn sign(&self) -> Result<Vec<u8>, String> {
+        let hash = self.signing_hash();
+        let derivation_path = store::state::with(|s| s.derivation_path.clone());
+        let signature = ecdsa_sign(hash.to_vec(), derivation_path).await?;
+        let public_key = store::state::get_ecdsa_public_key().public_key;
+
+        let (recovery_id, r, s) = recoverable_signature(&hash, &signature, &public_key)?;
+        Ok(self.encode_signed(recovery_id, &r, &s))
+    }
+
+    /// The RLP items shared by the signing payload and the signed transaction.
+    fn fields(&self) -> Vec<u8> {
+        let mut out = vec![];
+        match &self.fees {
+            EvmTxFees::Legacy { gas_price } => {
+                rlp_uint(&mut out, U256::from(self.nonce));
+                rlp_uint(&mut out, U256::from(*gas_price));
+                self.push_call_fields(&mut out);
+            }
+            EvmTxFees::Eip2930 {
+                gas_price,
+                access_list,
+            } => {
+                rlp_uint(&mut out, U256::from(self.chain_id));
+                rlp_uint(&mut out, U256::from(self.nonce));
+                rlp_uint(&mut out, U256::from(*gas_price));
+                self.push_call_fields(&mut out);
+                rlp_access_list(&mut out, access_list);
+            }
+            EvmTxFees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                access_list,
+            } => {
+                rlp_uint(&mut out, U256::from(self.chain_id));
+                rlp_uint(&mut out, U256::from(self.nonce));
+                rlp_uint(&mut out, U256::from(*max_priority_fee_per_gas));
+                rlp_uint(&mut out, U256::from(*max_fee_per_gas));
+                self.push_call_fields(&mut out);
+                rlp_access_list(&mut out, access_list);
+            }
+        }
+        out
+    }
+
+    fn push_call_fields(&self, out: &mut Vec<u8>) {
+        rlp_uint(out, U256::from(self.gas_limit));
+        match &self.to {
+            Some(to) => rlp_bytes(out, to.as_bytes()),
+            None => rlp_bytes(out, &[]),
+        }
+        rlp_uint(out, self.value);
+        rlp_bytes(out, &self.data);
+    }
+
+    /// Wraps `fields` in a list, prefixed with the type byte of typed transactions (EIP-2718).
+    fn envelope(&self, fields: &[u8]) -> Vec<u8> {
+        let mut out = match self.fees {
+            EvmTxFees::Legacy { .. } => vec![],
+            EvmTxFees::Eip2930 { .. } => vec![0x01],
+            EvmTxFees::Eip1559 { .. } => vec![0x02],
+        };
+        rlp_list(&mut out, fields);
+        out
+    }
+}
+
+/// Turns a 64-byte `r || s` signature into its low-s form and finds the recovery id under which
+/// it recovers to `public_key` (SEC1, compressed or not).
+fn recoverable_signature(
+    hash: &[u8; 32],
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<(u8, [u8; 32], [u8; 32]), String> {
+    let mut signature = Signature::parse_standard_slice(signature)
+        .map_err(|e| format!("Invalid ECDSA signature: {:?}", e))?;
+    // Nodes reject high-s signatures (EIP-2).
+    signature.normalize_s();
+
+    let message = Message::parse(hash);
+    for id in 0..2u8 {
+        let recovery_id = RecoveryId::parse(id).map_err(|e| format!("{:?}", e))?;
+        let Ok(recovered) = libsecp256k1::recover(&message, &signature, &recovery_id) else {
+            continue;
+        };
+        if recovered.serialize_compressed().as_slice() == public_key
+            || recovered.serialize().as_slice() == public_key
+        {
+            return Ok((id, signature.r.b32(), signature.s.b32()));
+        }
+    }
+    Err("Signature does not recover to the canister's public key".to_string())
+}
+
+fn rlp_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    if let [byte] = bytes {
+        if *byte < 0x80 {
+            out.push(*byte);
+            return;
+        }
+    }
+    rlp_length(out, bytes.len(), 0x80);
+    out.extend_from_slice(bytes);
+}
+
+/// Integers are big-endian without leading zeros, so zero is the empty string.
+fn rlp_uint(out: &mut Vec<u8>, value: U256) {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    rlp_bytes(out, &bytes[start..]);
+}
+
+fn rlp_list(out: &mut Vec<u8>, payload: &[u8]) {
+    rlp_length(out, payload.len(), 0xc0);
+    out.extend_from_slice(payload);
+}
+
+fn rlp_length(out: &mut Vec<u8>, len: usize, offset: u8) {
+    if len < 56 {
+        out.push(offset + len as u8);
+    } else {
+        let bytes = (len as u64).to_be_bytes();
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        out.push(offset + 55 + (bytes.len() - start) as u8);
+        out.extend_from_slice(&bytes[start..]);
+    }
+}
+
+fn rlp_access_list(out: &mut Vec<u8>, access_list: &[AccessListItem]) {
+    let mut items = vec![];
+    for item in access_list {
+        let mut keys = vec![];
+        for key in &item.storage_keys {
+            rlp_bytes(&mut keys, key.as_bytes());
+        }
+        let mut fields = vec![];
+        rlp_bytes(&mut fields, item.address.as_bytes());
+        rlp_list(&mut fields, &keys);
+        rlp_list(&mut items, &fields);
+    }
+    rlp_list(out, &items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // The example transaction of EIP-155.
+    fn eip155_example() -> EvmTxRequest {
+        EvmTxRequest {
+            chain_id: 1,
+            nonce: 9,
+            gas_limit: 21_000,
+            to: Some(H160::from_str("0x3535353535353535353535353535353535353535").unwrap()),
+            value: U256::from(1_000_000_000_000_000_000u128),
+            data: vec![],
+            fees: EvmTxFees::Legacy {
+                gas_price: 20_000_000_000,
+            },
+        }
+    }
+
+    #[test]
+    fn rlp_encodes_strings_integers_and_lists() {
+        let encode = |f: &dyn Fn(&mut Vec<u8>)| {
+            let mut out = vec![];
+            f(&mut out);
+            out
+        };
+        assert_eq!(encode(&|out| rlp_bytes(out, b"dog")), b"\x83dog");
+        assert_eq!(encode(&|out| rlp_bytes(out, &[0x0f])), [0x0f]);
+        assert_eq!(encode(&|out| rlp_bytes(out, &[0x80])), [0x81, 0x80]);
+        assert_eq!(encode(&|out| rlp_uint(out, U256::zero())), [0x80]);
+        assert_eq!(
+            encode(&|out| rlp_uint(out, U256::from(1024))),
+            [0x82, 0x04, 0x00]
+        );
+        assert_eq!(encode(&|out| rlp_list(out, &[])), [0xc0]);
+
+        let long = [b'a'; 56];
+        let encoded = encode(&|out| rlp_bytes(out, &long));
+        assert_eq!(encoded[..2], [0xb8, 56]);
+        assert_eq!(encoded.len(), 58);
+    }
+
+    #[test]
+    fn legacy_signing_hash_matches_eip155_example() {
+        let tx = eip155_example();
+        assert_eq!(
+            hex::encode(tx.signing_payload()),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+        );
+        assert_eq!(
+            hex::encode(tx.signing_hash()),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+    }
+
+    #[test]
+    fn legacy_signed_encoding_matches_eip155_example() {
+        let r: [u8; 32] =
+            hex::decode("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let s: [u8; 32] =
+            hex::decode("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(
+            hex::encode(eip155_example().encode_signed(0, &r, &s)),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000\
+             8025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d899\
+             7f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+
+    #[test]
+    fn typed_transactions_are_wrapped_in_their_envelopes() {
+        let mut tx = eip155_example();
+        tx.to = None;
+        tx.fees = EvmTxFees::Eip1559 {
+            max_fee_per_gas: 2,
+            max_priority_fee_per_gas: 1,
+            access_list: vec![],
+        };
+        let payload = tx.signing_payload();
+        assert_eq!(payload[0], 0x02);
+        // chain id, nonce, priority fee, max fee, gas limit, empty `to`.
+        assert_eq!(
+            payload[2..10],
+            [0x01, 0x09, 0x01, 0x02, 0x82, 0x52, 0x08, 0x80]
+        );
+
+        tx.fees = EvmTxFees::Eip2930 {
+            gas_price: 1,
+            access_list: vec![AccessListItem {
+                address: H160::zero(),
+                storage_keys: vec![H256::zero()],
+            }],
+        };
+        let payload = tx.signing_payload();
+        assert_eq!(payload[0], 0x01);
+        // [[address, [key]]]
+        let mut access_list = vec![0xf8, 0x38, 0xf7, 0x94];
+        access_list.extend([0u8; 20]);
+        access_list.extend([0xe1, 0xa0]);
+        access_list.extend([0u8; 32]);
+        assert!(payload.ends_with(&access_list));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod bitcoin;
 pub mod ethereum;
+pub mod evm_tx;
@@ -0,0 +1,257 @@
+use candid::Nat;
+use ethers_core::types::U256;
+use evm_rpc_types::{
+    BlockTag, GetLogsArgs, GetLogsRpcConfig, Hex20, Hex32, LogEntry, MultiRpcResult,
+};
+use ic_canister_log::log;
+use ic_govmind_types::{
+    chain::{BlockchainConfig, EvmDeposit, TokenStandard},
+    constants::{EVM_DEPOSIT_CONFIRMATIONS, EVM_DEPOSIT_MAX_BLOCK_RANGE, EVM_RPC_CANISTER_ID},
+    nat_to_u64,
+};
+use std::str::FromStr;
+
+use crate::{
+    chain::ethereum::{account_to_eth_address, keccak256},
+    ic_log::INFO,
+    services::{evm_abi::u256_to_nat, evm_providers, evm_service::EvmService},
+    store,
+    utils::current_time_secs,
+};
+
+/// Indexes ERC-20 `Transfer` logs to the DAO's EVM address for every supported token of each
+/// registered EVM chain. Only blocks at least `EVM_DEPOSIT_CONFIRMATIONS` deep are scanned, so
+/// indexed deposits survive reorgs. Returns the number of new deposits.
+pub async fn scan_deposits() -> Result<usize, String> {
+    let mut added = 0;
+    let mut errors = vec![];
+    for chain_config in store::state::list_evm_chain_configs() {
+        let chain_type = chain_config.chain_type.clone();
+        match scan_chain_deposits(chain_config).await {
+            Ok(count) => added += count,
+            Err(e) => errors.push(format!("{:?}: {}", chain_type, e)),
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(added),
+        false => Err(errors.join("; ")),
+    }
+}
+
+async fn scan_chain_deposits(chain_config: BlockchainConfig) -> Result<usize, String> {
+    let chain_type = chain_config.chain_type.clone();
+    let chain_id = chain_config
+        .chain_id()
+        .ok_or_else(|| format!("No chain id configured for {:?}", chain_type))?;
+
+    let tokens = erc20_tokens(&chain_config);
+    if tokens.is_empty() {
+        return Ok(0);
+    }
+
+    let head = evm_providers::block_number(&chain_config).await?;
+    let safe_head = head.saturating_sub(EVM_DEPOSIT_CONFIRMATIONS);
+
+    // A fresh cursor starts at the safe head; earlier blocks can be backfilled by moving it.
+    let Some(cursor) = store::deposits::get_cursor(chain_id) else {
+        store::deposits::set_cursor(chain_id, safe_head);
+        return Ok(0);
+    };
+    let Some((from_block, to_block)) = scan_range(cursor, safe_head) else {
+        return Ok(0);
+    };
+
+    let dao_address = account_to_eth_address()?;
+    let args = GetLogsArgs {
+        from_block: Some(BlockTag::Number(from_block.into())),
+        to_block: Some(BlockTag::Number(to_block.into())),
+        addresses: tokens.iter().map(|(_, address)| address.clone()).collect(),
+        topics: Some(vec![
+            vec![Hex32::from(keccak256(b"Transfer(address,address,uint256)"))],
+            vec![],
+            vec![address_topic(&dao_address)?],
+        ]),
+    };
+
+    let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
+    let result = evm_providers::call_multi(&chain_config, |rpc_services, rpc_config| async move {
+        let rpc_config = rpc_config.map(|config| GetLogsRpcConfig {
+            response_consensus: config.response_consensus,
+            ..Default::default()
+        });
+        evm_service
+            .eth_get_logs(&rpc_services, rpc_config, args)
+            .await
+    })
+    .await?;
+    let logs = match result {
+        MultiRpcResult::Consistent(Ok(logs)) => logs,
+        MultiRpcResult::Consistent(Err(e)) => {
+            return Err(format!("RPC error fetching logs: {:?}", e))
+        }
+        MultiRpcResult::Inconsistent(_) => {
+            return Err("Logs are inconsistent across services".to_string())
+        }
+    };
+
+    let now = current_time_secs();
+    let mut added = 0;
+    for entry in logs.iter().filter(|entry| !entry.removed) {
+        let Some(token_name) = tokens
+            .iter()
+            .find(|(_, address)| address == &entry.address)
+            .map(|(name, _)| name.clone())
+        else {
+            continue;
+        };
+
+        match decode_transfer(entry) {
+            Some(transfer) => {
+                let member_id = store::deposits::find_member_by_eth_address(&transfer.from);
+                let deposit = EvmDeposit {
+                    id: 0,
+                    chain_type: chain_type.clone(),
+                    chain_id,
+                    token_name,
+                    token_address: entry.address.to_string(),
+                    from: transfer.from,
+                    to: dao_address.clone(),
+                    amount: transfer.amount,
+                    tx_hash: transfer.tx_hash,
+                    log_index: transfer.log_index,
+                    block_number: transfer.block_number,
+                    member_id,
+                    detected_at: now,
+                };
+                if let Some(id) = store::deposits::add_deposit(deposit) {
+                    log!(INFO, "Indexed EVM deposit {}", id);
+                    added += 1;
+                }
+            }
+            None => log!(INFO, "Skipping malformed Transfer log: {:?}", entry),
+        }
+    }
+
+    store::deposits::set_cursor(chain_id, to_block);
+    store::state::save();
+    Ok(added)
+}
+
+/// The blocks after `cursor` to scan next, at most `EVM_DEPOSIT_MAX_BLOCK_RANGE` up to `safe_head`.
+fn scan_range(cursor: u64, safe_head: u64) -> Option<(u64, u64)> {
+    if cursor >= safe_head {
+        return None;
+    }
+    Some((
+        cursor + 1,
+        safe_head.min(cursor + EVM_DEPOSIT_MAX_BLOCK_RANGE),
+    ))
+}
+
+fn erc20_tokens(chain_config: &BlockchainConfig) -> Vec<(String, Hex20)> {
+    chain_config
+        .supported_tokens
+        .iter()
+        .filter(|token| matches!(token.standard, TokenStandard::ERC20))
+        .filter_map(|token| {
+            let address = Hex20::from_str(token.contract_address.as_deref()?).ok()?;
+            Some((token.token_name.clone(), address))
+        })
+        .collect()
+}
+
+/// An address as an indexed event argument: left-padded to 32 bytes.
+fn address_topic(address: &str) -> Result<Hex32, String> {
+    let address = Hex20::from_str(address)?;
+    let mut topic = [0u8; 32];
+    topic[12..].copy_from_slice(address.as_ref());
+    Ok(Hex32::from(topic))
+}
+
+struct Transfer {
+    from: String,
+    amount: Nat,
+    tx_hash: String,
+    log_index: u64,
+    block_number: u64,
+}
+
+fn decode_transfer(entry: &LogEntry) -> Option<Transfer> {
+    let from_topic = entry.topics.get(1)?;
+    let from = Hex20::from(<[u8; 20]>::try_from(&from_topic.as_array()[12..]).ok()?);
+    let data: &[u8] = entry.data.as_ref();
+    if data.len() != 32 {
+        return None;
+    }
+
+    Some(Transfer {
+        from: from.to_string(),
+        amount: u256_to_nat(U256::from_big_endian(data)),
+        tx_hash: entry.transaction_hash.as_ref()?.to_string(),
+        log_index: nat_to_u64(entry.log_index.as_ref()?.as_ref()),
+        block_number: nat_to_u64(entry.block_number.as_ref()?.as_ref()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAO_ADDRESS: &str = "0x00000000000000000000000000000000000000da";
+    const SENDER: &str = "0x1111111111111111111111111111111111111111";
+
+    fn transfer_log(data: Vec<u8>) -> LogEntry {
+        LogEntry {
+            address: Hex20::from([0xee; 20]),
+            topics: vec![
+                Hex32::from(keccak256(b"Transfer(address,address,uint256)")),
+                address_topic(SENDER).unwrap(),
+                address_topic(DAO_ADDRESS).unwrap(),
+            ],
+            data: data.into(),
+            block_number: Some(42u64.into()),
+            transaction_hash: Some(Hex32::from([0xab; 32])),
+            transaction_index: Some(0u64.into()),
+            block_hash: None,
+            log_index: Some(3u64.into()),
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn address_topic_left_pads_the_address() {
+        let topic = address_topic(DAO_ADDRESS).unwrap();
+        assert_eq!(topic.as_array()[..12], [0u8; 12]);
+        assert_eq!(topic.as_array()[31], 0xda);
+        assert!(address_topic("0x1234").is_err());
+    }
+
+    #[test]
+    fn decodes_a_transfer_log() {
+        let mut amount = [0u8; 32];
+        amount[30..].copy_from_slice(&[0x01, 0x00]);
+        let transfer = decode_transfer(&transfer_log(amount.to_vec())).unwrap();
+
+        assert_eq!(transfer.from, SENDER);
+        assert_eq!(transfer.amount, Nat::from(256u64));
+        assert_eq!(transfer.tx_hash, Hex32::from([0xab; 32]).to_string());
+        assert_eq!((transfer.block_number, transfer.log_index), (42, 3));
+    }
+
+    #[test]
+    fn rejects_a_transfer_log_without_a_single_word_of_data() {
+        assert!(decode_transfer(&transfer_log(vec![0u8; 31])).is_none());
+        assert!(decode_transfer(&transfer_log(vec![0u8; 64])).is_none());
+    }
+
+    #[test]
+    fn scan_range_stops_at_the_safe_head() {
+        assert_eq!(scan_range(100, 100), None);
+        assert_eq!(scan_range(100, 150), Some((101, 150)));
+        assert_eq!(
+            scan_range(100, u64::MAX),
+            Some((101, 100 + EVM_DEPOSIT_MAX_BLOCK_RANGE))
+        );
+    }
+}
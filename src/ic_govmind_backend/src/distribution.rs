@@ -0,0 +1,1023 @@
+use candid::{Nat, Principal};
+use ethers_core::types::H160;
+use ic_canister_log::log;
+use ic_govmind_types::{
+    chain::{BlockchainConfig, EvmTxStatus, TokenStandard},
+    dao::{
+        ChainType, DistributionModel, DistributionPayout, DistributionRecipient,
+        DistributionRecord, DistributionStatus, DistributionType, UnlockScheduleItem,
+        HOLDER_SUBACCOUNT,
+    },
+};
+use icrc_ledger_types::icrc1::{
+    account::{Account, Subaccount},
+    transfer::{Memo, TransferError},
+};
+use serde_bytes::ByteBuf;
+use std::str::FromStr;
+
+use crate::{
+    airdrop,
+    bridge::latest_replacement,
+    chain::ethereum::account_to_eth_address,
+    emission::{self, DEFAULT_EMISSION_PERIOD_SECS},
+    history,
+    ic_log::INFO,
+    rewards,
+    services::{evm_abi::generate_erc20_transfer_data, token_icrc1::TokenICRC1},
+    store, timer,
+    utils::{current_time_secs, sha256},
+    vesting,
+    wallet::WalletBlockchainConfig,
+};
+
+const INITIAL_PREFIX: &str = "initial:";
+
+/// Plans the payouts that came due under the base token's distribution model, then pays every
+/// payout that was not completed yet. Progress is saved after each transfer, so an upgrade or a
+/// trap never pays a recipient twice. `token_canister_id` is the base token's ledger, if it lives
+/// on the Internet Computer.
+pub async fn distribute_tokens(token_canister_id: Option<Principal>) {
+    plan_payouts(ic_cdk::api::time());
+    vesting::plan_releases(current_time_secs());
+    airdrop::plan_returns(current_time_secs());
+    rewards::plan_rewards(current_time_secs());
+    store::state::save();
+
+    let token_service = token_canister_id.map(|principal| TokenICRC1 { principal });
+    for payout in store::distribution::list_pending_payouts() {
+        if let Err(e) = pay(token_service.as_ref(), &payout.id).await {
+            log!(
+                INFO,
+                "Failed to record distribution payout {}: {}",
+                payout.id,
+                e
+            );
+        }
+        store::state::save();
+    }
+}
+
+/// Turns the due parts of the distribution model into payouts. Emission rounds are recorded in
+/// the model as they are planned, and stop once the supply cap is reached; initial and unlock
+/// payouts are marked done once paid.
+fn plan_payouts(now: u64) {
+    let Some(total_supply) = store::state::get_dao_info().map(|dao| dao.base_token.total_supply)
+    else {
+        return;
+    };
+    let planned = store::distribution::update_model(|model| due_payouts(model, &total_supply, now));
+
+    for (id, distribution_type, recipient, amount) in planned.unwrap_or_default() {
+        queue_payout(id, distribution_type, recipient, amount, None);
+    }
+}
+
+/// The payouts of `model` due at `now` (nanoseconds), as (id, type, recipient, amount).
+fn due_payouts(
+    model: &mut DistributionModel,
+    total_supply: &Nat,
+    now: u64,
+) -> Vec<(String, DistributionType, String, Nat)> {
+    let mut planned = vec![];
+
+    if model.initial_executed_at.is_none() {
+        for (addr, amount) in &model.initial_distribution {
+            planned.push((
+                format!("{}{}", INITIAL_PREFIX, addr),
+                DistributionType::Initial,
+                addr.clone(),
+                amount.clone(),
+            ));
+        }
+    }
+
+    if model.emission_rate.is_some() {
+        let period_ns = model
+            .emission_period
+            .unwrap_or(DEFAULT_EMISSION_PERIOD_SECS)
+            * 1_000_000_000;
+        let should_emit = match model.last_emission_time {
+            Some(last) => now.saturating_sub(last) >= period_ns,
+            None => true,
+        };
+        let emission = emission::next_round_emission(model, total_supply);
+        if should_emit && emission > 0u64 {
+            let round = model.emission_rounds.unwrap_or(0);
+            let (shares, _) = emission::split_emission(model, &emission);
+            for (addr, share) in shares {
+                planned.push((
+                    format!("emission:{}:{}", round, addr),
+                    DistributionType::Emission,
+                    addr,
+                    share,
+                ));
+            }
+            model.emission_rounds = Some(round + 1);
+            model.emitted_total = Some(model.emitted_total.clone().unwrap_or_default() + emission);
+            model.last_emission_time = Some(now);
+        }
+    }
+
+    let schedule = model.unlock_schedule.as_deref().unwrap_or_default();
+    for (item, id) in schedule.iter().zip(unlock_payout_ids(schedule)) {
+        if !item.executed && item.timestamp * 1_000_000_000 <= now {
+            planned.push((
+                id,
+                DistributionType::Scheduled,
+                item.addr.clone(),
+                item.amount.clone(),
+            ));
+        }
+    }
+
+    planned
+}
+
+/// Marks the initial distribution and the unlock items that the timer paid before payouts were
+/// persisted. It kept that progress on the heap only, so without this the first upgrade would
+/// plan those payouts again, under new memos the ledger cannot recognize as duplicates.
+pub fn migrate_legacy_progress() {
+    let pending = store::distribution::update_model(|model| {
+        model.initial_executed_at.is_none()
+            || model
+                .unlock_schedule
+                .iter()
+                .flatten()
+                .any(|item| !item.executed)
+    });
+    if pending != Some(true) {
+        return;
+    }
+
+    let records: Vec<DistributionRecord> =
+        store::distribution::filter_distribution_records(0, usize::MAX, |record| {
+            record.payout_id.is_none()
+        })
+        .into_iter()
+        .map(|(_, record)| record)
+        .collect();
+    if records.is_empty() {
+        return;
+    }
+    let paid = store::distribution::update_model(|model| mark_legacy_progress(model, &records));
+    for payout in paid.unwrap_or_default() {
+        store::distribution::add_payout(payout);
+    }
+    store::state::save();
+}
+
+/// Only legacy transfers that succeeded count as progress: the legacy timer also marked the
+/// ones that failed, which were never retried. Each unlock record is matched to one item with
+/// the same recipient and amount that was due when the record was written. Returns completed
+/// payouts for the initial recipients already paid, so that planning the rest of the initial
+/// distribution skips them.
+fn mark_legacy_progress(
+    model: &mut DistributionModel,
+    records: &[DistributionRecord],
+) -> Vec<DistributionPayout> {
+    let succeeded: Vec<&DistributionRecord> = records
+        .iter()
+        .filter(|record| record.status == DistributionStatus::Completed)
+        .collect();
+
+    let mut paid = vec![];
+    let mut paid_at = vec![];
+    if model.initial_executed_at.is_none() {
+        for (addr, amount) in &model.initial_distribution {
+            let record = succeeded.iter().find(|record| {
+                record.distribution_type == DistributionType::Initial
+                    && record.recipient == *addr
+                    && record.amount == *amount
+            });
+            if let Some(record) = record {
+                paid.push(legacy_payout(format!("{}{}", INITIAL_PREFIX, addr), record));
+                paid_at.push(record.timestamp);
+            }
+        }
+        if !paid.is_empty() && paid.len() == model.initial_distribution.len() {
+            model.initial_executed_at = paid_at.into_iter().max();
+        }
+    }
+
+    let mut unlocks: Vec<&DistributionRecord> = succeeded
+        .into_iter()
+        .filter(|record| record.distribution_type == DistributionType::Scheduled)
+        .collect();
+    for item in model.unlock_schedule.iter_mut().flatten() {
+        let matching = unlocks.iter().position(|record| {
+            record.recipient == item.addr
+                && record.amount == item.amount
+                && record.timestamp >= item.timestamp * 1_000_000_000
+        });
+        if let Some(index) = matching {
+            unlocks.swap_remove(index);
+            item.executed = true;
+        }
+    }
+    paid
+}
+
+/// A completed payout standing for a transfer the legacy timer made.
+fn legacy_payout(id: String, record: &DistributionRecord) -> DistributionPayout {
+    let paid_at = record.timestamp / 1_000_000_000;
+    DistributionPayout {
+        memo: sha256(id.as_bytes()),
+        id,
+        distribution_type: record.distribution_type.clone(),
+        recipient: record.recipient.clone(),
+        amount: record.amount.clone(),
+        from_subaccount: None,
+        created_at_time: None,
+        status: DistributionStatus::Completed,
+        attempts: 1,
+        block_index: record.block_index.clone(),
+        last_error: None,
+        scheduled_at: paid_at,
+        updated_at: paid_at,
+        evm_tx_hash: record.tx_hash.clone(),
+    }
+}
+
+/// Plans a transfer of `amount` to the `recipient` account, from `from_subaccount` or the
+/// treasury. Returns false if a payout with the same id was already planned.
+pub fn queue_payout(
+    id: String,
+    distribution_type: DistributionType,
+    recipient: String,
+    amount: Nat,
+    from_subaccount: Option<Subaccount>,
+) -> bool {
+    let now = current_time_secs();
+    let memo = sha256(id.as_bytes());
+    store::distribution::add_payout(DistributionPayout {
+        id,
+        distribution_type,
+        recipient,
+        amount,
+        from_subaccount,
+        memo,
+        created_at_time: None,
+        status: DistributionStatus::Pending,
+        attempts: 0,
+        block_index: None,
+        last_error: None,
+        scheduled_at: now,
+        updated_at: now,
+        evm_tx_hash: None,
+    })
+}
+
+/// Sends a pending payout through the wallet path of its recipient's chain and records the
+/// outcome. ICRC-1 recipients are paid by `token_service`, the base token's ledger.
+pub async fn pay(token_service: Option<&TokenICRC1>, payout_id: &str) -> Result<(), String> {
+    // Reloaded so that a payout paid concurrently, e.g. by a claim, reuses its transaction time.
+    let payout = store::distribution::get_payout(payout_id)
+        .ok_or_else(|| format!("Distribution payout {} not found", payout_id))?;
+    if payout.status != DistributionStatus::Pending {
+        return Ok(());
+    }
+
+    let base_chain = store::state::get_dao_info()
+        .ok_or("DAO not initialized")?
+        .base_token
+        .token_location
+        .chain;
+    let recipient = match parse_recipient(&payout.recipient, &base_chain) {
+        Ok(recipient) => recipient,
+        Err(e) => return fail_payout(&payout, e, "InvalidRecipient"),
+    };
+
+    match (recipient, token_service) {
+        (DistributionRecipient::Icrc1(account), Some(token_service)) => {
+            pay_icrc1(token_service, &payout, account).await
+        }
+        (DistributionRecipient::Icrc1(_), None) => fail_payout(
+            &payout,
+            "The base token is not an ICRC-1 ledger".to_string(),
+            "UnsupportedChain",
+        ),
+        (DistributionRecipient::Evm { chain_id, address }, _) => {
+            pay_evm(&payout, chain_id, &address).await
+        }
+        (DistributionRecipient::Solana(_), _) => fail_payout(
+            &payout,
+            "The DAO wallet cannot transfer on Solana".to_string(),
+            "UnsupportedChain",
+        ),
+    }
+}
+
+async fn pay_icrc1(
+    token_service: &TokenICRC1,
+    payout: &DistributionPayout,
+    account: Account,
+) -> Result<(), String> {
+    // The first attempt fixes the transaction time; retries resend the exact same transfer.
+    let created_at_time = payout.created_at_time.unwrap_or_else(ic_cdk::api::time);
+    store::distribution::update_payout(&payout.id, |stored| {
+        stored.created_at_time = Some(created_at_time);
+        stored.attempts += 1;
+    })?;
+
+    let result = token_service
+        .icrc1_transfer(
+            Some(payout.from_subaccount.unwrap_or(HOLDER_SUBACCOUNT)),
+            account,
+            payout.amount.clone(),
+            None,
+            Some(Memo(ByteBuf::from(payout.memo.clone()))),
+            Some(created_at_time),
+        )
+        .await;
+
+    let block_index = match result {
+        Ok(block_index) => Ok(block_index),
+        // An earlier attempt went through, but its reply was lost.
+        Err(TransferError::Duplicate { duplicate_of }) => Ok(duplicate_of),
+        Err(e) => Err(e),
+    };
+
+    match block_index {
+        Ok(block_index) => {
+            log!(
+                INFO,
+                "[{:?}] Distributed {} tokens to {} in block {}",
+                payout.distribution_type,
+                payout.amount,
+                payout.recipient,
+                block_index
+            );
+            store::distribution::update_payout(&payout.id, |stored| {
+                stored.status = DistributionStatus::Completed;
+                stored.block_index = Some(block_index.clone());
+                stored.last_error = None;
+            })?;
+            mark_paid(payout);
+            add_record(
+                payout,
+                DistributionStatus::Completed,
+                Some(block_index),
+                None,
+                None,
+            );
+        }
+        Err(e) => {
+            let error = format!("{:?}", e);
+            log!(
+                INFO,
+                "[{:?}] Failed to distribute {} tokens to {}: {}",
+                payout.distribution_type,
+                payout.amount,
+                payout.recipient,
+                error
+            );
+            // Retries run on every tick, so a transfer that went through would have been reported
+            // as a duplicate before its transaction time expired; a fresh time cannot pay twice.
+            let expired = matches!(e, TransferError::TooOld);
+            if payout.last_error.as_ref() != Some(&error) {
+                add_record(
+                    payout,
+                    DistributionStatus::Failed(error.clone()),
+                    None,
+                    Some(transfer_error_code(&e)),
+                    None,
+                );
+            }
+            store::distribution::update_payout(&payout.id, |stored| {
+                if expired {
+                    stored.created_at_time = None;
+                }
+                stored.last_error = Some(error);
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Sends an EVM payout through the wallet's transfer of the base token on the recipient's chain,
+/// e.g. an ERC-20 `transfer`, then waits for its receipt. The payout completes once the transfer
+/// is confirmed; a transfer that reverts or is dropped is sent again.
+async fn pay_evm(
+    payout: &DistributionPayout,
+    chain_id: u64,
+    recipient: &str,
+) -> Result<(), String> {
+    if let Some(tx_hash) = &payout.evm_tx_hash {
+        return settle_evm_payout(payout, tx_hash).await;
+    }
+
+    let token_name = store::state::get_dao_info()
+        .ok_or("DAO not initialized")?
+        .base_token
+        .name;
+    let chain_config = match store::state::get_chain_config_by_chain_id(chain_id) {
+        Some(chain_config) => chain_config,
+        None => {
+            let error = format!("Chain {} is not configured", chain_id);
+            return record_evm_error(payout, error);
+        }
+    };
+    let wallet_address = account_to_eth_address()?;
+    let expected = match expected_transfer(&chain_config, &token_name, recipient, &payout.amount) {
+        Ok(expected) => expected,
+        Err(e) => return record_evm_error(payout, e),
+    };
+
+    let attempt_at = current_time_secs();
+    store::distribution::update_payout(&payout.id, |stored| stored.attempts += 1)?;
+    let result = WalletBlockchainConfig(chain_config)
+        .token_transfer(
+            &token_name,
+            &wallet_address,
+            &None,
+            recipient,
+            &None,
+            payout.amount.clone(),
+            None,
+        )
+        .await;
+
+    let tx_hash = match result {
+        Ok(tx_hash) => tx_hash,
+        // An inconclusive broadcast leaves the transaction pending in the log; it must not be
+        // sent twice.
+        Err(e) => match find_tracked_transfer(chain_id, &wallet_address, &expected, attempt_at) {
+            Some(tx_hash) => tx_hash,
+            None => return record_evm_error(payout, e),
+        },
+    };
+    log!(
+        INFO,
+        "[{:?}] Sent {} tokens to {} in transaction {}",
+        payout.distribution_type,
+        payout.amount,
+        payout.recipient,
+        tx_hash
+    );
+    store::distribution::update_payout(&payout.id, |stored| {
+        stored.evm_tx_hash = Some(tx_hash);
+        stored.last_error = None;
+    })?;
+    Ok(())
+}
+
+async fn settle_evm_payout(payout: &DistributionPayout, tx_hash: &str) -> Result<(), String> {
+    let original = store::transactions::find_transaction_by_hash(tx_hash)
+        .ok_or_else(|| format!("Transaction {} not found", tx_hash))?;
+    let tx = latest_replacement(original.clone());
+    let error = match &tx.status {
+        // A cancel replacement was mined in place of the transfer.
+        EvmTxStatus::Confirmed if tx.data != original.data || tx.to != original.to => {
+            "Transfer was cancelled".to_string()
+        }
+        EvmTxStatus::Confirmed => {
+            log!(
+                INFO,
+                "[{:?}] Distributed {} tokens to {} in transaction {}",
+                payout.distribution_type,
+                payout.amount,
+                payout.recipient,
+                tx.tx_hash
+            );
+            store::distribution::update_payout(&payout.id, |stored| {
+                stored.status = DistributionStatus::Completed;
+                stored.evm_tx_hash = Some(tx.tx_hash.clone());
+            })?;
+            mark_paid(payout);
+            add_record(
+                payout,
+                DistributionStatus::Completed,
+                None,
+                None,
+                Some(tx.tx_hash),
+            );
+            return Ok(());
+        }
+        EvmTxStatus::Failed(reason) => format!("Transfer reverted: {}", reason),
+        // Transactions used to be dropped on a timeout alone, yet may still be mined. Only a
+        // transaction whose nonce went to another is void; the others are polled again.
+        EvmTxStatus::Dropped if !timer::is_dropped(&tx).await? => {
+            store::transactions::set_status(tx.id, EvmTxStatus::Pending)?;
+            return Ok(());
+        }
+        EvmTxStatus::Dropped => "Transfer was dropped".to_string(),
+        EvmTxStatus::Pending | EvmTxStatus::Replaced => return Ok(()),
+    };
+
+    // Nothing was transferred, so the next tick sends a new transaction.
+    add_record(
+        payout,
+        DistributionStatus::Failed(error.clone()),
+        None,
+        Some("TransferFailed".to_string()),
+        Some(tx.tx_hash),
+    );
+    store::distribution::update_payout(&payout.id, |stored| {
+        stored.evm_tx_hash = None;
+        stored.last_error = Some(error);
+    })?;
+    Ok(())
+}
+
+fn record_evm_error(payout: &DistributionPayout, error: String) -> Result<(), String> {
+    log!(
+        INFO,
+        "[{:?}] Failed to distribute {} tokens to {}: {}",
+        payout.distribution_type,
+        payout.amount,
+        payout.recipient,
+        error
+    );
+    if payout.last_error.as_ref() != Some(&error) {
+        add_record(
+            payout,
+            DistributionStatus::Failed(error.clone()),
+            None,
+            Some("TransferFailed".to_string()),
+            None,
+        );
+    }
+    store::distribution::update_payout(&payout.id, |stored| {
+        stored.last_error = Some(error);
+    })?;
+    Ok(())
+}
+
+/// The `to`, value and call data of the transaction paying `amount` of `token_name`.
+fn expected_transfer(
+    chain_config: &BlockchainConfig,
+    token_name: &str,
+    recipient: &str,
+    amount: &Nat,
+) -> Result<(Option<String>, Nat, Option<String>), String> {
+    let token = chain_config.get_token_config(token_name)?;
+    match token.standard {
+        TokenStandard::Native => Ok((Some(recipient.to_lowercase()), amount.clone(), None)),
+        TokenStandard::ERC20 => {
+            let contract_address = token
+                .contract_address
+                .as_ref()
+                .ok_or("ERC20 contract address is missing")?;
+            let data = generate_erc20_transfer_data(recipient, amount)?;
+            Ok((
+                Some(contract_address.to_lowercase()),
+                Nat::from(0u64),
+                Some(format!("0x{}", hex::encode(data))),
+            ))
+        }
+        _ => Err(format!(
+            "Token {} cannot be sent on an EVM chain",
+            token_name
+        )),
+    }
+}
+
+/// A pending transaction sent by this attempt, unless another payout already owns it.
+fn find_tracked_transfer(
+    chain_id: u64,
+    from: &str,
+    (to, value, data): &(Option<String>, Nat, Option<String>),
+    since: u64,
+) -> Option<String> {
+    store::transactions::list_transactions_since(since)
+        .into_iter()
+        .find(|tx| {
+            tx.status == EvmTxStatus::Pending
+                && tx.chain_id == chain_id
+                && tx.from.eq_ignore_ascii_case(from)
+                && tx.to.as_ref().map(|to| to.to_lowercase()) == *to
+                && tx.value == *value
+                && tx.data == *data
+                && store::distribution::find_payout_by_tx_hash(&tx.tx_hash).is_none()
+        })
+        .map(|tx| tx.tx_hash)
+}
+
+/// Marks a payout that can never be paid as failed.
+fn fail_payout(payout: &DistributionPayout, error: String, error_code: &str) -> Result<(), String> {
+    add_record(
+        payout,
+        DistributionStatus::Failed(error.clone()),
+        None,
+        Some(error_code.to_string()),
+        None,
+    );
+    store::distribution::update_payout(&payout.id, |stored| {
+        stored.status = DistributionStatus::Failed(error);
+    })?;
+    Ok(())
+}
+
+/// Parses a payout recipient and checks its address. A bare `0x` address is on `base_chain`, the
+/// chain of the base token.
+pub fn parse_recipient(
+    text: &str,
+    base_chain: &ChainType,
+) -> Result<DistributionRecipient, String> {
+    let recipient = match text.starts_with("0x") {
+        true => DistributionRecipient::Evm {
+            chain_id: store::state::get_chain_id(base_chain)
+                .or_else(|| base_chain.evm_chain_id())
+                .ok_or_else(|| format!("{} is not an address on {:?}", text, base_chain))?,
+            address: text.to_string(),
+        },
+        false => DistributionRecipient::from_str(text)?,
+    };
+
+    match &recipient {
+        DistributionRecipient::Icrc1(_) => {}
+        DistributionRecipient::Evm { address, .. } => {
+            if !address.starts_with("0x") || H160::from_str(address).is_err() {
+                return Err(format!("Invalid EVM address {}", address));
+            }
+        }
+        DistributionRecipient::Solana(address) => {
+            let is_key = bs58::decode(address)
+                .into_vec()
+                .is_ok_and(|bytes| bytes.len() == 32);
+            if !is_key {
+                return Err(format!("Invalid Solana address {}", address));
+            }
+        }
+    }
+    Ok(recipient)
+}
+
+/// Checks every recipient of a distribution model whose base token lives on `base_chain`.
+pub fn validate_recipients(
+    model: &DistributionModel,
+    base_chain: &ChainType,
+) -> Result<(), String> {
+    let weights = model
+        .emission_schedule
+        .as_ref()
+        .and_then(|schedule| schedule.weights.as_ref());
+    let recipients = model
+        .initial_distribution
+        .keys()
+        .chain(weights.into_iter().flat_map(|weights| weights.keys()))
+        .chain(
+            model
+                .unlock_schedule
+                .iter()
+                .flatten()
+                .map(|item| &item.addr),
+        );
+    for recipient in recipients {
+        parse_recipient(recipient, base_chain)?;
+    }
+    Ok(())
+}
+
+/// Pays a payout requested by its recipient right away. Returns the amount paid.
+pub async fn pay_claim(token_service: &TokenICRC1, payout_id: &str) -> Result<Nat, String> {
+    pay(Some(token_service), payout_id).await?;
+    store::state::save();
+    match store::distribution::get_payout(payout_id) {
+        Some(payout) if payout.status == DistributionStatus::Completed => Ok(payout.amount),
+        Some(payout) => Err(format!(
+            "Transfer of {} failed and will be retried: {}",
+            payout.amount,
+            payout.last_error.unwrap_or_default()
+        )),
+        None => Err(format!("Distribution payout {} not found", payout_id)),
+    }
+}
+
+/// Records a paid payout in the distribution model.
+fn mark_paid(payout: &DistributionPayout) {
+    let now = ic_cdk::api::time();
+    store::distribution::update_model(|model| match payout.distribution_type {
+        DistributionType::Initial => {
+            if store::distribution::all_completed(INITIAL_PREFIX) {
+                model.initial_executed_at = Some(now);
+            }
+        }
+        DistributionType::Scheduled => mark_unlock_executed(model, &payout.id),
+        DistributionType::Emission
+        | DistributionType::Vesting
+        | DistributionType::Airdrop
+        | DistributionType::Unstake
+        | DistributionType::VotingReward => {}
+    });
+}
+
+fn mark_unlock_executed(model: &mut DistributionModel, payout_id: &str) {
+    if let Some(schedule) = model.unlock_schedule.as_mut() {
+        let ids = unlock_payout_ids(schedule);
+        for (item, id) in schedule.iter_mut().zip(ids) {
+            if id == payout_id {
+                item.executed = true;
+            }
+        }
+    }
+}
+
+/// The payout ids of the unlock items, derived from their content so that editing the schedule
+/// does not shift them onto other items. Identical items are told apart by their occurrence.
+fn unlock_payout_ids(schedule: &[UnlockScheduleItem]) -> Vec<String> {
+    schedule
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let id = format!("unlock:{}:{}:{}", item.timestamp, item.addr, item.amount.0);
+            let occurrence = schedule[..index]
+                .iter()
+                .filter(|other| {
+                    other.timestamp == item.timestamp
+                        && other.addr == item.addr
+                        && other.amount == item.amount
+                })
+                .count();
+            match occurrence {
+                0 => id,
+                n => format!("{}:{}", id, n),
+            }
+        })
+        .collect()
+}
+
+fn add_record(
+    payout: &DistributionPayout,
+    status: DistributionStatus,
+    block_index: Option<Nat>,
+    error_code: Option<String>,
+    tx_hash: Option<String>,
+) {
+    history::add_record(DistributionRecord {
+        distribution_type: payout.distribution_type.clone(),
+        timestamp: ic_cdk::api::time(),
+        recipient: payout.recipient.clone(),
+        amount: payout.amount.clone(),
+        status,
+        block_index,
+        error_code,
+        payout_id: Some(payout.id.clone()),
+        tx_hash,
+    });
+}
+
+/// The variant name of a ledger error, stable across its details.
+fn transfer_error_code(error: &TransferError) -> String {
+    match error {
+        TransferError::BadFee { .. } => "BadFee".to_string(),
+        TransferError::BadBurn { .. } => "BadBurn".to_string(),
+        TransferError::InsufficientFunds { .. } => "InsufficientFunds".to_string(),
+        TransferError::TooOld => "TooOld".to_string(),
+        TransferError::CreatedInFuture { .. } => "CreatedInFuture".to_string(),
+        TransferError::TemporarilyUnavailable => "TemporarilyUnavailable".to_string(),
+        TransferError::Duplicate { .. } => "Duplicate".to_string(),
+        TransferError::GenericError { error_code, .. } => format!("GenericError:{}", error_code),
+    }
+}
+
+pub fn base_token_ledger() -> Result<TokenICRC1, String> {
+    let dao = store::state::get_dao_info().ok_or("DAO not initialized")?;
+    let principal = dao
+        .base_token
+        .token_location
+        .canister_id
+        .ok_or("The base token is not an ICRC-1 ledger")?;
+    Ok(TokenICRC1 { principal })
+}
+
+/// An account of this canister, e.g. the treasury at `HOLDER_SUBACCOUNT`.
+pub fn subaccount_account(subaccount: Subaccount) -> Account {
+    Account {
+        owner: ic_cdk::api::canister_self(),
+        subaccount: Some(subaccount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::DistributionRecordWrapper;
+    use candid::{CandidType, Encode};
+    use ic_govmind_types::to_cbor_bytes;
+    use ic_stable_structures::Storable;
+    use serde::Serialize;
+    use std::{
+        borrow::Cow,
+        collections::HashMap,
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    // The distribution model and records as the timer wrote them before payouts were persisted.
+    #[derive(Serialize)]
+    struct LegacyUnlockItem {
+        addr: String,
+        timestamp: u64,
+        amount: u128,
+        executed: bool,
+    }
+
+    #[derive(Serialize)]
+    struct LegacyModel {
+        initial_distribution: HashMap<String, u128>,
+        emission_period: Option<u64>,
+        emission_rate: Option<u128>,
+        unlock_schedule: Option<Vec<LegacyUnlockItem>>,
+        initial_executed_at: Option<u64>,
+        last_emission_time: Option<u64>,
+    }
+
+    #[derive(CandidType)]
+    struct LegacyRecord {
+        distribution_type: DistributionType,
+        timestamp: u64,
+        recipient: String,
+        amount: Nat,
+        tx_result: String,
+    }
+
+    #[derive(CandidType)]
+    struct LegacyRecordWrapper(LegacyRecord);
+
+    fn unlock(addr: &str, timestamp: u64, amount: u128) -> LegacyUnlockItem {
+        LegacyUnlockItem {
+            addr: addr.to_string(),
+            timestamp,
+            amount,
+            executed: false,
+        }
+    }
+
+    fn legacy_model(
+        initial_distribution: HashMap<String, u128>,
+        unlock_schedule: Vec<LegacyUnlockItem>,
+    ) -> DistributionModel {
+        let legacy = LegacyModel {
+            initial_distribution,
+            emission_period: None,
+            emission_rate: None,
+            unlock_schedule: Some(unlock_schedule),
+            initial_executed_at: None,
+            last_emission_time: None,
+        };
+        ciborium::from_reader(&to_cbor_bytes(&legacy)[..]).unwrap()
+    }
+
+    fn record(
+        distribution_type: DistributionType,
+        secs: u64,
+        recipient: &str,
+        amount: u64,
+        tx_result: &str,
+    ) -> DistributionRecord {
+        let legacy = LegacyRecordWrapper(LegacyRecord {
+            distribution_type,
+            timestamp: secs * 1_000_000_000,
+            recipient: recipient.to_string(),
+            amount: Nat::from(amount),
+            tx_result: tx_result.to_string(),
+        });
+        DistributionRecordWrapper::from_bytes(Cow::Owned(Encode!(&legacy).unwrap())).into_inner()
+    }
+
+    fn payout(id: &str, status: DistributionStatus, scheduled_at: u64) -> DistributionPayout {
+        DistributionPayout {
+            id: id.to_string(),
+            distribution_type: DistributionType::Initial,
+            recipient: "a".to_string(),
+            amount: Nat::from(100u64),
+            from_subaccount: None,
+            memo: sha256(id.as_bytes()),
+            created_at_time: Some(1),
+            status,
+            attempts: 1,
+            block_index: None,
+            last_error: None,
+            scheduled_at,
+            updated_at: scheduled_at,
+            evm_tx_hash: None,
+        }
+    }
+
+    /// Polls `future` once; it must finish without waiting on a call.
+    fn now_or_never<F: Future>(future: F) -> F::Output {
+        let mut context = Context::from_waker(Waker::noop());
+        match pin!(future).poll(&mut context) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future waits on a call"),
+        }
+    }
+
+    #[test]
+    fn legacy_progress_is_not_planned_again() {
+        let mut model = legacy_model(
+            HashMap::from([("a".to_string(), 100), ("b".to_string(), 200)]),
+            vec![
+                unlock("a", 1_000, 50),
+                unlock("a", 2_000, 50),
+                unlock("b", 5_000, 70),
+            ],
+        );
+        let records = vec![
+            record(DistributionType::Initial, 10, "a", 100, "Success: 1"),
+            record(
+                DistributionType::Initial,
+                10,
+                "b",
+                200,
+                "Error: InsufficientFunds",
+            ),
+            record(DistributionType::Scheduled, 1_060, "a", 50, "Success: 3"),
+            record(DistributionType::Scheduled, 5_060, "b", 70, "Error: TooOld"),
+        ];
+
+        mark_legacy_progress(&mut model, &records);
+        let paid: Vec<String> = mark_legacy_progress(&mut model, &records)
+            .into_iter()
+            .map(|payout| payout.id)
+            .collect();
+        assert_eq!(paid, vec!["initial:a".to_string()]);
+        assert_eq!(model.initial_executed_at, None);
+
+        // Failed transfers are planned again; the paid initial recipient is already a payout.
+        let planned = due_payouts(&mut model, &Nat::from(1_000u64), 6_000 * 1_000_000_000);
+        let mut ids: Vec<String> = planned
+            .into_iter()
+            .map(|(id, ..)| id)
+            .filter(|id| !paid.contains(id))
+            .collect();
+        ids.sort();
+        assert_eq!(
+            ids,
+            vec!["initial:b", "unlock:2000:a:50", "unlock:5000:b:70"]
+        );
+    }
+
+    #[test]
+    fn legacy_initial_distribution_is_done_once_every_recipient_was_paid() {
+        let mut model = legacy_model(HashMap::from([("a".to_string(), 100)]), vec![]);
+        let records = vec![
+            record(DistributionType::Initial, 10, "a", 100, "Error: TooOld"),
+            record(DistributionType::Initial, 20, "a", 100, "Success: 4"),
+        ];
+
+        let paid = mark_legacy_progress(&mut model, &records);
+        assert_eq!(paid.len(), 1);
+        assert_eq!(paid[0].block_index, Some(Nat::from(4u64)));
+        assert_eq!(model.initial_executed_at, Some(20 * 1_000_000_000));
+    }
+
+    #[test]
+    fn unlock_ids_follow_the_item_not_its_position() {
+        let item = |addr: &str, timestamp: u64, amount: u64| UnlockScheduleItem {
+            addr: addr.to_string(),
+            timestamp,
+            amount: Nat::from(amount),
+            executed: false,
+        };
+        let ids = unlock_payout_ids(&[item("a", 1_000, 50), item("a", 1_000, 50)]);
+        assert_eq!(ids, vec!["unlock:1000:a:50", "unlock:1000:a:50:1"]);
+
+        let mut model = legacy_model(
+            HashMap::new(),
+            vec![unlock("b", 500, 10), unlock("a", 1_000, 50)],
+        );
+        mark_unlock_executed(&mut model, "unlock:1000:a:50");
+        let executed: Vec<bool> = model
+            .unlock_schedule
+            .unwrap()
+            .iter()
+            .map(|item| item.executed)
+            .collect();
+        assert_eq!(executed, vec![false, true]);
+    }
+
+    #[test]
+    fn retried_payout_is_not_transferred_twice() {
+        assert!(store::distribution::add_payout(payout(
+            "initial:a",
+            DistributionStatus::Completed,
+            1
+        )));
+        assert!(store::distribution::add_payout(payout(
+            "initial:b",
+            DistributionStatus::Pending,
+            2
+        )));
+        assert!(!store::distribution::add_payout(payout(
+            "initial:a",
+            DistributionStatus::Pending,
+            3
+        )));
+
+        // A completed payout returns before reaching any ledger.
+        now_or_never(pay(None, "initial:a")).unwrap();
+        let paid = store::distribution::get_payout("initial:a").unwrap();
+        assert_eq!(paid.status, DistributionStatus::Completed);
+        assert_eq!(paid.attempts, 1);
+
+        let pending: Vec<String> = store::distribution::list_pending_payouts()
+            .into_iter()
+            .map(|payout| payout.id)
+            .collect();
+        assert_eq!(pending, vec!["initial:b".to_string()]);
+    }
+}
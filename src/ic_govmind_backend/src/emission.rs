@@ -0,0 +1,268 @@
+use candid::Nat;
+use ic_govmind_types::dao::{DistributionModel, EmissionDecay, SupplyProjection};
+use std::collections::HashMap;
+
+const BPS: u64 = 10_000;
+// Fixed-point scale of the exponential decay factor.
+const SCALE: u128 = 1_000_000_000_000_000_000;
+pub const DEFAULT_EMISSION_PERIOD_SECS: u64 = 60;
+
+/// Tokens emitted by round `round` (zero-based), before the supply cap.
+pub fn round_emission(model: &DistributionModel, round: u64) -> Nat {
+    let zero = Nat::from(0u64);
+    let Some(rate) = model.emission_rate.clone() else {
+        return zero;
+    };
+
+    match model.emission_schedule.as_ref().map(|s| &s.decay) {
+        None | Some(EmissionDecay::Constant) => rate,
+        Some(EmissionDecay::Halving { every_rounds }) => {
+            let halvings = round / (*every_rounds).max(1);
+            match halvings < 128 {
+                true => rate / Nat::from(1u128 << halvings),
+                false => zero,
+            }
+        }
+        Some(EmissionDecay::Exponential { decay_bps }) => {
+            let keep = BPS.saturating_sub(*decay_bps as u64);
+            rate * Nat::from(decay_factor(keep, round)) / Nat::from(SCALE)
+        }
+    }
+}
+
+/// (keep_bps / 10_000)^round in `SCALE` fixed point, by exponentiation by squaring.
+fn decay_factor(keep_bps: u64, round: u64) -> u128 {
+    let mut factor = SCALE;
+    let mut base = keep_bps.min(BPS) as u128 * SCALE / BPS as u128;
+    let mut exponent = round;
+    while exponent > 0 && factor > 0 {
+        if exponent & 1 == 1 {
+            factor = factor * base / SCALE;
+        }
+        base = base * base / SCALE;
+        exponent >>= 1;
+    }
+    factor
+}
+
+/// Checks the emission schedule of a distribution model before it is set.
+pub fn validate_schedule(model: &DistributionModel) -> Result<(), String> {
+    let Some(schedule) = &model.emission_schedule else {
+        return Ok(());
+    };
+    if schedule.treasury_share_bps as u64 > BPS {
+        return Err(format!(
+            "Treasury share of {} bps exceeds {} bps",
+            schedule.treasury_share_bps, BPS
+        ));
+    }
+    match schedule.decay {
+        EmissionDecay::Exponential { decay_bps } if decay_bps as u64 > BPS => Err(format!(
+            "Emission decay of {} bps exceeds {} bps",
+            decay_bps, BPS
+        )),
+        EmissionDecay::Halving { every_rounds: 0 } => {
+            Err("Emission cannot halve every 0 rounds".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Emission still allowed under the cap: the total supply less the initial allocations and what
+/// was already emitted.
+pub fn remaining_emission(model: &DistributionModel, total_supply: &Nat) -> Nat {
+    let mut used = model.emitted_total.clone().unwrap_or_default();
+    for amount in model.initial_distribution.values() {
+        used += amount.clone();
+    }
+    match *total_supply > used {
+        true => total_supply.clone() - used,
+        false => Nat::from(0u64),
+    }
+}
+
+/// Splits a round's emission among recipients in proportion to their weights. The treasury
+/// share and the rounding remainder stay in the treasury, whose part is returned second.
+pub fn split_emission(model: &DistributionModel, amount: &Nat) -> (Vec<(String, Nat)>, Nat) {
+    let schedule = model.emission_schedule.as_ref();
+    let weights: &HashMap<String, Nat> = schedule
+        .and_then(|s| s.weights.as_ref())
+        .unwrap_or(&model.initial_distribution);
+    let treasury_bps = schedule.map_or(0, |s| (s.treasury_share_bps as u64).min(BPS));
+
+    let distributable = amount.clone() * Nat::from(BPS - treasury_bps) / Nat::from(BPS);
+    let total_weight = weights
+        .values()
+        .fold(Nat::from(0u64), |sum, weight| sum + weight.clone());
+    if total_weight == 0u64 {
+        return (vec![], amount.clone());
+    }
+
+    let mut shares: Vec<(String, Nat)> = weights
+        .iter()
+        .map(|(addr, weight)| {
+            (
+                addr.clone(),
+                distributable.clone() * weight.clone() / total_weight.clone(),
+            )
+        })
+        .filter(|(_, share)| *share > 0u64)
+        .collect();
+    shares.sort();
+
+    let distributed = shares
+        .iter()
+        .fold(Nat::from(0u64), |sum, (_, share)| sum + share.clone());
+    (shares, amount.clone() - distributed)
+}
+
+/// Emission of the next round, capped by what the supply still allows.
+pub fn next_round_emission(model: &DistributionModel, total_supply: &Nat) -> Nat {
+    let round = model.emission_rounds.unwrap_or(0);
+    round_emission(model, round).min(remaining_emission(model, total_supply))
+}
+
+/// Plays the emission of the next `rounds` rounds forward from the model's current state.
+pub fn project_supply(
+    model: &DistributionModel,
+    total_supply: &Nat,
+    now: u64,
+    rounds: u64,
+) -> Vec<SupplyProjection> {
+    let period = model
+        .emission_period
+        .unwrap_or(DEFAULT_EMISSION_PERIOD_SECS);
+    let next_at = model
+        .last_emission_time
+        .map_or(now, |last| last / 1_000_000_000 + period);
+
+    let first_round = model.emission_rounds.unwrap_or(0);
+    let mut remaining = remaining_emission(model, total_supply);
+    let mut emitted_total = model.emitted_total.clone().unwrap_or_default();
+    let mut distributed = model
+        .initial_distribution
+        .values()
+        .fold(Nat::from(0u64), |sum, amount| sum + amount.clone());
+    let mut projections = vec![];
+    for i in 0..rounds {
+        let round = first_round + i;
+        let emission = round_emission(model, round).min(remaining.clone());
+        if emission == 0u64 {
+            break;
+        }
+        let (_, to_treasury) = split_emission(model, &emission);
+        remaining -= emission.clone();
+        emitted_total += emission.clone();
+        distributed += emission.clone() - to_treasury.clone();
+
+        projections.push(SupplyProjection {
+            round,
+            timestamp: next_at + i * period,
+            emission,
+            to_treasury,
+            emitted_total: emitted_total.clone(),
+            distributed_supply: distributed.clone(),
+        });
+    }
+    projections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_govmind_types::dao::EmissionSchedule;
+
+    fn model(decay: EmissionDecay, treasury_share_bps: u16) -> DistributionModel {
+        DistributionModel {
+            initial_distribution: HashMap::from([
+                ("a".to_string(), Nat::from(300u64)),
+                ("b".to_string(), Nat::from(100u64)),
+            ]),
+            emission_period: Some(10),
+            emission_rate: Some(Nat::from(1_000u64)),
+            unlock_schedule: None,
+            initial_executed_at: None,
+            last_emission_time: None,
+            emission_schedule: Some(EmissionSchedule {
+                weights: None,
+                decay,
+                treasury_share_bps,
+            }),
+            emission_rounds: None,
+            emitted_total: None,
+        }
+    }
+
+    #[test]
+    fn decays_by_round() {
+        let halving = model(EmissionDecay::Halving { every_rounds: 2 }, 0);
+        assert_eq!(round_emission(&halving, 1), Nat::from(1_000u64));
+        assert_eq!(round_emission(&halving, 2), Nat::from(500u64));
+        assert_eq!(round_emission(&halving, 5), Nat::from(250u64));
+
+        let exponential = model(EmissionDecay::Exponential { decay_bps: 1_000 }, 0);
+        assert_eq!(round_emission(&exponential, 2), Nat::from(810u64));
+    }
+
+    #[test]
+    fn exponential_decay_of_far_rounds_is_computed_directly() {
+        let mut exponential = model(EmissionDecay::Exponential { decay_bps: 5_000 }, 0);
+        exponential.emission_rate = Some(Nat::from(1u128 << 100));
+        assert_eq!(round_emission(&exponential, 10), Nat::from(1u128 << 90));
+        assert_eq!(round_emission(&exponential, u64::MAX), Nat::from(0u64));
+
+        let none_kept = model(EmissionDecay::Exponential { decay_bps: 10_000 }, 0);
+        assert_eq!(round_emission(&none_kept, 0), Nat::from(1_000u64));
+        assert_eq!(round_emission(&none_kept, 1), Nat::from(0u64));
+    }
+
+    #[test]
+    fn rejects_decay_beyond_the_whole_emission() {
+        assert!(
+            validate_schedule(&model(EmissionDecay::Exponential { decay_bps: 10_000 }, 0)).is_ok()
+        );
+        assert!(
+            validate_schedule(&model(EmissionDecay::Exponential { decay_bps: 10_001 }, 0)).is_err()
+        );
+        assert!(validate_schedule(&model(EmissionDecay::Constant, 10_001)).is_err());
+        assert!(validate_schedule(&model(EmissionDecay::Halving { every_rounds: 0 }, 0)).is_err());
+    }
+
+    #[test]
+    fn splits_pro_rata_with_treasury_share() {
+        let model = model(EmissionDecay::Constant, 1_000);
+        let (shares, to_treasury) = split_emission(&model, &Nat::from(1_001u64));
+        assert_eq!(
+            shares,
+            vec![
+                ("a".to_string(), Nat::from(675u64)),
+                ("b".to_string(), Nat::from(225u64)),
+            ]
+        );
+        assert_eq!(to_treasury, Nat::from(101u64));
+    }
+
+    #[test]
+    fn projection_stops_at_the_cap() {
+        let model = model(EmissionDecay::Constant, 0);
+        let projections = project_supply(&model, &Nat::from(2_900u64), 100, 10);
+        assert_eq!(projections.len(), 3);
+        assert_eq!(projections[2].emission, Nat::from(500u64));
+        assert_eq!(projections[2].timestamp, 120);
+        assert_eq!(projections[2].distributed_supply, Nat::from(2_900u64));
+    }
+
+    #[test]
+    fn projection_follows_the_decay() {
+        let mut model = model(EmissionDecay::Exponential { decay_bps: 700 }, 0);
+        model.emission_rounds = Some(3);
+        let projections = project_supply(&model, &Nat::from(1_000_000u64), 100, 40);
+        assert_eq!(projections.len(), 40);
+        for projection in projections {
+            assert_eq!(
+                projection.emission,
+                round_emission(&model, projection.round)
+            );
+        }
+    }
+}
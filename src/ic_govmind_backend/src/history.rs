@@ -0,0 +1,339 @@
+use candid::Nat;
+use ic_govmind_types::dao::{
+    DistributionRecord, DistributionRecordFilter, DistributionStatus, DistributionTotal,
+    DistributionType,
+};
+use ic_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use std::{collections::BTreeMap, mem::discriminant};
+use time::OffsetDateTime;
+
+use crate::store;
+
+const CSV_HEADER: &str =
+    "id,timestamp,date,type,recipient,amount,status,block_index,tx_hash,error_code,error,payout_id";
+// Rows per CSV page, keeping the response well below the query reply limit.
+const CSV_PAGE_SIZE: usize = 5_000;
+
+pub fn matches_filter(filter: &DistributionRecordFilter, record: &DistributionRecord) -> bool {
+    filter
+        .recipient
+        .as_ref()
+        .is_none_or(|recipient| *recipient == record.recipient)
+        && filter
+            .distribution_type
+            .as_ref()
+            .is_none_or(|distribution_type| *distribution_type == record.distribution_type)
+        && filter
+            .status
+            .as_ref()
+            .is_none_or(|status| discriminant(status) == discriminant(&record.status))
+        && filter.from.is_none_or(|from| record.timestamp >= from)
+        && filter.to.is_none_or(|to| record.timestamp < to)
+}
+
+pub fn list_records(
+    filter: &DistributionRecordFilter,
+    start: u64,
+    limit: usize,
+) -> Vec<(u64, DistributionRecord)> {
+    store::distribution::filter_distribution_records(start, limit, |record| {
+        matches_filter(filter, record)
+    })
+}
+
+/// Stores `record` and adds it to the running total of its recipient.
+pub fn add_record(record: DistributionRecord) {
+    let mut total =
+        store::distribution::get_total(&record.recipient).unwrap_or_else(|| empty_total(&record));
+    add_to_total(&mut total, &record);
+    store::distribution::set_total(total);
+    store::distribution::add_distribution_record(record);
+}
+
+/// Computes the running totals of the records stored before they were kept.
+pub fn migrate_totals() {
+    if store::distribution::has_totals() {
+        return;
+    }
+    let totals = store::distribution::with_distribution_records(0, |records| {
+        aggregate(records.map(|(_, record)| record))
+    });
+    for total in totals {
+        store::distribution::set_total(total);
+    }
+}
+
+/// Totals per recipient of the records matching `filter`, sorted by recipient. Filters on the
+/// recipient alone are served from the running totals.
+pub fn totals(filter: &DistributionRecordFilter) -> Vec<DistributionTotal> {
+    if filter.distribution_type.is_none()
+        && filter.status.is_none()
+        && filter.from.is_none()
+        && filter.to.is_none()
+    {
+        return match &filter.recipient {
+            Some(recipient) => store::distribution::get_total(recipient)
+                .into_iter()
+                .collect(),
+            None => store::distribution::list_totals(),
+        };
+    }
+    store::distribution::with_distribution_records(0, |records| {
+        aggregate(
+            records
+                .map(|(_, record)| record)
+                .filter(|record| matches_filter(filter, record)),
+        )
+    })
+}
+
+fn aggregate(records: impl Iterator<Item = DistributionRecord>) -> Vec<DistributionTotal> {
+    let mut totals: BTreeMap<String, DistributionTotal> = BTreeMap::new();
+    for record in records {
+        let total = totals
+            .entry(record.recipient.clone())
+            .or_insert_with(|| empty_total(&record));
+        add_to_total(total, &record);
+    }
+    totals.into_values().collect()
+}
+
+fn empty_total(record: &DistributionRecord) -> DistributionTotal {
+    DistributionTotal {
+        recipient: record.recipient.clone(),
+        received: Nat::from(0u64),
+        transfers: 0,
+        failed_attempts: 0,
+        first_at: record.timestamp,
+        last_at: record.timestamp,
+    }
+}
+
+fn add_to_total(total: &mut DistributionTotal, record: &DistributionRecord) {
+    match record.status {
+        DistributionStatus::Completed => {
+            total.received += record.amount.clone();
+            total.transfers += 1;
+        }
+        DistributionStatus::Failed(_) => total.failed_attempts += 1,
+        DistributionStatus::Pending => {}
+    }
+    total.first_at = total.first_at.min(record.timestamp);
+    total.last_at = total.last_at.max(record.timestamp);
+}
+
+/// Serves `/distributions.csv`, filtered by the `recipient`, `type`, `status`, `from` and `to`
+/// query parameters. Rows come in pages of at most `CSV_PAGE_SIZE`: `offset` skips that many
+/// matching rows and `limit` shortens the page.
+pub fn do_reply(req: HttpRequest) -> HttpResponse {
+    let (filter, offset, limit) = match parse_filter(&req).and_then(|filter| {
+        let offset = u64_param(&req, "offset")?.unwrap_or(0) as usize;
+        let limit = u64_param(&req, "limit")?.map_or(CSV_PAGE_SIZE, |limit| limit as usize);
+        Ok((filter, offset, limit.min(CSV_PAGE_SIZE)))
+    }) {
+        Ok(page) => page,
+        Err(e) => {
+            return HttpResponseBuilder::bad_request()
+                .with_body_and_content_length(e)
+                .build()
+        }
+    };
+    let records: Vec<(u64, DistributionRecord)> =
+        store::distribution::with_distribution_records(0, |records| {
+            records
+                .filter(|(_, record)| matches_filter(&filter, record))
+                .skip(offset)
+                .take(limit)
+                .collect()
+        });
+    HttpResponseBuilder::ok()
+        .header("Content-Type", "text/csv; charset=utf-8")
+        .header(
+            "Content-Disposition",
+            "attachment; filename=\"distributions.csv\"",
+        )
+        .with_body_and_content_length(to_csv(&records))
+        .build()
+}
+
+fn parse_filter(req: &HttpRequest) -> Result<DistributionRecordFilter, String> {
+    let distribution_type = match req.raw_query_param("type") {
+        None => None,
+        Some("Initial") => Some(DistributionType::Initial),
+        Some("Scheduled") => Some(DistributionType::Scheduled),
+        Some("Emission") => Some(DistributionType::Emission),
+        Some("Vesting") => Some(DistributionType::Vesting),
+        Some("Airdrop") => Some(DistributionType::Airdrop),
+        Some("Unstake") => Some(DistributionType::Unstake),
+        Some("VotingReward") => Some(DistributionType::VotingReward),
+        Some(other) => return Err(format!("unknown distribution type '{}'", other)),
+    };
+    let status = match req.raw_query_param("status") {
+        None => None,
+        Some("Pending") => Some(DistributionStatus::Pending),
+        Some("Completed") => Some(DistributionStatus::Completed),
+        Some("Failed") => Some(DistributionStatus::Failed(String::new())),
+        Some(other) => return Err(format!("unknown status '{}'", other)),
+    };
+
+    Ok(DistributionRecordFilter {
+        recipient: req.raw_query_param("recipient").map(str::to_string),
+        distribution_type,
+        status,
+        from: u64_param(req, "from")?,
+        to: u64_param(req, "to")?,
+    })
+}
+
+fn u64_param(req: &HttpRequest, name: &str) -> Result<Option<u64>, String> {
+    req.raw_query_param(name)
+        .map(|value| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("failed to parse the '{}' parameter", name))
+        })
+        .transpose()
+}
+
+fn to_csv(records: &[(u64, DistributionRecord)]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for (id, record) in records {
+        let (status, error) = match &record.status {
+            DistributionStatus::Pending => ("Pending", ""),
+            DistributionStatus::Completed => ("Completed", ""),
+            DistributionStatus::Failed(error) => ("Failed", error.as_str()),
+        };
+        let date = OffsetDateTime::from_unix_timestamp_nanos(record.timestamp as i128)
+            .map(|date| date.to_string())
+            .unwrap_or_default();
+        let fields = [
+            id.to_string(),
+            record.timestamp.to_string(),
+            date,
+            format!("{:?}", record.distribution_type),
+            record.recipient.clone(),
+            record.amount.0.to_string(),
+            status.to_string(),
+            record
+                .block_index
+                .as_ref()
+                .map(|index| index.0.to_string())
+                .unwrap_or_default(),
+            record.tx_hash.clone().unwrap_or_default(),
+            record.error_code.clone().unwrap_or_default(),
+            error.to_string(),
+            record.payout_id.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Prefixes fields that spreadsheets would evaluate as formulas with `'`, then quotes fields
+/// holding separators, quotes or line breaks, as RFC 4180 requires.
+fn csv_field(field: &str) -> String {
+    let field = match field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        true => format!("'{}", field),
+        false => field.to_string(),
+    };
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(recipient: &str, amount: u64, status: DistributionStatus) -> DistributionRecord {
+        DistributionRecord {
+            distribution_type: DistributionType::Emission,
+            timestamp: 1_000,
+            recipient: recipient.to_string(),
+            amount: Nat::from(amount),
+            status,
+            block_index: None,
+            error_code: None,
+            payout_id: None,
+            tx_hash: None,
+        }
+    }
+
+    #[test]
+    fn failed_status_matches_every_error() {
+        let filter = DistributionRecordFilter {
+            status: Some(DistributionStatus::Failed(String::new())),
+            to: Some(1_001),
+            ..Default::default()
+        };
+        let failed = record("a", 1, DistributionStatus::Failed("TooOld".to_string()));
+        assert!(matches_filter(&filter, &failed));
+        assert!(!matches_filter(
+            &filter,
+            &record("a", 1, DistributionStatus::Completed)
+        ));
+    }
+
+    #[test]
+    fn totals_count_completed_transfers_only() {
+        let records = [
+            record("a", 5, DistributionStatus::Completed),
+            record("a", 5, DistributionStatus::Failed("TooOld".to_string())),
+            record("a", 7, DistributionStatus::Completed),
+        ];
+        let totals = aggregate(records.into_iter());
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].received, Nat::from(12u64));
+        assert_eq!((totals[0].transfers, totals[0].failed_attempts), (2, 1));
+    }
+
+    #[test]
+    fn quotes_fields_with_separators() {
+        assert_eq!(csv_field("abc"), "abc");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+
+    #[test]
+    fn escapes_formulas() {
+        assert_eq!(csv_field("=1+1"), "'=1+1");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("-1,2"), "\"'-1,2\"");
+        assert_eq!(csv_field("1-1"), "1-1");
+    }
+
+    #[test]
+    fn running_totals_match_the_records() {
+        add_record(record("a", 5, DistributionStatus::Completed));
+        add_record(record("b", 3, DistributionStatus::Completed));
+        add_record(record(
+            "a",
+            5,
+            DistributionStatus::Failed("TooOld".to_string()),
+        ));
+        add_record(record("a", 7, DistributionStatus::Completed));
+
+        let running = totals(&DistributionRecordFilter::default());
+        let computed = totals(&DistributionRecordFilter {
+            to: Some(u64::MAX),
+            ..Default::default()
+        });
+        assert_eq!(running.len(), 2);
+        for (running, computed) in running.iter().zip(&computed) {
+            assert_eq!(running.recipient, computed.recipient);
+            assert_eq!(running.received, computed.received);
+            assert_eq!(
+                (running.transfers, running.failed_attempts),
+                (computed.transfers, computed.failed_attempts)
+            );
+        }
+
+        let a = totals(&DistributionRecordFilter {
+            recipient: Some("a".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(a[0].received, Nat::from(12u64));
+    }
+}
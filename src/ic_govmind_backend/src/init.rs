@@ -1,11 +1,15 @@
 use candid::{CandidType, Deserialize, Principal};
-use evm_rpc_types::{EthMainnetService, EthSepoliaService, RpcApi, RpcServices};
+use evm_rpc_types::{EthMainnetService, EthSepoliaService, L2MainnetService, RpcApi, RpcServices};
 use std::{collections::HashMap, time::Duration};
 
 use crate::{
+    distribution, history,
     signer::ecdsa::get_ecdsa_public_key_result,
     store::{self},
-    timer::restore_token_distribution_timer,
+    timer::{
+        restore_token_distribution_timer, setup_bridge_status_timer,
+        setup_evm_deposit_indexer_timer, setup_evm_tx_receipt_timer, setup_price_refresh_timer,
+    },
     types::{EcdsaKeyIds, KeyEnvironment, SchnorrKeyIds},
 };
 use ic_cdk::{init, post_upgrade, pre_upgrade};
@@ -64,6 +68,10 @@ fn init(args: Option<CanisterArgs>) {
             ic_cdk_timers::set_timer(Duration::from_secs(0), || {
                 ic_cdk::futures::spawn(job_ecdsa_setup())
             });
+            setup_evm_tx_receipt_timer();
+            setup_bridge_status_timer();
+            setup_evm_deposit_indexer_timer();
+            setup_price_refresh_timer();
         }
         Some(CanisterArgs::Upgrade(_)) => {
             ic_cdk::trap(
@@ -84,6 +92,16 @@ fn pre_upgrade() {
 #[post_upgrade]
 fn post_upgrade(args: Option<CanisterArgs>) {
     store::state::load();
+    distribution::migrate_legacy_progress();
+    history::migrate_totals();
+    // Canisters installed before the chain registry get the preset EVM chains they lack.
+    for chain in init_l2_chains().into_iter().chain([init_bnb_chain()]) {
+        let _ = store::state::add_chain_config(chain);
+    }
+    setup_evm_tx_receipt_timer();
+    setup_bridge_status_timer();
+    setup_evm_deposit_indexer_timer();
+    setup_price_refresh_timer();
 
     let mut should_setup_ecdsa = false;
 
@@ -204,6 +222,83 @@ pub fn init_eth_local_chain() -> BlockchainConfig {
     )
 }
 
+/// An EVM chain whose only preconfigured token is its native currency; ERC-20 tokens are added
+/// through the chain admin API.
+fn init_evm_chain(
+    chain_type: ChainType,
+    chain_id: u64,
+    chain_name: &str,
+    rpc_url: &str,
+    rpc_services: RpcServices,
+    native_symbol: &str,
+) -> BlockchainConfig {
+    BlockchainConfig::new_evm_config(
+        chain_type,
+        rpc_url.to_string(),
+        Some(chain_id),
+        Some(rpc_services),
+        None,
+        vec![TokenConfig {
+            token_name: native_symbol.to_string(),
+            symbol: native_symbol.to_string(),
+            contract_address: None,
+            decimal: 18,
+            chain_name: chain_name.to_string(),
+            standard: TokenStandard::Native,
+            ..Default::default()
+        }],
+    )
+}
+
+pub fn init_l2_chains() -> Vec<BlockchainConfig> {
+    let public_node = Some(vec![L2MainnetService::PublicNode]);
+    vec![
+        init_evm_chain(
+            ChainType::Evm(42161),
+            42161,
+            "Arbitrum One",
+            "https://arb1.arbitrum.io/rpc",
+            RpcServices::ArbitrumOne(public_node.clone()),
+            "ETH",
+        ),
+        init_evm_chain(
+            ChainType::Evm(8453),
+            8453,
+            "Base",
+            "https://mainnet.base.org",
+            RpcServices::BaseMainnet(public_node.clone()),
+            "ETH",
+        ),
+        init_evm_chain(
+            ChainType::Evm(10),
+            10,
+            "OP Mainnet",
+            "https://mainnet.optimism.io",
+            RpcServices::OptimismMainnet(public_node),
+            "ETH",
+        ),
+    ]
+}
+
+/// BNB Chain has no providers in the EVM RPC canister, so it goes through a custom endpoint.
+pub fn init_bnb_chain() -> BlockchainConfig {
+    let rpc_url = "https://bsc-dataseed.bnbchain.org";
+    init_evm_chain(
+        ChainType::BNBChain,
+        56,
+        "BNB Chain",
+        rpc_url,
+        RpcServices::Custom {
+            chain_id: 56,
+            services: vec![RpcApi {
+                url: rpc_url.to_string(),
+                headers: None,
+            }],
+        },
+        "BNB",
+    )
+}
+
 pub fn init_chain_config() -> Vec<BlockchainConfig> {
     let mut chains = Vec::new();
 
@@ -217,6 +312,7 @@ pub fn init_chain_config() -> Vec<BlockchainConfig> {
             rpc_url: "https://icp.network.rpc".to_string(),
             chain_id: None,
             rpc_services: None,
+            consensus: None,
         }),
         supported_tokens: vec![
             TokenConfig {
@@ -304,6 +400,7 @@ pub fn init_chain_config() -> Vec<BlockchainConfig> {
             rpc_url: "https://blockstream.info/api/".to_string(),
             chain_id: None,
             rpc_services: None,
+            consensus: None,
         }),
         supported_tokens: vec![TokenConfig {
             token_name: "BTC".to_string(),
@@ -321,6 +418,8 @@ pub fn init_chain_config() -> Vec<BlockchainConfig> {
     chains.push(eth_chain);
     chains.push(init_eth_sepolia_chain());
     chains.push(init_eth_local_chain());
+    chains.extend(init_l2_chains());
+    chains.push(init_bnb_chain());
     chains.push(btc_chain);
     chains
 }
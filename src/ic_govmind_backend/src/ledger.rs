@@ -0,0 +1,134 @@
+use candid::Encode;
+use ic_canister_log::log;
+use ic_cdk::management_canister::{install_code, CanisterInstallMode, InstallCodeArgs};
+use ic_govmind_types::{
+    dao::ChainType,
+    icrc::{LedgerArgument, LedgerUpgrade, LedgerUpgradeArg, UpgradeArgs},
+    nat_to_u64,
+};
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+
+use crate::{
+    airdrop,
+    distribution::base_token_ledger,
+    ic_log::INFO,
+    services::token_icrc1::TokenICRC1,
+    store,
+    utils::{current_time_secs, sha256},
+    vesting, ICRC1_WASM,
+};
+
+const LOGO_KEY: &str = "icrc1:logo";
+// Reported by the ledger from its own settings rather than stored as metadata.
+const LEDGER_METADATA_KEYS: [&str; 5] = [
+    "icrc1:name",
+    "icrc1:symbol",
+    "icrc1:decimals",
+    "icrc1:fee",
+    "icrc1:max_memo_length",
+];
+
+/// Upgrades the base token's ledger, changing the settings given in `arg`. Returns the id of the
+/// recorded upgrade.
+pub async fn upgrade_ledger(arg: LedgerUpgradeArg) -> Result<u64, String> {
+    let ledger = base_token_ledger()?;
+
+    let mut updates = arg.metadata.unwrap_or_default();
+    if let Some(logo) = arg.logo {
+        updates.push((LOGO_KEY.to_string(), logo));
+    }
+    let metadata_keys: Vec<String> = updates.iter().map(|(key, _)| key.clone()).collect();
+    let metadata = match updates.is_empty() {
+        true => None,
+        false => Some(merge_metadata(&ledger, updates).await?),
+    };
+
+    let wasm_module = arg
+        .wasm_module
+        .map(|wasm| wasm.into_vec())
+        .unwrap_or_else(|| ICRC1_WASM.to_vec());
+    let wasm_hash = hex::encode(sha256(&wasm_module));
+    let upgrade_args = UpgradeArgs {
+        token_symbol: None,
+        transfer_fee: arg.transfer_fee.clone(),
+        metadata,
+        maximum_number_of_accounts: None,
+        accounts_overflow_trim_quantity: None,
+        change_fee_collector: arg.change_fee_collector.clone(),
+        max_memo_length: arg.max_memo_length,
+        token_name: None,
+        feature_flags: None,
+    };
+    let ledger_arg = Encode!(&LedgerArgument::Upgrade(Some(upgrade_args)))
+        .map_err(|e| format!("Failed to encode the ledger upgrade: {:?}", e))?;
+
+    install_code(&InstallCodeArgs {
+        mode: CanisterInstallMode::Upgrade(None),
+        canister_id: ledger.principal,
+        wasm_module,
+        arg: ledger_arg,
+    })
+    .await
+    .map_err(|e| format!("Failed to upgrade ledger {}: {:?}", ledger.principal, e))?;
+
+    if let Some(transfer_fee) = &arg.transfer_fee {
+        update_token_fee(nat_to_u64(transfer_fee));
+        vesting::reprice_fees(transfer_fee, arg.proposal_id, current_time_secs());
+        airdrop::reprice_fees(transfer_fee, arg.proposal_id);
+    }
+    let id = store::state::record_ledger_upgrade(LedgerUpgrade {
+        id: 0,
+        ledger_id: ledger.principal,
+        proposal_id: arg.proposal_id,
+        wasm_hash,
+        transfer_fee: arg.transfer_fee,
+        metadata_keys,
+        change_fee_collector: arg.change_fee_collector,
+        max_memo_length: arg.max_memo_length,
+        upgraded_at: current_time_secs(),
+    });
+    store::state::save();
+    Ok(id)
+}
+
+/// The ledger replaces its whole metadata on upgrade, so updates are applied to the entries it
+/// currently stores.
+async fn merge_metadata(
+    ledger: &TokenICRC1,
+    updates: Vec<(String, MetadataValue)>,
+) -> Result<Vec<(String, MetadataValue)>, String> {
+    let mut metadata: Vec<(String, MetadataValue)> = ledger
+        .icrc1_metadata()
+        .await?
+        .into_iter()
+        .filter(|(key, _)| !LEDGER_METADATA_KEYS.contains(&key.as_str()))
+        .collect();
+
+    for (key, value) in updates {
+        if LEDGER_METADATA_KEYS.contains(&key.as_str()) {
+            return Err(format!("Metadata {} is managed by the ledger", key));
+        }
+        metadata.retain(|(existing, _)| *existing != key);
+        metadata.push((key, value));
+    }
+    Ok(metadata)
+}
+
+fn update_token_fee(fee: u64) {
+    let Some(dao) = store::state::get_dao_info() else {
+        return;
+    };
+    let token_name = dao.base_token.name;
+    let result = store::state::update_chain_config(ChainType::InternetComputer, |chain| {
+        if let Some(token) = chain
+            .supported_tokens
+            .iter_mut()
+            .find(|token| token.token_name == token_name)
+        {
+            token.fee = fee;
+        }
+    });
+    if let Err(e) = result {
+        log!(INFO, "Failed to update the fee of {}: {}", token_name, e);
+    }
+}
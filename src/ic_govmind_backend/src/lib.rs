@@ -1,16 +1,29 @@
+pub mod airdrop;
 pub mod api_cycles;
+pub mod base_token;
+pub mod bridge;
+pub mod deposits;
+pub mod distribution;
+pub mod emission;
 pub mod ic_log;
 pub mod chain;
 pub mod guards;
+pub mod history;
 pub mod init;
+pub mod ledger;
+pub mod oracle;
 pub mod query_api;
+pub mod rewards;
 pub mod services;
 pub mod signer;
+pub mod staking;
 pub mod store;
+pub mod supply;
 pub mod timer;
 pub mod types;
 pub mod update_api;
 pub mod utils;
+pub mod vesting;
 pub mod wallet;
 pub mod candid_file_generator;
 
@@ -18,13 +31,7 @@ pub const ICRC1_WASM: &[u8] = std::include_bytes!("../../icrc1_ledger/icrc1-ledg
 pub const BITCOIN_NETWORK: ic_cdk::bitcoin_canister::Network =
     ic_cdk::bitcoin_canister::Network::Testnet;
 
-const ETH_DEFAULT_GAS_PRICE: u64 = 10_000_000_000;
-const ETH_CREATE_TOKEN_GAS: u64 = 65000;
 const ETH_TRANSFER_GAS: u64 = 21000;
-const ETH_ERC20_TRANSFER_GAS: u64 = 85000;
 const ETH_ERC20_APPROVE_GAS: u64 = 65000;
+const ETH_CKERC20_DEPOSIT_GAS: u64 = 150000;
 const ETH_CREATE_TOKEN_CONTRACT: &str = "0xEc7C877BB038Ac9014c20bf4363217e253094E89";
-const WEB3_URL: &str = "<RPC-URL>";
-const ECDSA_SIGN_CYCLES: u64 = 3 * 10_000_000_000;
-
-ic_cdk::export_candid!();
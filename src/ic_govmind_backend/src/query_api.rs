@@ -1,13 +1,30 @@
-use ic_cdk::query;
+use candid::Principal;
+use ic_cdk::{
+    management_canister::{HttpRequestResult, TransformArgs},
+    query,
+};
 
 use crate::{
-    store::{self, proposals, state, State},
-    types::Addresses,
+    distribution::{self, subaccount_account},
+    emission, history, rewards, staking,
+    store::{self, approvals, proposals, state, transactions, State},
+    types::{Addresses, Erc20Approval},
+    utils::current_time_secs,
+    vesting,
 };
 use ic_govmind_types::{
-    chain::BlockchainConfig,
-    dao::{ChainType, Committee, Dao, DistributionRecord, Proposal},
+    chain::{BlockchainConfig, ChainConfigChange, EvmDeposit, EvmTransaction, RpcProviderHealth},
+    cketh::BridgeOperation,
+    dao::{
+        Airdrop, ChainType, Committee, Dao, DistributionPayout, DistributionRecipient,
+        DistributionRecord, DistributionRecordFilter, DistributionTotal, EvmTokenDeployment,
+        Proposal, RewardEvent, StakePositionStatus, StakingConfig, SupplyChange, SupplyProjection,
+        VestingGrantStatus, VotingRewardsConfig, REWARDS_SUBACCOUNT,
+    },
+    icrc::LedgerUpgrade,
+    price::{TokenPrice, TreasuryValuation},
 };
+use icrc_ledger_types::icrc1::account::Account;
 
 #[query]
 pub fn get_state() -> State {
@@ -19,6 +36,11 @@ fn dao_info() -> Option<Dao> {
     state::get_dao_info()
 }
 
+#[query]
+fn get_evm_token_deployment() -> Option<EvmTokenDeployment> {
+    state::get_base_token_deployment()
+}
+
 #[query]
 fn get_all_proposals() -> Vec<Proposal> {
     proposals::get_all_proposals()
@@ -45,11 +67,204 @@ pub fn get_chain_config_by_type(chain_type: ChainType) -> Option<BlockchainConfi
     store::state::get_chain_config(&chain_type)
 }
 
+/// Call statistics of the RPC providers of one EVM chain, or of all of them.
+#[query]
+pub fn get_rpc_provider_health(chain_type: Option<ChainType>) -> Vec<RpcProviderHealth> {
+    match chain_type {
+        Some(chain_type) => match store::state::get_chain_id(&chain_type) {
+            Some(chain_id) => store::rpc_health::list(Some(chain_id)),
+            None => vec![],
+        },
+        None => store::rpc_health::list(None),
+    }
+}
+
+#[query]
+pub fn list_chain_config_changes(start: u64, limit: u64) -> Vec<ChainConfigChange> {
+    store::state::list_chain_config_changes(start, limit as usize)
+}
+
 #[query]
 pub fn list_distribution_records(start: u64, limit: u64) -> Vec<(u64, DistributionRecord)> {
     store::distribution::list_distribution_records(start, limit as usize)
 }
 
+/// Parses and checks a distribution recipient, e.g. a key of `initial_distribution`.
+#[query]
+pub fn parse_distribution_recipient(recipient: String) -> Result<DistributionRecipient, String> {
+    let base_chain = state::get_dao_info()
+        .map(|dao| dao.base_token.token_location.chain)
+        .unwrap_or_default();
+    distribution::parse_recipient(&recipient, &base_chain)
+}
+
+/// Distribution records matching `filter`, from id `start` on.
+#[query]
+pub fn filter_distribution_records(
+    filter: DistributionRecordFilter,
+    start: u64,
+    limit: u64,
+) -> Vec<(u64, DistributionRecord)> {
+    history::list_records(&filter, start, limit as usize)
+}
+
+/// What each recipient received over the distribution records matching `filter`.
+#[query]
+pub fn get_distribution_totals(filter: DistributionRecordFilter) -> Vec<DistributionTotal> {
+    history::totals(&filter)
+}
+
+#[query]
+pub fn list_distribution_payouts(start: u64, limit: u64) -> Vec<DistributionPayout> {
+    store::distribution::list_payouts(start, limit as usize)
+}
+
+/// Projects the base token's emission over the next `rounds` rounds, at most 1000.
+#[query]
+pub fn project_token_supply(rounds: u64) -> Vec<SupplyProjection> {
+    let Some(dao) = state::get_dao_info() else {
+        return vec![];
+    };
+    match &dao.base_token.distribution_model {
+        Some(model) => emission::project_supply(
+            model,
+            &dao.base_token.total_supply,
+            current_time_secs(),
+            rounds.min(1_000),
+        ),
+        None => vec![],
+    }
+}
+
+#[query]
+pub fn list_supply_changes(start: u64, limit: u64) -> Vec<SupplyChange> {
+    state::list_supply_changes(start, limit as usize)
+}
+
+#[query]
+pub fn list_ledger_upgrades(start: u64, limit: u64) -> Vec<LedgerUpgrade> {
+    state::list_ledger_upgrades(start, limit as usize)
+}
+
+/// The account where the caller sends the tokens they want to stake.
+#[query]
+pub fn get_staking_account() -> Account {
+    staking::staking_account(ic_cdk::api::msg_caller())
+}
+
+#[query]
+pub fn get_staking_config() -> StakingConfig {
+    store::staking::get_config()
+}
+
+/// Stake positions of `owner`, or of every staker.
+#[query]
+pub fn list_stake_positions(owner: Option<Principal>) -> Vec<StakePositionStatus> {
+    let now = current_time_secs();
+    store::staking::list_positions(owner)
+        .iter()
+        .map(|position| staking::position_status(position, now))
+        .collect()
+}
+
+#[query]
+pub fn get_voting_power(principal: Principal) -> u64 {
+    let now = current_time_secs();
+    staking::vote_weight(principal, now, now)
+}
+
+/// The rewards pool paying voting rewards, funded by transfers from the treasury.
+#[query]
+pub fn get_rewards_pool_account() -> Account {
+    subaccount_account(REWARDS_SUBACCOUNT)
+}
+
+#[query]
+pub fn get_voting_rewards_config() -> Option<VotingRewardsConfig> {
+    store::rewards::get_config()
+}
+
+#[query]
+pub fn list_reward_events(start: u64, limit: u64) -> Vec<RewardEvent> {
+    store::rewards::list_events(start, limit as usize)
+}
+
+/// Voting rewards paid or owed to `member`.
+#[query]
+pub fn list_voting_rewards(member: Principal) -> Vec<DistributionPayout> {
+    rewards::member_rewards(member)
+}
+
+#[query]
+pub fn get_vesting_grant(id: u64) -> Option<VestingGrantStatus> {
+    let now = current_time_secs();
+    store::vesting::get_grant(id).map(|grant| vesting::grant_status(&grant, now))
+}
+
+#[query]
+pub fn list_vesting_grants() -> Vec<VestingGrantStatus> {
+    let now = current_time_secs();
+    store::vesting::list_grants()
+        .iter()
+        .map(|grant| vesting::grant_status(grant, now))
+        .collect()
+}
+
+#[query]
+pub fn get_airdrop(id: u64) -> Option<Airdrop> {
+    store::airdrops::get_airdrop(id)
+}
+
+#[query]
+pub fn list_airdrops() -> Vec<Airdrop> {
+    store::airdrops::list_airdrops()
+}
+
+#[query]
+pub fn get_evm_transaction(id: u64) -> Option<EvmTransaction> {
+    transactions::get_transaction(id)
+}
+
+#[query]
+pub fn get_evm_transaction_by_hash(tx_hash: String) -> Option<EvmTransaction> {
+    transactions::find_transaction_by_hash(&tx_hash)
+}
+
+#[query]
+pub fn list_evm_transactions(start: u64, limit: u64) -> Vec<EvmTransaction> {
+    transactions::list_transactions(start, limit as usize)
+}
+
+#[query]
+pub fn get_proposal_evm_transactions(proposal_id: u64) -> Vec<EvmTransaction> {
+    transactions::list_transactions_by_proposal(proposal_id)
+}
+
+#[query]
+pub fn list_evm_deposits(start: u64, limit: u64) -> Vec<EvmDeposit> {
+    store::deposits::list_deposits(start, limit as usize)
+}
+
+#[query]
+pub fn list_member_evm_deposits(user_id: String) -> Vec<EvmDeposit> {
+    store::deposits::list_deposits_by_member(&user_id)
+}
+
+#[query]
+pub fn get_bridge_operation(id: u64) -> Option<BridgeOperation> {
+    store::bridge::get_operation(id)
+}
+
+#[query]
+pub fn list_bridge_operations(start: u64, limit: u64) -> Vec<BridgeOperation> {
+    store::bridge::list_operations(start, limit as usize)
+}
+
+#[query]
+pub fn list_erc20_approvals() -> Vec<Erc20Approval> {
+    approvals::list_approvals()
+}
+
 #[query]
 pub fn get_active_committees() -> Vec<Committee> {
     state::with(|s| {
@@ -65,6 +280,21 @@ pub fn get_active_committees() -> Vec<Committee> {
     })
 }
 
+#[query]
+pub fn get_token_price(symbol: String) -> Option<TokenPrice> {
+    store::prices::get_price(&symbol)
+}
+
+#[query]
+pub fn get_treasury_valuation() -> TreasuryValuation {
+    crate::oracle::treasury_valuation()
+}
+
+#[query(hidden = true)]
+fn transform_price_response(args: TransformArgs) -> HttpRequestResult {
+    crate::oracle::transform_price_response(args)
+}
+
 #[query(hidden = true)]
 fn http_request(req: ic_http_types::HttpRequest) -> ic_http_types::HttpResponse {
     if ic_cdk::api::data_certificate().is_none() {
@@ -72,6 +302,8 @@ fn http_request(req: ic_http_types::HttpRequest) -> ic_http_types::HttpResponse
     }
     if req.path() == "/logs" {
         crate::ic_log::do_reply(req)
+    } else if req.path() == "/distributions.csv" {
+        history::do_reply(req)
     } else {
         ic_http_types::HttpResponseBuilder::not_found().build()
     }
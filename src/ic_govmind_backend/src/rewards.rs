@@ -0,0 +1,216 @@
+use candid::{Nat, Principal};
+use ic_canister_log::log;
+use ic_govmind_types::dao::{
+    ChainType, DistributionPayout, DistributionType, ProposalStatus, RewardEvent,
+    REWARDS_SUBACCOUNT,
+};
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+
+use crate::{distribution::queue_payout, ic_log::INFO, store};
+
+/// Settles the reward periods that ended by `now`: the votes cast on proposals finalized since
+/// the last reward event share the periods' rewards, plus whatever the last event rolled over.
+/// Missed periods are settled together, as one event. The pool pays the ledger fee of every
+/// payout out of the rewards, and votes cast before rewards were enabled earn none.
+pub fn plan_rewards(now: u64) {
+    let Some(config) = store::rewards::get_config() else {
+        return;
+    };
+    if config.period_secs == 0 {
+        return;
+    }
+    let last_event = store::rewards::last_event();
+    let last_end = last_event
+        .as_ref()
+        .map_or(config.start_at, |event| event.end_timestamp);
+    if now < last_end.saturating_add(config.period_secs) {
+        return;
+    }
+    let periods = (now - last_end) / config.period_secs;
+    let round = last_event.as_ref().map_or(1, |event| event.round + 1);
+    let rolled_over = last_event
+        .map(|event| event.rolled_over)
+        .unwrap_or_default();
+    let amount = config.amount_per_period * Nat::from(periods) + rolled_over;
+
+    let proposals: Vec<_> = store::proposals::get_all_proposals()
+        .into_iter()
+        .filter(|proposal| {
+            proposal.reward_event_round.is_none()
+                && matches!(
+                    proposal.status,
+                    ProposalStatus::Passed | ProposalStatus::Rejected | ProposalStatus::Executed
+                )
+        })
+        .collect();
+    // Votes are cast by principal; older votes without one cannot be paid.
+    let since = store::rewards::rewarded_votes_since();
+    let votes: Vec<(String, u64)> = proposals
+        .iter()
+        .flat_map(|proposal| &proposal.votes)
+        .filter(|vote| vote.voted_at >= since)
+        .filter_map(|vote| {
+            let voter = Principal::from_text(&vote.voter_id).ok()?;
+            Some((Account::from(voter).to_string(), vote.weight))
+        })
+        .collect();
+
+    let fee = base_token_fee();
+    let (shares, total_weight) = split_rewards(&votes, &amount, &fee);
+    let distributed = shares.iter().fold(Nat::from(0u64), |sum, (_, share)| {
+        sum + share.clone() + fee.clone()
+    });
+    for (recipient, share) in shares {
+        queue_payout(
+            reward_payout_id(round, &recipient),
+            DistributionType::VotingReward,
+            recipient,
+            share,
+            Some(REWARDS_SUBACCOUNT),
+        );
+    }
+
+    let settled_proposals: Vec<u64> = proposals.iter().map(|proposal| proposal.id).collect();
+    store::proposals::set_reward_event_round(&settled_proposals, round);
+    log!(
+        INFO,
+        "Reward round {} settled {} proposals, distributing {} of {}",
+        round,
+        settled_proposals.len(),
+        distributed,
+        amount
+    );
+    store::rewards::add_event(RewardEvent {
+        round,
+        end_timestamp: last_end + periods * config.period_secs,
+        settled_proposals,
+        total_weight: total_weight.min(u64::MAX as u128) as u64,
+        rolled_over: amount - distributed.clone(),
+        distributed,
+    });
+}
+
+/// The voting rewards paid or owed to `owner`.
+pub fn member_rewards(owner: Principal) -> Vec<DistributionPayout> {
+    store::rewards::list_payouts(&Account::from(owner).to_string())
+}
+
+/// The base token's ledger fee, as recorded in the Internet Computer chain config.
+fn base_token_fee() -> Nat {
+    let Some(dao) = store::state::get_dao_info() else {
+        return Nat::from(0u64);
+    };
+    store::state::get_chain_config(&ChainType::InternetComputer)
+        .and_then(|chain| {
+            chain
+                .supported_tokens
+                .into_iter()
+                .find(|token| token.token_name == dao.base_token.name)
+        })
+        .map_or(Nat::from(0u64), |token| Nat::from(token.fee))
+}
+
+/// Splits `amount` among voters in proportion to the total weight each cast, less the `fee` of
+/// paying each share. Returns the shares and the total weight; the rounding remainder and shares
+/// too small to pay their fee are not handed out.
+fn split_rewards(votes: &[(String, u64)], amount: &Nat, fee: &Nat) -> (Vec<(String, Nat)>, u128) {
+    let mut weights: BTreeMap<&str, u128> = BTreeMap::new();
+    for (voter, weight) in votes {
+        *weights.entry(voter.as_str()).or_default() += *weight as u128;
+    }
+    let total_weight: u128 = weights.values().sum();
+    if total_weight == 0 {
+        return (vec![], 0);
+    }
+
+    let shares = weights
+        .into_iter()
+        .map(|(voter, weight)| {
+            (
+                voter.to_string(),
+                amount.clone() * Nat::from(weight) / Nat::from(total_weight),
+            )
+        })
+        .filter(|(_, share)| share > fee)
+        .map(|(voter, share)| (voter, share - fee.clone()))
+        .collect();
+    (shares, total_weight)
+}
+
+fn reward_payout_id(round: u64, recipient: &str) -> String {
+    format!("voting_reward:{}:{}", round, recipient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_govmind_types::dao::DistributionStatus;
+
+    #[test]
+    fn splits_by_the_weight_cast() {
+        let votes = vec![
+            ("a".to_string(), 1),
+            ("b".to_string(), 2),
+            ("a".to_string(), 3),
+        ];
+        let (shares, total_weight) = split_rewards(&votes, &Nat::from(1_000u64), &Nat::from(0u64));
+        assert_eq!(total_weight, 6);
+        assert_eq!(
+            shares,
+            vec![
+                ("a".to_string(), Nat::from(666u64)),
+                ("b".to_string(), Nat::from(333u64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_is_split_without_votes() {
+        let (shares, total_weight) =
+            split_rewards(&[("a".to_string(), 0)], &Nat::from(10u64), &Nat::from(0u64));
+        assert!(shares.is_empty());
+        assert_eq!(total_weight, 0);
+    }
+
+    #[test]
+    fn shares_pay_their_ledger_fee() {
+        let votes = vec![("a".to_string(), 99), ("b".to_string(), 1)];
+        let (shares, _) = split_rewards(&votes, &Nat::from(1_000u64), &Nat::from(10u64));
+        assert_eq!(shares, vec![("a".to_string(), Nat::from(980u64))]);
+    }
+
+    #[test]
+    fn member_rewards_are_listed_by_recipient() {
+        let member = Principal::from_slice(&[1]);
+        let other = Principal::from_slice(&[2]);
+        for (id, owner, distribution_type) in [
+            ("voting_reward:1:a", member, DistributionType::VotingReward),
+            ("voting_reward:1:b", other, DistributionType::VotingReward),
+            ("unstake:1", member, DistributionType::Unstake),
+        ] {
+            store::distribution::add_payout(DistributionPayout {
+                id: id.to_string(),
+                distribution_type,
+                recipient: Account::from(owner).to_string(),
+                amount: Nat::from(1u64),
+                from_subaccount: None,
+                memo: vec![],
+                created_at_time: None,
+                status: DistributionStatus::Pending,
+                attempts: 0,
+                block_index: None,
+                last_error: None,
+                scheduled_at: 0,
+                updated_at: 0,
+                evm_tx_hash: None,
+            });
+        }
+
+        let ids: Vec<String> = member_rewards(member)
+            .into_iter()
+            .map(|payout| payout.id)
+            .collect();
+        assert_eq!(ids, vec!["voting_reward:1:a".to_string()]);
+    }
+}
@@ -0,0 +1,62 @@
+use candid::Principal;
+use ic_cdk::call::Call;
+use ic_govmind_types::cketh::{
+    MinterInfo, RetrieveErc20Request, RetrieveEthRequest, RetrieveEthStatus, WithdrawErc20Arg,
+    WithdrawErc20Error, WithdrawalArg, WithdrawalError,
+};
+
+#[derive(Clone, Debug)]
+pub struct CkEthMinter {
+    pub principal: Principal,
+}
+
+impl CkEthMinter {
+    pub fn new(principal: Principal) -> Self {
+        Self { principal }
+    }
+
+    /// Burns ckETH approved to the minter and queues an ETH transfer to `arg.recipient`.
+    pub async fn withdraw_eth(
+        &self,
+        arg: WithdrawalArg,
+    ) -> Result<Result<RetrieveEthRequest, WithdrawalError>, String> {
+        match Call::unbounded_wait(self.principal, "withdraw_eth")
+            .with_arg(arg)
+            .await
+        {
+            Ok(res) => res.candid().map_err(|e| format!("Decoding error: {:?}", e)),
+            Err(e) => Err(format!("Call failed: {:?}", e)),
+        }
+    }
+
+    /// Burns a ckERC20 token, and ckETH for the transaction fee, and queues the ERC-20 transfer.
+    pub async fn withdraw_erc20(
+        &self,
+        arg: WithdrawErc20Arg,
+    ) -> Result<Result<RetrieveErc20Request, WithdrawErc20Error>, String> {
+        match Call::unbounded_wait(self.principal, "withdraw_erc20")
+            .with_arg(arg)
+            .await
+        {
+            Ok(res) => res.candid().map_err(|e| format!("Decoding error: {:?}", e)),
+            Err(e) => Err(format!("Call failed: {:?}", e)),
+        }
+    }
+
+    pub async fn retrieve_eth_status(&self, block_index: u64) -> Result<RetrieveEthStatus, String> {
+        match Call::bounded_wait(self.principal, "retrieve_eth_status")
+            .with_arg(block_index)
+            .await
+        {
+            Ok(res) => res.candid().map_err(|e| format!("Decoding error: {:?}", e)),
+            Err(e) => Err(format!("Call failed: {:?}", e)),
+        }
+    }
+
+    pub async fn get_minter_info(&self) -> Result<MinterInfo, String> {
+        match Call::bounded_wait(self.principal, "get_minter_info").await {
+            Ok(res) => res.candid().map_err(|e| format!("Decoding error: {:?}", e)),
+            Err(e) => Err(format!("Call failed: {:?}", e)),
+        }
+    }
+}
@@ -1,12 +1,16 @@
-use candid::Principal;
-use ethers_core::abi::{encode, Abi, Token};
+use candid::{Nat, Principal};
+use ethers_core::abi::{
+    encode,
+    token::{LenientTokenizer, Tokenizer},
+    Abi, Function, FunctionExt, ParamType, Token,
+};
 use ethers_core::types::{Address as EthersAddress, U256};
 use hex::decode;
 use serde_json::from_str;
 use std::str::FromStr;
 
 use crate::chain::ethereum::keccak256;
-use crate::utils::principal_to_eth_hex;
+use crate::utils::{nat_to_be_bytes32, principal_to_eth_hex};
 
 #[allow(dead_code)]
 pub const BALANCE_OF_ABI_JSON: &str = r#"
@@ -31,6 +35,49 @@ pub const BALANCE_OF_ABI_JSON: &str = r#"
     }]
 "#;
 
+pub const ALLOWANCE_ABI_JSON: &str = r#"
+    [{
+        "constant": true,
+        "inputs": [
+        {
+            "name": "_owner",
+            "type": "address"
+        },
+        {
+            "name": "_spender",
+            "type": "address"
+        }
+        ],
+        "name": "allowance",
+        "outputs": [
+        {
+            "name": "remaining",
+            "type": "uint256"
+        }
+        ],
+        "payable": false,
+        "stateMutability": "view",
+        "type": "function"
+    }]
+"#;
+
+pub const ERC20_METADATA_ABI_JSON: &str = r#"
+    [{
+        "inputs": [],
+        "name": "symbol",
+        "outputs": [{ "name": "", "type": "string" }],
+        "stateMutability": "view",
+        "type": "function"
+    },
+    {
+        "inputs": [],
+        "name": "decimals",
+        "outputs": [{ "name": "", "type": "uint8" }],
+        "stateMutability": "view",
+        "type": "function"
+    }]
+"#;
+
 #[allow(dead_code)]
 pub const CKETH_DEPOSIT_ABI_JSON: &str = r#"
     [{
@@ -82,11 +129,279 @@ pub const CREATE_TOKEN_ABI_JSON: &str = r#"
     }]
 "#;
 
+pub const ERC20_WRITE_ABI_JSON: &str = r#"
+    [{
+        "inputs": [
+            { "name": "to", "type": "address" },
+            { "name": "amount", "type": "uint256" }
+        ],
+        "name": "transfer",
+        "outputs": [{ "name": "", "type": "bool" }],
+        "stateMutability": "nonpayable",
+        "type": "function"
+    },
+    {
+        "inputs": [
+            { "name": "spender", "type": "address" },
+            { "name": "amount", "type": "uint256" }
+        ],
+        "name": "approve",
+        "outputs": [{ "name": "", "type": "bool" }],
+        "stateMutability": "nonpayable",
+        "type": "function"
+    },
+    {
+        "inputs": [
+            { "name": "from", "type": "address" },
+            { "name": "to", "type": "address" },
+            { "name": "amount", "type": "uint256" }
+        ],
+        "name": "transferFrom",
+        "outputs": [{ "name": "", "type": "bool" }],
+        "stateMutability": "nonpayable",
+        "type": "function"
+    }]
+"#;
+
+// Selectors of the `Error(string)` and `Panic(uint256)` revert payloads emitted by Solidity.
+const REVERT_ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const REVERT_PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
 pub fn parse_abi(abi_json: &str) -> Abi {
     from_str(abi_json).expect("Failed to parse ABI")
 }
 
-pub fn generate_erc20_transfer_data(recipient: &str, amount: u64) -> Result<Vec<u8>, String> {
+/// Parses a JSON ABI, accepting either a full ABI array or a single function fragment.
+pub fn try_parse_abi(abi_json: &str) -> Result<Abi, String> {
+    let abi_json = abi_json.trim();
+    let result = if abi_json.starts_with('{') {
+        from_str(&format!("[{}]", abi_json))
+    } else {
+        from_str(abi_json)
+    };
+    result.map_err(|e| format!("Invalid ABI: {}", e))
+}
+
+/// Finds a function by name, or by its full signature (e.g. `transfer(address,uint256)`)
+/// when the name is overloaded.
+pub fn find_function<'a>(abi: &'a Abi, function_name: &str) -> Result<&'a Function, String> {
+    match abi.functions_by_name(function_name).map(|v| &v[..]) {
+        Ok([f]) => Ok(f),
+        Ok(fs) => Err(format!(
+            "Found {} overloads of {}, pass one of: {}",
+            fs.len(),
+            function_name,
+            fs.iter()
+                .map(|f| f.abi_signature())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        Err(_) => abi
+            .functions()
+            .find(|f| function_name == f.abi_signature())
+            .ok_or_else(|| format!("Function {} not found in ABI", function_name)),
+    }
+}
+
+/// Encodes the calldata of `function`, parsing each argument against the type of its input.
+/// Arrays and tuples use the bracketed forms `[a,b]` and `(a,b)`.
+pub fn encode_function_call(function: &Function, args: &[String]) -> Result<Vec<u8>, String> {
+    if args.len() != function.inputs.len() {
+        return Err(format!(
+            "{} expects {} arguments, got {}",
+            function.abi_signature(),
+            function.inputs.len(),
+            args.len()
+        ));
+    }
+
+    let tokens = function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(param, value)| {
+            LenientTokenizer::tokenize(&param.kind, value)
+                .map_err(|e| format!("Invalid value for {} ({}): {}", param.name, param.kind, e))
+        })
+        .collect::<Result<Vec<Token>, String>>()?;
+
+    function
+        .encode_input(&tokens)
+        .map_err(|e| format!("Failed to encode {}: {}", function.abi_signature(), e))
+}
+
+/// Decodes the arguments of a call to `function` as `name(param=value, ...)`.
+pub fn describe_function_call(function: &Function, data: &[u8]) -> Result<String, String> {
+    let input = data
+        .get(4..)
+        .ok_or_else(|| "Call data is shorter than a selector".to_string())?;
+    let tokens = function
+        .decode_input(input)
+        .map_err(|e| format!("Failed to decode {}: {}", function.abi_signature(), e))?;
+
+    let args = function
+        .inputs
+        .iter()
+        .zip(&tokens)
+        .map(|(param, token)| match param.name.as_str() {
+            "" => format_token(token),
+            name => format!("{}={}", name, format_token(token)),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(format!("{}({})", function.name, args.join(", ")))
+}
+
+/// Formats a decoded ABI value the way block explorers show it.
+pub fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("{:#x}", address),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => format!("0x{}", hex::encode(bytes)),
+        Token::Int(value) => ethers_core::types::I256::from_raw(*value).to_string(),
+        Token::Uint(value) => value.to_string(),
+        Token::Bool(value) => value.to_string(),
+        Token::String(value) => format!("{:?}", value),
+        Token::FixedArray(tokens) | Token::Array(tokens) => format!(
+            "[{}]",
+            tokens
+                .iter()
+                .map(format_token)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Token::Tuple(tokens) => format!(
+            "({})",
+            tokens
+                .iter()
+                .map(format_token)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Formats a raw token amount with its decimals, e.g. `1500000` with 6 decimals as `1.5`.
+pub fn format_token_amount(amount: &Nat, decimals: u8) -> String {
+    let digits = amount.0.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    match fraction.trim_end_matches('0') {
+        "" => whole.to_string(),
+        fraction => format!("{}.{}", whole, fraction),
+    }
+}
+
+/// Decodes the return data of a reverted call: a reason string, a panic code or a custom error.
+pub fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+
+    let (selector, payload) = data.split_at(4);
+    if selector == REVERT_ERROR_SELECTOR {
+        match ethers_core::abi::decode(&[ParamType::String], payload)
+            .ok()?
+            .pop()?
+        {
+            Token::String(reason) => Some(reason),
+            _ => None,
+        }
+    } else if selector == REVERT_PANIC_SELECTOR {
+        match ethers_core::abi::decode(&[ParamType::Uint(256)], payload)
+            .ok()?
+            .pop()?
+        {
+            Token::Uint(code) => Some(format!("panic code {:#x}", code)),
+            _ => None,
+        }
+    } else {
+        Some(format!("custom error 0x{}", hex::encode(data)))
+    }
+}
+
+/// Extracts the return data of an `eth_call` response, or the decoded reason it reverted with.
+pub fn parse_call_response(resp: &str) -> Result<String, String> {
+    let val: serde_json::Value =
+        serde_json::from_str(resp).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    if let Some(result) = val.get("result").and_then(|r| r.as_str()) {
+        return Ok(result.to_string());
+    }
+
+    let error = val
+        .get("error")
+        .ok_or_else(|| format!("Missing 'result' field in RPC response: {}", resp))?;
+    let message = error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .unwrap_or("execution reverted");
+    let reason = error
+        .get("data")
+        .and_then(|d| d.as_str())
+        .and_then(|d| decode(d.trim_start_matches("0x")).ok())
+        .and_then(|data| decode_revert_reason(&data));
+
+    Err(match reason {
+        Some(reason) => format!("execution reverted: {}", reason),
+        None => message.to_string(),
+    })
+}
+
+/// Decodes the return data of `symbol()`. Some early tokens, e.g. MKR, return a `bytes32`.
+pub fn decode_erc20_symbol(data: &[u8]) -> Option<String> {
+    if let Ok(Token::String(symbol)) =
+        ethers_core::abi::decode(&[ParamType::String], data).map(|mut tokens| tokens.remove(0))
+    {
+        return Some(symbol);
+    }
+
+    let bytes = data.get(..32)?;
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(32);
+    String::from_utf8(bytes[..end].to_vec())
+        .ok()
+        .filter(|symbol| !symbol.is_empty())
+}
+
+pub fn decode_erc20_decimals(data: &[u8]) -> Option<u8> {
+    match ethers_core::abi::decode(&[ParamType::Uint(8)], data)
+        .ok()?
+        .pop()?
+    {
+        Token::Uint(decimals) if decimals <= U256::from(u8::MAX) => Some(decimals.as_u32() as u8),
+        _ => None,
+    }
+}
+
+pub fn nat_to_u256(amount: &Nat) -> Result<U256, String> {
+    Ok(U256::from_big_endian(&nat_to_be_bytes32(amount)?))
+}
+
+pub fn u256_to_nat(value: U256) -> Nat {
+    Nat::from_str(&value.to_string()).expect("U256 is a valid decimal number")
+}
+
+/// Parses a 0x-prefixed hex quantity or `uint256` return value of any size.
+pub fn parse_hex_nat(raw: &str) -> Result<Nat, String> {
+    let hex_part = raw
+        .trim()
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("Invalid hex quantity (missing 0x): {}", raw))?;
+
+    if hex_part.is_empty() {
+        return Ok(Nat::from(0u64));
+    }
+
+    U256::from_str_radix(hex_part, 16)
+        .map(u256_to_nat)
+        .map_err(|e| format!("Failed to parse hex quantity '{}': {:?}", raw, e))
+}
+
+pub fn generate_erc20_transfer_data(recipient: &str, amount: &Nat) -> Result<Vec<u8>, String> {
     // Define the function signature hash for "transfer(address,uint256)"
     let function_signature = keccak256(b"transfer(address,uint256)")[..4].to_vec();
 
@@ -100,7 +415,7 @@ pub fn generate_erc20_transfer_data(recipient: &str, amount: u64) -> Result<Vec<
     }
     let ethers_recipient_address = EthersAddress::from_slice(&decoded_address);
 
-    let amount_u256 = U256::from(amount);
+    let amount_u256 = nat_to_u256(amount)?;
 
     // Encode the function call using ethers ABI encode function
     let data = [
@@ -115,7 +430,7 @@ pub fn generate_erc20_transfer_data(recipient: &str, amount: u64) -> Result<Vec<
     Ok(data)
 }
 
-pub fn generate_erc20_approve(spender: &str, amount: u64) -> Result<Vec<u8>, String> {
+pub fn generate_erc20_approve(spender: &str, amount: &Nat) -> Result<Vec<u8>, String> {
     let function_signature = keccak256(b"approve(address,uint256)")[..4].to_vec();
 
     let trimmed_spender = spender.strip_prefix("0x").unwrap_or(spender);
@@ -126,7 +441,7 @@ pub fn generate_erc20_approve(spender: &str, amount: u64) -> Result<Vec<u8>, Str
     }
     let ethers_spender_address = EthersAddress::from_slice(&decoded_address);
 
-    let amount_u256 = U256::from(amount);
+    let amount_u256 = nat_to_u256(amount)?;
 
     let data = [
         function_signature,
@@ -182,7 +497,7 @@ pub fn generate_cketh_deposit_eth(
 /// Constructs the calldata for calling `depositErc20(address,uint256,bytes32,bytes32)`
 pub fn generate_cketh_deposit_erc20(
     erc20_address: EthersAddress,
-    amount: u128,
+    amount: &Nat,
     principal: Principal,
     subaccount: [u8; 32],
 ) -> Result<Vec<u8>, String> {
@@ -193,7 +508,7 @@ pub fn generate_cketh_deposit_erc20(
 
     let tokens = vec![
         Token::Address(erc20_address),
-        Token::Uint(amount.into()),
+        Token::Uint(nat_to_u256(amount)?),
         Token::FixedBytes(fixed_principal_bytes.to_vec()),
         Token::FixedBytes(subaccount.to_vec()),
     ];
@@ -208,32 +523,62 @@ pub fn generate_cketh_deposit_erc20(
 pub fn generate_create_token(
     name: String,
     symbol: String,
-    supply: u64,
+    supply: &Nat,
     owner: String,
 ) -> Result<Vec<u8>, String> {
     // Function signature for `createToken(string,string,uint256,address)`
     let function_signature = keccak256(b"createToken(string,string,uint256,address)")[..4].to_vec();
 
-    let name_token = Token::String(name);
-    let symbol_token = Token::String(symbol);
-    let supply_token = Token::Uint(U256::from(supply));
-    let owner_address = match EthersAddress::from_str(&owner) {
-        Ok(addr) => addr,
-        Err(_) => return Err("Invalid owner address".to_string()),
-    };
-
-    let owner_token = Token::Address(owner_address);
-
     // ABI encode the tokens and concatenate with function selector
     let data = [
         function_signature,
-        encode(&[name_token, symbol_token, supply_token, owner_token]),
+        encode_token_args(name, symbol, supply, owner)?,
     ]
     .concat();
 
     Ok(data)
 }
 
+/// Constructs the init code deploying `bytecode` with a `(string,string,uint256,address)` constructor
+pub fn generate_token_deployment(
+    bytecode: &str,
+    name: String,
+    symbol: String,
+    supply: &Nat,
+    owner: String,
+) -> Result<Vec<u8>, String> {
+    let creation_code = decode(bytecode.strip_prefix("0x").unwrap_or(bytecode))
+        .map_err(|e| format!("Invalid hex in bytecode: {:?}", e))?;
+    if creation_code.is_empty() {
+        return Err("Bytecode is empty".to_string());
+    }
+
+    Ok([
+        creation_code,
+        encode_token_args(name, symbol, supply, owner)?,
+    ]
+    .concat())
+}
+
+fn encode_token_args(
+    name: String,
+    symbol: String,
+    supply: &Nat,
+    owner: String,
+) -> Result<Vec<u8>, String> {
+    let owner_address = match EthersAddress::from_str(&owner) {
+        Ok(addr) => addr,
+        Err(_) => return Err("Invalid owner address".to_string()),
+    };
+
+    Ok(encode(&[
+        Token::String(name),
+        Token::String(symbol),
+        Token::Uint(nat_to_u256(supply)?),
+        Token::Address(owner_address),
+    ]))
+}
+
 pub fn parse_nonce_hex(raw: &str) -> Result<u128, String> {
     let trimmed = raw.trim();
 
@@ -251,6 +596,20 @@ pub fn parse_nonce_hex(raw: &str) -> Result<u128, String> {
         .map_err(|e| format!("Failed to parse on-chain nonce '{}': {}", raw, e))
 }
 
+pub fn parse_hex_quantity(raw: &str) -> Result<u128, String> {
+    let trimmed = raw.trim();
+    let hex_part = trimmed
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("Invalid hex quantity (missing 0x): {}", raw))?;
+
+    if hex_part.is_empty() {
+        return Ok(0u128);
+    }
+
+    u128::from_str_radix(hex_part, 16)
+        .map_err(|e| format!("Failed to parse hex quantity '{}': {}", raw, e))
+}
+
 pub fn extract_json_result(resp: &str) -> Result<String, String> {
     match serde_json::from_str::<serde_json::Value>(resp) {
         Ok(val) => {
@@ -263,3 +622,73 @@ pub fn extract_json_result(resp: &str) -> Result<String, String> {
         Err(e) => Err(format!("Failed to parse JSON: {}", e)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_token_amount() {
+        assert_eq!(format_token_amount(&Nat::from(500_000_000u64), 6), "500");
+        assert_eq!(format_token_amount(&Nat::from(1_500_000u64), 6), "1.5");
+        assert_eq!(format_token_amount(&Nat::from(42u64), 6), "0.000042");
+        assert_eq!(format_token_amount(&Nat::from(0u64), 18), "0");
+        assert_eq!(format_token_amount(&Nat::from(7u64), 0), "7");
+    }
+
+    #[test]
+    fn test_decode_revert_reason() {
+        let mut data = REVERT_ERROR_SELECTOR.to_vec();
+        data.extend(encode(&[Token::String(
+            "ERC20: transfer amount exceeds balance".to_string(),
+        )]));
+        assert_eq!(
+            decode_revert_reason(&data).as_deref(),
+            Some("ERC20: transfer amount exceeds balance")
+        );
+
+        let mut data = REVERT_PANIC_SELECTOR.to_vec();
+        data.extend(encode(&[Token::Uint(U256::from(0x11))]));
+        assert_eq!(
+            decode_revert_reason(&data).as_deref(),
+            Some("panic code 0x11")
+        );
+    }
+
+    #[test]
+    fn token_deployment_appends_the_constructor_args_to_the_bytecode() {
+        let owner = "0x00000000000000000000000000000000000000da".to_string();
+        let supply = Nat::from(1_000u64);
+        let init_code = generate_token_deployment(
+            "0x6080",
+            "Gov".to_string(),
+            "GOV".to_string(),
+            &supply,
+            owner.clone(),
+        )
+        .unwrap();
+        assert_eq!(init_code[..2], [0x60, 0x80]);
+        assert_eq!(
+            init_code[2..],
+            encode_token_args("Gov".to_string(), "GOV".to_string(), &supply, owner.clone())
+                .unwrap()
+        );
+
+        assert!(generate_token_deployment(
+            "0x",
+            "Gov".to_string(),
+            "GOV".to_string(),
+            &supply,
+            owner.clone()
+        )
+        .is_err());
+        assert!(generate_token_deployment(
+            "0x6080",
+            "Gov".to_string(),
+            "GOV".to_string(),
+            &supply,
+            "not an address".to_string()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use candid::Nat;
+use evm_rpc_types::{BlockTag, FeeHistory, FeeHistoryArgs, MultiRpcResult, Nat256};
+use ic_govmind_types::{
+    chain::{BlockchainConfig, FeeConfig},
+    constants::EVM_RPC_CANISTER_ID,
+};
+
+use crate::{
+    services::{
+        evm_abi::{extract_json_result, parse_hex_quantity},
+        evm_providers,
+        evm_service::EvmService,
+    },
+    utils::nat_to_u128,
+};
+
+/// Fee parameters of an EIP-1559 (type 2) transaction, in wei per gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Eip1559Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+fn nat256_to_u128(value: &Nat256) -> Result<u128, String> {
+    nat_to_u128(value.as_ref()).ok_or_else(|| format!("Fee value {} does not fit in u128", value))
+}
+
+/// Derives the EIP-1559 fees from an `eth_feeHistory` response.
+///
+/// The tip is the median of the requested reward percentile over the returned
+/// blocks, bounded by the chain's priority fee floor and cap. The max fee leaves
+/// room for the base fee to double before the transaction becomes unincludable,
+/// and is clamped to the chain's max fee cap.
+pub fn estimate_eip1559_fees(
+    fee_history: &FeeHistory,
+    fee_config: &FeeConfig,
+) -> Result<Eip1559Fees, String> {
+    let base_fee_per_gas = match fee_history.base_fee_per_gas.last() {
+        Some(base_fee) => nat256_to_u128(base_fee)?,
+        None => return Err("Fee history does not contain a base fee".to_string()),
+    };
+
+    let mut rewards = fee_history
+        .reward
+        .iter()
+        .filter_map(|block_rewards| block_rewards.first())
+        .map(nat256_to_u128)
+        .collect::<Result<Vec<u128>, String>>()?;
+    rewards.sort_unstable();
+    let median_reward = rewards.get(rewards.len() / 2).copied().unwrap_or(0);
+
+    let max_priority_fee_per_gas = median_reward
+        .max(fee_config.min_priority_fee_per_gas as u128)
+        .min(fee_config.max_priority_fee_per_gas_cap as u128);
+
+    let max_fee_cap = fee_config.max_fee_per_gas_cap as u128;
+    let min_max_fee = base_fee_per_gas.saturating_add(max_priority_fee_per_gas);
+    if min_max_fee > max_fee_cap {
+        return Err(format!(
+            "Current base fee {} wei plus tip {} wei exceeds the max fee cap of {} wei",
+            base_fee_per_gas, max_priority_fee_per_gas, max_fee_cap
+        ));
+    }
+
+    let max_fee_per_gas = base_fee_per_gas
+        .saturating_mul(2)
+        .saturating_add(max_priority_fee_per_gas)
+        .min(max_fee_cap);
+
+    Ok(Eip1559Fees {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+    })
+}
+
+pub fn gas_limit_with_margin(estimated_gas: u128, margin_percent: u64) -> u128 {
+    estimated_gas.saturating_mul(100 + margin_percent as u128) / 100
+}
+
+/// Fetches the recent fee history of `chain` and derives the EIP-1559 fees.
+pub async fn resolve_eip1559_fees(chain: &BlockchainConfig) -> Result<Eip1559Fees, String> {
+    let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
+    let fee_config = chain.get_fee_config();
+
+    let args = FeeHistoryArgs {
+        block_count: Nat256::from(fee_config.fee_history_block_count),
+        newest_block: BlockTag::Latest,
+        reward_percentiles: Some(vec![fee_config.reward_percentile]),
+    };
+
+    let result = evm_providers::call_multi(chain, |rpc_services, rpc_config| async move {
+        evm_service
+            .eth_fee_history(&rpc_services, rpc_config, args)
+            .await
+    })
+    .await?;
+    match result {
+        MultiRpcResult::Consistent(Ok(fee_history)) => {
+            estimate_eip1559_fees(&fee_history, &fee_config)
+        }
+        MultiRpcResult::Consistent(Err(rpc_err)) => {
+            Err(format!("RPC error fetching fee history: {:?}", rpc_err))
+        }
+        MultiRpcResult::Inconsistent(_) => {
+            Err("Fee history is inconsistent across services".to_string())
+        }
+    }
+}
+
+/// Estimates the gas limit of a transaction on `chain` and adds the chain's safety margin.
+pub async fn estimate_gas_limit(
+    chain: &BlockchainConfig,
+    from: &str,
+    to: Option<&str>,
+    value: &Nat,
+    data: &[u8],
+) -> Result<u128, String> {
+    let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
+    let resp = evm_providers::request(chain, |rpc_service| {
+        evm_service.estimate_gas(rpc_service, from, to, value, data)
+    })
+    .await
+    .map_err(|e| format!("Gas estimation failed: {}", e))?;
+
+    let result_hex = extract_json_result(&resp)?;
+    let estimated_gas = parse_hex_quantity(&result_hex)?;
+    Ok(gas_limit_with_margin(
+        estimated_gas,
+        chain.get_fee_config().gas_limit_margin_percent,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    fn fee_history(base_fee_gwei: u64, rewards_gwei: &[u64]) -> FeeHistory {
+        FeeHistory {
+            oldest_block: Nat256::from(1u64),
+            base_fee_per_gas: vec![Nat256::from(base_fee_gwei * GWEI)],
+            gas_used_ratio: vec![],
+            reward: rewards_gwei
+                .iter()
+                .map(|reward| vec![Nat256::from(reward * GWEI)])
+                .collect(),
+        }
+    }
+
+    fn fee_config() -> FeeConfig {
+        FeeConfig {
+            max_fee_per_gas_cap: 100 * GWEI,
+            max_priority_fee_per_gas_cap: 5 * GWEI,
+            min_priority_fee_per_gas: 2 * GWEI,
+            ..FeeConfig::default()
+        }
+    }
+
+    fn gwei(fees: Eip1559Fees) -> (u128, u128) {
+        let gwei = GWEI as u128;
+        (
+            fees.max_fee_per_gas / gwei,
+            fees.max_priority_fee_per_gas / gwei,
+        )
+    }
+
+    #[test]
+    fn tip_is_the_median_reward() {
+        let fees = estimate_eip1559_fees(&fee_history(10, &[4, 3, 9]), &fee_config()).unwrap();
+        assert_eq!(gwei(fees), (24, 4));
+    }
+
+    #[test]
+    fn tip_is_clamped_to_the_floor_and_cap() {
+        let low = estimate_eip1559_fees(&fee_history(10, &[1]), &fee_config()).unwrap();
+        assert_eq!(gwei(low), (22, 2));
+
+        let high = estimate_eip1559_fees(&fee_history(10, &[50]), &fee_config()).unwrap();
+        assert_eq!(gwei(high), (25, 5));
+
+        let none = estimate_eip1559_fees(&fee_history(10, &[]), &fee_config()).unwrap();
+        assert_eq!(gwei(none), (22, 2));
+    }
+
+    #[test]
+    fn max_fee_is_clamped_to_the_ceiling() {
+        let fees = estimate_eip1559_fees(&fee_history(60, &[3]), &fee_config()).unwrap();
+        assert_eq!(gwei(fees), (100, 3));
+    }
+
+    #[test]
+    fn rejects_a_base_fee_above_the_ceiling() {
+        assert!(estimate_eip1559_fees(&fee_history(98, &[3]), &fee_config()).is_err());
+        assert!(estimate_eip1559_fees(
+            &fee_history(10, &[3]),
+            &FeeConfig {
+                max_fee_per_gas_cap: 0,
+                ..fee_config()
+            }
+        )
+        .is_err());
+
+        let mut history = fee_history(10, &[3]);
+        history.base_fee_per_gas.clear();
+        assert!(estimate_eip1559_fees(&history, &fee_config()).is_err());
+    }
+
+    #[test]
+    fn adds_the_gas_limit_margin() {
+        assert_eq!(gas_limit_with_margin(21_000, 20), 25_200);
+        assert_eq!(gas_limit_with_margin(21_000, 0), 21_000);
+    }
+}
@@ -0,0 +1,384 @@
+use evm_rpc_types::{
+    ConsensusStrategy, MultiRpcResult, RpcConfig, RpcError, RpcService, RpcServices,
+};
+use ic_cdk::api::call::CallResult;
+use ic_govmind_types::{chain::BlockchainConfig, constants::EVM_RPC_CANISTER_ID};
+use std::future::Future;
+
+use crate::{
+    services::{
+        evm_abi::{extract_json_result, parse_hex_quantity},
+        evm_service::EvmService,
+    },
+    store,
+};
+
+/// Sends a raw JSON-RPC request to the healthiest providers of `chain` one after the other,
+/// until enough of them agree on the result under the chain's consensus strategy. Returns the
+/// raw response of an agreeing provider.
+pub async fn request<F, Fut>(chain: &BlockchainConfig, call: F) -> Result<String, String>
+where
+    F: Fn(RpcService) -> Fut,
+    Fut: Future<Output = CallResult<(Result<String, RpcError>,)>>,
+{
+    let chain_id = chain_id(chain)?;
+    let providers = select_providers(chain)?;
+    let quorum = quorum(&chain.consensus(), providers.len());
+
+    // Responses grouped by their `result`, or by their JSON-RPC error.
+    let mut votes: Vec<(Result<String, String>, String, usize)> = vec![];
+    let mut errors = vec![];
+    for (i, provider) in providers.iter().enumerate() {
+        let started_at = ic_cdk::api::time();
+        match call(provider.clone()).await {
+            Ok((Ok(response),)) => {
+                record_success(chain_id, provider, started_at);
+                let key = extract_json_result(&response);
+                match votes.iter_mut().find(|(k, _, _)| *k == key) {
+                    Some(vote) => vote.2 += 1,
+                    None => votes.push((key, response, 1)),
+                }
+            }
+            Ok((Err(e),)) => {
+                record_failure(chain_id, provider, &e);
+                errors.push(format!("{}: {:?}", provider_name(provider), e));
+            }
+            Err((code, msg)) => errors.push(format!("rejected: {:?}, {:?}", code, msg)),
+        }
+
+        let best = votes.iter().map(|(_, _, count)| *count).max().unwrap_or(0);
+        if best >= quorum {
+            break;
+        }
+        if best + providers.len() - i - 1 < quorum {
+            break;
+        }
+    }
+
+    if let Some((_, response, _)) = votes.iter().find(|(_, _, count)| *count >= quorum) {
+        return Ok(response.clone());
+    }
+    // Reverts and other JSON-RPC errors are worded differently by each provider; let the caller
+    // report the first one rather than a disagreement.
+    if errors.is_empty() && votes.iter().all(|(key, _, _)| key.is_err()) {
+        if let Some((_, response, _)) = votes.first() {
+            return Ok(response.clone());
+        }
+    }
+    Err(format!(
+        "No consensus among {} of {} providers: {} distinct responses, errors: [{}]",
+        quorum,
+        providers.len(),
+        votes.len(),
+        errors.join("; ")
+    ))
+}
+
+/// Latest block number of `chain`. Providers are rarely at the same height, so this takes the
+/// lowest head among a quorum of responses, which is safe for confirmation counting.
+pub async fn block_number(chain: &BlockchainConfig) -> Result<u64, String> {
+    let chain_id = chain_id(chain)?;
+    let providers = select_providers(chain)?;
+    let quorum = quorum(&chain.consensus(), providers.len());
+    let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
+
+    let mut heads = vec![];
+    let mut errors = vec![];
+    for provider in &providers {
+        let started_at = ic_cdk::api::time();
+        match evm_service.get_block_number(provider.clone()).await {
+            Ok((Ok(response),)) => {
+                record_success(chain_id, provider, started_at);
+                match extract_json_result(&response).and_then(|hex| parse_hex_quantity(&hex)) {
+                    Ok(head) => heads.push(head as u64),
+                    Err(e) => errors.push(format!("{}: {}", provider_name(provider), e)),
+                }
+            }
+            Ok((Err(e),)) => {
+                record_failure(chain_id, provider, &e);
+                errors.push(format!("{}: {:?}", provider_name(provider), e));
+            }
+            Err((code, msg)) => errors.push(format!("rejected: {:?}, {:?}", code, msg)),
+        }
+        if heads.len() >= quorum {
+            break;
+        }
+    }
+
+    match heads.len() >= quorum {
+        true => Ok(heads.into_iter().min().unwrap_or_default()),
+        false => Err(format!(
+            "Only {} of {} required providers returned a block number: [{}]",
+            heads.len(),
+            quorum,
+            errors.join("; ")
+        )),
+    }
+}
+
+/// Calls one of the multi-provider methods of the EVM RPC canister with the healthy providers of
+/// `chain` and its consensus strategy, and records the outcome of each provider.
+pub async fn call_multi<T, F, Fut>(
+    chain: &BlockchainConfig,
+    call: F,
+) -> Result<MultiRpcResult<T>, String>
+where
+    F: FnOnce(RpcServices, Option<RpcConfig>) -> Fut,
+    Fut: Future<Output = CallResult<(MultiRpcResult<T>,)>>,
+{
+    let chain_id = chain_id(chain)?;
+    let providers = select_providers(chain)?;
+    let rpc_services = to_rpc_services(&chain.rpc_services()?, &providers);
+    let rpc_config = RpcConfig {
+        response_size_estimate: None,
+        // The providers are listed explicitly, so the threshold total is their count.
+        response_consensus: Some(match chain.consensus() {
+            ConsensusStrategy::Threshold { min, .. } => {
+                ConsensusStrategy::Threshold { total: None, min }
+            }
+            ConsensusStrategy::Equality => ConsensusStrategy::Equality,
+        }),
+    };
+
+    let started_at = ic_cdk::api::time();
+    let (result,) = call(rpc_services, Some(rpc_config))
+        .await
+        .map_err(|(code, msg)| format!("EVM RPC call rejected: {:?}, {:?}", code, msg))?;
+
+    match &result {
+        MultiRpcResult::Consistent(Err(e)) if is_provider_failure(e) => {
+            for provider in &providers {
+                record_failure(chain_id, provider, e);
+            }
+        }
+        MultiRpcResult::Consistent(_) => {
+            for provider in &providers {
+                record_success(chain_id, provider, started_at);
+            }
+        }
+        MultiRpcResult::Inconsistent(results) => {
+            for (provider, result) in results {
+                match result {
+                    Err(e) if is_provider_failure(e) => record_failure(chain_id, provider, e),
+                    _ => record_success(chain_id, provider, started_at),
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// The providers to query, healthiest first. Excluded providers are left out unless the
+/// consensus strategy would then lack providers.
+fn select_providers(chain: &BlockchainConfig) -> Result<Vec<RpcService>, String> {
+    let chain_id = chain_id(chain)?;
+    let providers: Vec<(RpcService, bool, u32, u64)> = chain
+        .rpc_providers()?
+        .into_iter()
+        .map(
+            |provider| match store::rpc_health::get(chain_id, &provider_name(&provider)) {
+                Some(health) => (
+                    provider,
+                    store::rpc_health::is_excluded(&health),
+                    health.consecutive_failures,
+                    health.average_latency_ms,
+                ),
+                None => (provider, false, 0, 0),
+            },
+        )
+        .collect();
+    let required = match chain.consensus() {
+        ConsensusStrategy::Threshold { min, .. } => (min as usize).max(1),
+        ConsensusStrategy::Equality => 1,
+    };
+    Ok(rank_providers(providers, required))
+}
+
+/// Orders `(provider, excluded, consecutive failures, average latency)` healthiest first and
+/// keeps the healthy ones, topped up with excluded ones until `required` are left.
+fn rank_providers(
+    mut providers: Vec<(RpcService, bool, u32, u64)>,
+    required: usize,
+) -> Vec<RpcService> {
+    providers.sort_by_key(|(_, excluded, failures, latency)| (*excluded, *failures, *latency));
+    let healthy = providers
+        .iter()
+        .filter(|(_, excluded, _, _)| !excluded)
+        .count();
+    let count = healthy.max(required).min(providers.len());
+    providers
+        .into_iter()
+        .take(count)
+        .map(|(provider, _, _, _)| provider)
+        .collect()
+}
+
+/// Agreeing responses needed out of `provider_count`.
+fn quorum(strategy: &ConsensusStrategy, provider_count: usize) -> usize {
+    match strategy {
+        ConsensusStrategy::Equality => provider_count,
+        ConsensusStrategy::Threshold { min, .. } => (*min as usize).clamp(1, provider_count),
+    }
+}
+
+/// Rebuilds `configured` with only `providers`.
+fn to_rpc_services(configured: &RpcServices, providers: &[RpcService]) -> RpcServices {
+    match configured {
+        RpcServices::Custom { chain_id, .. } => RpcServices::Custom {
+            chain_id: *chain_id,
+            services: providers
+                .iter()
+                .filter_map(|p| match p {
+                    RpcService::Custom(api) => Some(api.clone()),
+                    _ => None,
+                })
+                .collect(),
+        },
+        RpcServices::EthMainnet(_) => RpcServices::EthMainnet(Some(
+            providers
+                .iter()
+                .filter_map(|p| match p {
+                    RpcService::EthMainnet(s) => Some(*s),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        RpcServices::EthSepolia(_) => RpcServices::EthSepolia(Some(
+            providers
+                .iter()
+                .filter_map(|p| match p {
+                    RpcService::EthSepolia(s) => Some(*s),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        RpcServices::ArbitrumOne(_) => RpcServices::ArbitrumOne(Some(
+            providers
+                .iter()
+                .filter_map(|p| match p {
+                    RpcService::ArbitrumOne(s) => Some(*s),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        RpcServices::BaseMainnet(_) => RpcServices::BaseMainnet(Some(
+            providers
+                .iter()
+                .filter_map(|p| match p {
+                    RpcService::BaseMainnet(s) => Some(*s),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        RpcServices::OptimismMainnet(_) => RpcServices::OptimismMainnet(Some(
+            providers
+                .iter()
+                .filter_map(|p| match p {
+                    RpcService::OptimismMainnet(s) => Some(*s),
+                    _ => None,
+                })
+                .collect(),
+        )),
+    }
+}
+
+/// A provider's name in the health metrics. Custom providers are named by host, since their
+/// URL may carry an API key.
+pub fn provider_name(provider: &RpcService) -> String {
+    match provider {
+        RpcService::Custom(api) => api.host_str().unwrap_or_else(|| "custom".to_string()),
+        provider => format!("{:?}", provider),
+    }
+}
+
+/// Whether an error is the provider's fault rather than a verdict on the request itself.
+fn is_provider_failure(error: &RpcError) -> bool {
+    matches!(
+        error,
+        RpcError::ProviderError(_) | RpcError::HttpOutcallError(_)
+    )
+}
+
+fn record_success(chain_id: u64, provider: &RpcService, started_at: u64) {
+    let latency_ms = ic_cdk::api::time().saturating_sub(started_at) / 1_000_000;
+    store::rpc_health::record_success(chain_id, &provider_name(provider), latency_ms);
+}
+
+fn record_failure(chain_id: u64, provider: &RpcService, error: &RpcError) {
+    store::rpc_health::record_failure(chain_id, &provider_name(provider), format!("{:?}", error));
+}
+
+fn chain_id(chain: &BlockchainConfig) -> Result<u64, String> {
+    chain
+        .chain_id()
+        .ok_or_else(|| format!("No chain id configured for {:?}", chain.chain_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evm_rpc_types::EthMainnetService;
+
+    fn mainnet(service: EthMainnetService) -> RpcService {
+        RpcService::EthMainnet(service)
+    }
+
+    #[test]
+    fn equality_needs_every_provider_to_agree() {
+        assert_eq!(quorum(&ConsensusStrategy::Equality, 3), 3);
+    }
+
+    #[test]
+    fn threshold_quorum_is_clamped_to_the_provider_count() {
+        let threshold = |min| ConsensusStrategy::Threshold { total: None, min };
+        assert_eq!(quorum(&threshold(2), 3), 2);
+        assert_eq!(quorum(&threshold(5), 3), 3);
+        assert_eq!(quorum(&threshold(0), 3), 1);
+    }
+
+    #[test]
+    fn providers_are_ranked_healthiest_first() {
+        let ranked = rank_providers(
+            vec![
+                (mainnet(EthMainnetService::Ankr), false, 2, 10),
+                (mainnet(EthMainnetService::Alchemy), false, 0, 300),
+                (mainnet(EthMainnetService::PublicNode), false, 0, 50),
+            ],
+            1,
+        );
+        assert_eq!(
+            ranked,
+            vec![
+                mainnet(EthMainnetService::PublicNode),
+                mainnet(EthMainnetService::Alchemy),
+                mainnet(EthMainnetService::Ankr),
+            ]
+        );
+    }
+
+    #[test]
+    fn excluded_providers_are_left_out_while_enough_are_healthy() {
+        let providers = vec![
+            (mainnet(EthMainnetService::Ankr), true, 5, 10),
+            (mainnet(EthMainnetService::Alchemy), false, 0, 100),
+            (mainnet(EthMainnetService::Cloudflare), false, 1, 100),
+        ];
+        assert_eq!(
+            rank_providers(providers.clone(), 2),
+            vec![
+                mainnet(EthMainnetService::Alchemy),
+                mainnet(EthMainnetService::Cloudflare),
+            ]
+        );
+        assert_eq!(rank_providers(providers, 3).len(), 3);
+    }
+
+    #[test]
+    fn selection_never_exceeds_the_configured_providers() {
+        let providers = vec![(mainnet(EthMainnetService::Llama), true, 9, 0)];
+        assert_eq!(
+            rank_providers(providers, 3),
+            vec![mainnet(EthMainnetService::Llama)]
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use candid::{Nat, Principal};
 use ethers_core::abi::{Abi, Address, FunctionExt, Token};
 use evm_rpc_types::{
-    CallArgs, MultiRpcResult, RpcApi, RpcConfig, RpcError, RpcService, RpcServices,
-    SendRawTransactionStatus,
+    CallArgs, FeeHistory, FeeHistoryArgs, GetLogsArgs, GetLogsRpcConfig, LogEntry, MultiRpcResult,
+    RpcConfig, RpcError, RpcService, RpcServices, SendRawTransactionStatus, TransactionReceipt,
 };
 use ic_canister_log::log;
 use ic_cdk::api::call::CallResult;
@@ -11,7 +11,9 @@ use serde_json::{json, Value};
 
 use crate::{
     ic_log::INFO,
-    services::evm_abi::{parse_abi, BALANCE_OF_ABI_JSON},
+    services::evm_abi::{
+        parse_abi, ALLOWANCE_ABI_JSON, BALANCE_OF_ABI_JSON, ERC20_METADATA_ABI_JSON,
+    },
 };
 
 pub struct EvmService {
@@ -89,61 +91,61 @@ impl EvmService {
         })
     }
 
-    pub async fn get_token_prices(
+    pub async fn eth_fee_history(
         &self,
-        token_symbols: Vec<&str>,
-    ) -> CallResult<(Result<String, RpcError>,)> {
-        // Base URL for the API
-        let base_url =
-            "https://api.g.alchemy.com/prices/v1/Zrf_uaW1uz6JoGyTeZBXvxz5msbiTjeA/tokens/by-symbol";
-
-        // Construct query parameters for the symbols
-        let query_params: String = token_symbols
-            .iter()
-            .map(|symbol| format!("symbols={}", symbol))
-            .collect::<Vec<String>>()
-            .join("&");
-
-        // Final URL
-        let final_url = format!("{}?{}", base_url, query_params);
-
-        // Log the constructed URL
-        ic_cdk::println!("Constructed URL: {}", final_url);
-
-        let rpc_api = RpcApi {
-            url: final_url,
-            headers: None, // Add headers if necessary, e.g., authentication headers
-        };
+        rpc_services: &RpcServices,
+        rpc_config: Option<RpcConfig>,
+        fee_history_args: FeeHistoryArgs,
+    ) -> CallResult<(MultiRpcResult<FeeHistory>,)> {
+        let args: (RpcServices, Option<RpcConfig>, FeeHistoryArgs) =
+            (rpc_services.clone(), rpc_config, fee_history_args);
 
-        // Create the RpcService instance
-        let service: RpcService = RpcService::Custom(rpc_api);
+        ic_cdk::api::call::call_with_payment128(
+            self.principal,
+            "eth_feeHistory",
+            args,
+            EVM_CALL_DEFAULT_CYCLES,
+        )
+        .await
+        .map_err(|err| {
+            ic_cdk::println!("Error calling eth_feeHistory: {:?}", err);
+            err
+        })
+    }
 
-        let json_request = String::from("{}");
+    /// Estimate the gas a transaction will consume through `eth_estimateGas`.
+    pub async fn estimate_gas(
+        &self,
+        service: RpcService,
+        from: &str,
+        to: Option<&str>,
+        value: &Nat,
+        data: &[u8],
+    ) -> CallResult<(Result<String, RpcError>,)> {
+        let mut tx = json!({
+            "from": from,
+            "value": format!("0x{}", value.0.to_str_radix(16)),
+            "data": format!("0x{}", hex::encode(data)),
+        });
+        if let Some(to) = to {
+            tx["to"] = json!(to);
+        }
 
+        let json_request = Self::build_json_rpc_request("eth_estimateGas", vec![tx]);
+        ic_cdk::println!("estimate_gas request: {}", json_request);
         let params = (service, json_request, 1000_u64);
 
-        // Perform the API call
-        match ic_cdk::api::call::call_with_payment128(
+        ic_cdk::api::call::call_with_payment128(
             self.principal,
             "request",
             params,
             EVM_CALL_DEFAULT_CYCLES,
         )
         .await
-        {
-            Ok(result) => {
-                ic_cdk::println!("Success calling get_token_prices: {:?}", result);
-                Ok(result)
-            }
-            Err((rejection_code, msg)) => {
-                ic_cdk::println!(
-                    "Error calling get_token_prices: {:?}, {:?}",
-                    rejection_code,
-                    msg
-                );
-                Err((rejection_code, msg))
-            }
-        }
+        .map_err(|err| {
+            ic_cdk::println!("Error calling estimate_gas: {:?}", err);
+            err
+        })
     }
 
     pub async fn eth_send_raw_transaction(
@@ -169,6 +171,71 @@ impl EvmService {
         .await
     }
 
+    pub async fn eth_get_transaction_receipt(
+        &self,
+        rpc_services: &RpcServices,
+        rpc_config: Option<RpcConfig>,
+        tx_hash: String,
+    ) -> CallResult<(MultiRpcResult<Option<TransactionReceipt>>,)> {
+        let args: (RpcServices, Option<RpcConfig>, String) =
+            (rpc_services.clone(), rpc_config, tx_hash);
+
+        ic_cdk::api::call::call_with_payment128(
+            self.principal,
+            "eth_getTransactionReceipt",
+            args,
+            EVM_CALL_DEFAULT_CYCLES,
+        )
+        .await
+        .map_err(|err| {
+            ic_cdk::println!("Error calling eth_getTransactionReceipt: {:?}", err);
+            err
+        })
+    }
+
+    pub async fn eth_get_logs(
+        &self,
+        rpc_services: &RpcServices,
+        rpc_config: Option<GetLogsRpcConfig>,
+        get_logs_args: GetLogsArgs,
+    ) -> CallResult<(MultiRpcResult<Vec<LogEntry>>,)> {
+        let args: (RpcServices, Option<GetLogsRpcConfig>, GetLogsArgs) =
+            (rpc_services.clone(), rpc_config, get_logs_args);
+
+        ic_cdk::api::call::call_with_payment128(
+            self.principal,
+            "eth_getLogs",
+            args,
+            EVM_CALL_DEFAULT_CYCLES,
+        )
+        .await
+        .map_err(|err| {
+            ic_cdk::println!("Error calling eth_getLogs: {:?}", err);
+            err
+        })
+    }
+
+    /// Get the number of the most recent block.
+    pub async fn get_block_number(
+        &self,
+        service: RpcService,
+    ) -> CallResult<(Result<String, RpcError>,)> {
+        let json_request = Self::build_json_rpc_request("eth_blockNumber", vec![]);
+        let params = (service, json_request, 1000_u64);
+
+        ic_cdk::api::call::call_with_payment128(
+            self.principal,
+            "request",
+            params,
+            EVM_CALL_DEFAULT_CYCLES,
+        )
+        .await
+        .map_err(|err| {
+            ic_cdk::println!("Error calling get_block_number: {:?}", err);
+            err
+        })
+    }
+
     pub async fn eth_call(
         &self,
         rpc_services: RpcServices,
@@ -184,6 +251,43 @@ impl EvmService {
             })
     }
 
+    /// Executes a call from `from` against the latest block through `eth_call`, without
+    /// broadcasting anything.
+    pub async fn simulate_call(
+        &self,
+        service: RpcService,
+        from: &str,
+        to: &str,
+        value: &Nat,
+        data: &[u8],
+    ) -> CallResult<(Result<String, RpcError>,)> {
+        let call_params = vec![
+            json!({
+                "from": from,
+                "to": to,
+                "value": format!("0x{}", value.0.to_str_radix(16)),
+                "data": format!("0x{}", hex::encode(data)),
+            }),
+            json!("latest"),
+        ];
+
+        let json_request = Self::build_json_rpc_request("eth_call", call_params);
+        ic_cdk::println!("simulate_call request: {}", json_request);
+        let params = (service, json_request, 2000_u64);
+
+        ic_cdk::api::call::call_with_payment128(
+            self.principal,
+            "request",
+            params,
+            EVM_CALL_DEFAULT_CYCLES,
+        )
+        .await
+        .map_err(|err| {
+            ic_cdk::println!("Error calling simulate_call: {:?}", err);
+            err
+        })
+    }
+
     pub async fn request_cost(
         &self,
         rpc_service: RpcService,
@@ -294,6 +398,48 @@ impl EvmService {
         .await
     }
 
+    pub async fn get_erc20_allowance(
+        &self,
+        rpc_service: RpcService,
+        contract_address: String,
+        owner: Address,
+        spender: Address,
+        block_number: &str,
+    ) -> CallResult<(Result<String, RpcError>,)> {
+        let args = vec![Token::Address(owner), Token::Address(spender)];
+        let allowance_abi = parse_abi(ALLOWANCE_ABI_JSON);
+
+        self.eth_contract_call(
+            rpc_service,
+            contract_address,
+            &allowance_abi,
+            "allowance",
+            &args,
+            block_number,
+        )
+        .await
+    }
+
+    /// Reads `symbol()` or `decimals()` of an ERC-20 contract.
+    pub async fn get_erc20_metadata(
+        &self,
+        rpc_service: RpcService,
+        contract_address: String,
+        function_name: &str,
+    ) -> CallResult<(Result<String, RpcError>,)> {
+        let metadata_abi = parse_abi(ERC20_METADATA_ABI_JSON);
+
+        self.eth_contract_call(
+            rpc_service,
+            contract_address,
+            &metadata_abi,
+            function_name,
+            &[],
+            "latest",
+        )
+        .await
+    }
+
     /// Get the current transaction count (nonce) for an account.
     pub async fn get_account_nonce(
         &self,
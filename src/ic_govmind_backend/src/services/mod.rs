@@ -1,3 +1,7 @@
 pub mod token_icrc1;
 pub mod evm_abi;
+pub mod evm_fee;
+pub mod evm_providers;
 pub mod evm_service;
+pub mod cketh_minter;
+pub mod xrc;
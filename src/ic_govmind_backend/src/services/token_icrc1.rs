@@ -5,6 +5,7 @@ use ic_ledger_types::{
     TransferResult,
 };
 use icrc_ledger_types::{
+    icrc::generic_metadata_value::MetadataValue,
     icrc1::{
         account::{Account, Subaccount as ICRCSubaccount},
         transfer::{Memo as ICRCMemo, TransferArg as ICRCTransferArg, TransferError},
@@ -90,6 +91,42 @@ impl TokenICRC1 {
         }
     }
 
+    pub async fn icrc1_fee(&self) -> Result<Nat, String> {
+        let res = Call::bounded_wait(self.principal, "icrc1_fee").await;
+
+        match res {
+            Ok(resp) => match resp.candid::<Nat>() {
+                Ok(fee) => Ok(fee),
+                Err(e) => Err(format!("Decoding error: {:?}", e)),
+            },
+            Err(e) => Err(format!("Call failed: {:?}", e)),
+        }
+    }
+
+    pub async fn icrc1_metadata(&self) -> Result<Vec<(String, MetadataValue)>, String> {
+        let res = Call::bounded_wait(self.principal, "icrc1_metadata").await;
+
+        match res {
+            Ok(resp) => match resp.candid::<Vec<(String, MetadataValue)>>() {
+                Ok(metadata) => Ok(metadata),
+                Err(e) => Err(format!("Decoding error: {:?}", e)),
+            },
+            Err(e) => Err(format!("Call failed: {:?}", e)),
+        }
+    }
+
+    pub async fn icrc1_total_supply(&self) -> Result<Nat, String> {
+        let res = Call::bounded_wait(self.principal, "icrc1_total_supply").await;
+
+        match res {
+            Ok(resp) => match resp.candid::<Nat>() {
+                Ok(supply) => Ok(supply),
+                Err(e) => Err(format!("Decoding error: {:?}", e)),
+            },
+            Err(e) => Err(format!("Call failed: {:?}", e)),
+        }
+    }
+
     pub async fn icrc1_transfer(
         &self,
         from_subaccount: Option<ICRCSubaccount>,
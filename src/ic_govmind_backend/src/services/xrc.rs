@@ -0,0 +1,107 @@
+use candid::{CandidType, Deserialize, Principal};
+use ic_cdk::call::Call;
+use ic_govmind_types::constants::XRC_CALL_CYCLES;
+
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub enum AssetClass {
+    Cryptocurrency,
+    FiatCurrency,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct Asset {
+    pub symbol: String,
+    pub class: AssetClass,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct GetExchangeRateRequest {
+    pub base_asset: Asset,
+    pub quote_asset: Asset,
+    pub timestamp: Option<u64>,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct ExchangeRateMetadata {
+    pub decimals: u32,
+    pub base_asset_num_queried_sources: u64,
+    pub base_asset_num_received_rates: u64,
+    pub quote_asset_num_queried_sources: u64,
+    pub quote_asset_num_received_rates: u64,
+    pub standard_deviation: u64,
+    pub forex_timestamp: Option<u64>,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct ExchangeRate {
+    pub base_asset: Asset,
+    pub quote_asset: Asset,
+    pub timestamp: u64,
+    pub rate: u64,
+    pub metadata: ExchangeRateMetadata,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub struct OtherError {
+    pub code: u32,
+    pub description: String,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize)]
+pub enum ExchangeRateError {
+    AnonymousPrincipalNotAllowed,
+    Pending,
+    CryptoBaseAssetNotFound,
+    CryptoQuoteAssetNotFound,
+    StablecoinRateNotFound,
+    StablecoinRateTooFewRates,
+    StablecoinRateZeroRate,
+    ForexInvalidTimestamp,
+    ForexBaseAssetNotFound,
+    ForexQuoteAssetNotFound,
+    ForexAssetsNotFound,
+    RateLimited,
+    NotEnoughCycles,
+    FailedToAcceptCycles,
+    InconsistentRatesReceived,
+    Other(OtherError),
+}
+
+/// Client of the IC exchange-rate canister (XRC).
+#[derive(Clone, Debug)]
+pub struct ExchangeRateCanister {
+    pub principal: Principal,
+}
+
+impl ExchangeRateCanister {
+    pub fn new(principal: Principal) -> Self {
+        Self { principal }
+    }
+
+    /// Fetches the latest rate of a cryptocurrency in USD. Each call costs `XRC_CALL_CYCLES`.
+    pub async fn get_usd_rate(
+        &self,
+        symbol: &str,
+    ) -> Result<Result<ExchangeRate, ExchangeRateError>, String> {
+        let request = GetExchangeRateRequest {
+            base_asset: Asset {
+                symbol: symbol.to_string(),
+                class: AssetClass::Cryptocurrency,
+            },
+            quote_asset: Asset {
+                symbol: "USD".to_string(),
+                class: AssetClass::FiatCurrency,
+            },
+            timestamp: None,
+        };
+
+        match Call::unbounded_wait(self.principal, "get_exchange_rate")
+            .with_arg(request)
+            .with_cycles(XRC_CALL_CYCLES)
+            .await
+        {
+            Ok(res) => res.candid().map_err(|e| format!("Decoding error: {:?}", e)),
+            Err(e) => Err(format!("Call failed: {:?}", e)),
+        }
+    }
+}
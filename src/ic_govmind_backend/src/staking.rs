@@ -0,0 +1,313 @@
+use candid::{Nat, Principal};
+use ic_govmind_types::dao::{
+    DissolveState, DistributionStatus, DistributionType, StakePosition, StakePositionStatus,
+    StakeReconciliation, StakingConfig,
+};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+
+use crate::{
+    distribution::{self, base_token_ledger, queue_payout, subaccount_account},
+    store,
+    utils::{current_time_secs, nat_to_u128, sha256},
+};
+
+const BPS: u64 = 10_000;
+
+/// The subaccount of the DAO canister holding the stakes of `owner`.
+pub fn staking_subaccount(owner: Principal) -> Subaccount {
+    let mut data = b"govmind-stake".to_vec();
+    data.extend_from_slice(owner.as_slice());
+    sha256(&data)
+        .try_into()
+        .expect("sha256 digests are 32 bytes")
+}
+
+/// Where `owner` sends the tokens they want to stake.
+pub fn staking_account(owner: Principal) -> Account {
+    subaccount_account(staking_subaccount(owner))
+}
+
+/// Locks `amount` of the tokens the caller sent to their staking account, for a dissolve delay of
+/// `dissolve_delay_secs`. Returns the position id.
+pub async fn stake(amount: Nat, dissolve_delay_secs: u64) -> Result<u64, String> {
+    let owner = ic_cdk::api::msg_caller();
+    let config = store::staking::get_config();
+    if dissolve_delay_secs < config.min_dissolve_delay_secs
+        || dissolve_delay_secs > config.max_dissolve_delay_secs
+    {
+        return Err(format!(
+            "Dissolve delay must be between {} and {} seconds",
+            config.min_dissolve_delay_secs, config.max_dissolve_delay_secs
+        ));
+    }
+
+    let token_service = base_token_ledger()?;
+    let fee = token_service.icrc1_fee().await?;
+    if amount <= fee {
+        return Err(format!("Stake must be larger than the ledger fee {}", fee));
+    }
+    let account = staking_account(owner);
+    let balance = token_service.icrc1_balance_of(account).await?;
+
+    // Read after the call, so that concurrent stakes see each other's positions.
+    let staked = staked_amount(owner);
+    let available = match balance > staked {
+        true => balance - staked,
+        false => Nat::from(0u64),
+    };
+    if amount > available {
+        return Err(format!(
+            "Only {} unstaked tokens in {}, send more tokens there first",
+            available, account
+        ));
+    }
+
+    let id = store::staking::add_position(StakePosition {
+        id: 0,
+        owner,
+        amount,
+        dissolve_delay_secs,
+        dissolve_state: DissolveState::Locked,
+        created_at: current_time_secs(),
+        unstaked_at: None,
+    });
+    store::state::save();
+    Ok(id)
+}
+
+/// Starts the dissolve delay of one of the caller's positions. Returns when it dissolves.
+pub fn start_dissolving(position_id: u64) -> Result<u64, String> {
+    let owner = ic_cdk::api::msg_caller();
+    let now = current_time_secs();
+    let dissolve_at = store::staking::update_position(position_id, |position| {
+        check_owner(position, owner)?;
+        match position.dissolve_state {
+            DissolveState::Locked => {
+                let dissolve_at = now + position.dissolve_delay_secs;
+                position.dissolve_state = DissolveState::Dissolving { dissolve_at };
+                Ok(dissolve_at)
+            }
+            DissolveState::Dissolving { .. } => Err(format!(
+                "Stake position {} is already dissolving",
+                position_id
+            )),
+        }
+    })?;
+    store::state::save();
+    Ok(dissolve_at)
+}
+
+/// Returns the tokens of one of the caller's dissolved positions, less the ledger fee. Returns
+/// the amount sent.
+pub async fn unstake(position_id: u64) -> Result<Nat, String> {
+    let owner = ic_cdk::api::msg_caller();
+    let token_service = base_token_ledger()?;
+    let fee = token_service.icrc1_fee().await?;
+
+    let now = current_time_secs();
+    let amount = store::staking::update_position(position_id, |position| {
+        check_owner(position, owner)?;
+        match position.dissolve_state {
+            DissolveState::Dissolving { dissolve_at } if dissolve_at <= now => {}
+            DissolveState::Dissolving { dissolve_at } => {
+                return Err(format!(
+                    "Stake position {} dissolves at {}",
+                    position_id, dissolve_at
+                ))
+            }
+            DissolveState::Locked => {
+                return Err(format!(
+                    "Stake position {} must be dissolved first",
+                    position_id
+                ))
+            }
+        }
+        if position.amount <= fee {
+            return Err(format!("Stake is too small to pay the ledger fee {}", fee));
+        }
+        position.unstaked_at = Some(now);
+        Ok(position.amount.clone() - fee)
+    })?;
+
+    let payout_id = unstake_payout_id(position_id);
+    queue_payout(
+        payout_id.clone(),
+        DistributionType::Unstake,
+        Account::from(owner).to_string(),
+        amount,
+        Some(staking_subaccount(owner)),
+    );
+    store::state::save();
+
+    distribution::pay_claim(&token_service, &payout_id).await
+}
+
+/// Compares the staked tokens of every staker with the balance of their staking account.
+pub async fn reconcile() -> Result<Vec<StakeReconciliation>, String> {
+    let token_service = base_token_ledger()?;
+    let mut owners: Vec<Principal> = store::staking::list_positions(None)
+        .into_iter()
+        .map(|position| position.owner)
+        .collect();
+    owners.sort();
+    owners.dedup();
+
+    let mut reconciliations = vec![];
+    for owner in owners {
+        let ledger_balance = token_service
+            .icrc1_balance_of(staking_account(owner))
+            .await?;
+        reconciliations.push(StakeReconciliation {
+            owner,
+            staked: staked_amount(owner),
+            ledger_balance,
+        });
+    }
+    Ok(reconciliations)
+}
+
+/// Tokens of `owner` still held by their staking account: every position that was not unstaked,
+/// or whose payout the ledger has not accepted yet.
+fn staked_amount(owner: Principal) -> Nat {
+    store::staking::list_positions(Some(owner))
+        .into_iter()
+        .filter(|position| {
+            position.unstaked_at.is_none()
+                || store::distribution::get_payout(&unstake_payout_id(position.id))
+                    .is_none_or(|payout| payout.status != DistributionStatus::Completed)
+        })
+        .fold(Nat::from(0u64), |sum, position| sum + position.amount)
+}
+
+pub fn position_status(position: &StakePosition, now: u64) -> StakePositionStatus {
+    let config = store::staking::get_config();
+    StakePositionStatus {
+        position: position.clone(),
+        remaining_delay_secs: remaining_delay(position, now),
+        voting_power: voting_power(position, &config, now),
+    }
+}
+
+/// Voting power of `owner`'s stakes, as a vote weight. Only stakes that stay locked until
+/// `voting_ends_at` count, so that their tokens cannot be unstaked and vote again before the
+/// vote closes.
+pub fn vote_weight(owner: Principal, now: u64, voting_ends_at: u64) -> u64 {
+    let config = store::staking::get_config();
+    weight_of(
+        &store::staking::list_positions(Some(owner)),
+        &config,
+        now,
+        voting_ends_at,
+    )
+}
+
+fn weight_of(
+    positions: &[StakePosition],
+    config: &StakingConfig,
+    now: u64,
+    voting_ends_at: u64,
+) -> u64 {
+    let power = positions
+        .iter()
+        .filter(|position| remaining_delay(position, now) >= voting_ends_at.saturating_sub(now))
+        .fold(Nat::from(0u64), |sum, position| {
+            sum + voting_power(position, config, now)
+        });
+    nat_to_u128(&power).map_or(u64::MAX, |power| power.min(u64::MAX as u128) as u64)
+}
+
+/// The staked amount with a bonus for the remaining dissolve delay. Stakes that were unstaked or
+/// dissolve sooner than the minimum delay have no voting power.
+fn voting_power(position: &StakePosition, config: &StakingConfig, now: u64) -> Nat {
+    let remaining = remaining_delay(position, now);
+    if position.unstaked_at.is_some()
+        || remaining == 0
+        || remaining < config.min_dissolve_delay_secs
+    {
+        return Nat::from(0u64);
+    }
+
+    let max_delay = config.max_dissolve_delay_secs.max(1);
+    let bonus_bps =
+        config.max_bonus_bps as u128 * remaining.min(max_delay) as u128 / max_delay as u128;
+    position.amount.clone() * Nat::from(BPS as u128 + bonus_bps) / Nat::from(BPS)
+}
+
+fn remaining_delay(position: &StakePosition, now: u64) -> u64 {
+    match position.dissolve_state {
+        DissolveState::Locked => position.dissolve_delay_secs,
+        DissolveState::Dissolving { dissolve_at } => dissolve_at.saturating_sub(now),
+    }
+}
+
+fn check_owner(position: &StakePosition, owner: Principal) -> Result<(), String> {
+    if position.owner != owner {
+        return Err(format!("Stake position {} is not yours", position.id));
+    }
+    if position.unstaked_at.is_some() {
+        return Err(format!("Stake position {} was unstaked", position.id));
+    }
+    Ok(())
+}
+
+fn unstake_payout_id(position_id: u64) -> String {
+    format!("unstake:{}", position_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> StakingConfig {
+        StakingConfig {
+            min_dissolve_delay_secs: 100,
+            max_dissolve_delay_secs: 1_000,
+            max_bonus_bps: 10_000,
+        }
+    }
+
+    fn position(dissolve_state: DissolveState) -> StakePosition {
+        StakePosition {
+            id: 1,
+            owner: Principal::anonymous(),
+            amount: Nat::from(1_000u64),
+            dissolve_delay_secs: 500,
+            dissolve_state,
+            created_at: 0,
+            unstaked_at: None,
+        }
+    }
+
+    #[test]
+    fn bonus_grows_with_the_remaining_delay() {
+        let locked = position(DissolveState::Locked);
+        assert_eq!(voting_power(&locked, &config(), 0), Nat::from(1_500u64));
+
+        let dissolving = position(DissolveState::Dissolving { dissolve_at: 1_200 });
+        assert_eq!(
+            voting_power(&dissolving, &config(), 1_000),
+            Nat::from(1_200u64)
+        );
+    }
+
+    #[test]
+    fn short_or_unstaked_positions_have_no_power() {
+        let dissolving = position(DissolveState::Dissolving { dissolve_at: 1_050 });
+        assert_eq!(voting_power(&dissolving, &config(), 1_000), Nat::from(0u64));
+
+        let mut unstaked = position(DissolveState::Locked);
+        unstaked.unstaked_at = Some(0);
+        assert_eq!(voting_power(&unstaked, &config(), 0), Nat::from(0u64));
+    }
+
+    #[test]
+    fn stakes_dissolving_before_the_vote_closes_do_not_count() {
+        let locked = position(DissolveState::Locked);
+        let dissolving = position(DissolveState::Dissolving { dissolve_at: 1_300 });
+        let positions = [locked, dissolving];
+
+        assert_eq!(weight_of(&positions, &config(), 1_000, 1_200), 2_800);
+        assert_eq!(weight_of(&positions, &config(), 1_000, 1_400), 1_500);
+        assert_eq!(weight_of(&positions, &config(), 1_000, 1_600), 0);
+    }
+}
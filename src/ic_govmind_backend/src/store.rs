@@ -1,28 +1,38 @@
-use candid::{CandidType, Decode, Deserialize, Encode, Principal};
+use candid::{CandidType, Decode, Deserialize, Encode, Nat, Principal};
 use ic_cdk_timers::TimerId;
 use ic_management_canister_types::{
     EcdsaKeyId, EcdsaPublicKeyResult, SchnorrKeyId, SchnorrPublicKeyResult,
 };
-use std::{cell::RefCell, collections::HashMap};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+};
 
 use ciborium::{from_reader, into_writer};
 use ic_govmind_types::{
-    chain::BlockchainConfig,
-    dao::{ChainType, Committee, Dao, DaoAsset, DaoMember, DistributionRecord, Proposal},
+    chain::{
+        BlockchainConfig, ChainConfigAction, ChainConfigChange, EvmDeposit, EvmTransaction,
+        EvmTxPreview, RpcProviderHealth,
+    },
+    cketh::{BridgeOperation, CkEthConfig},
+    dao::{
+        Airdrop, ChainType, Committee, Dao, DaoAsset, DaoMember, DistributionModel,
+        DistributionPayout, DistributionRecord, DistributionStatus, DistributionTotal,
+        DistributionType, EvmTokenDeployment, Proposal, RewardEvent, StakePosition, StakingConfig,
+        SupplyChange, SupplyChangeKind, VestingGrant, VotingRewardsConfig,
+    },
+    icrc::LedgerUpgrade,
+    price::{PriceFeedConfig, TokenPrice},
 };
 use ic_stable_structures::{
     memory_manager::{MemoryId, MemoryManager, VirtualMemory},
     storable::Bound,
     DefaultMemoryImpl, StableBTreeMap, StableCell, Storable,
 };
-use ic_web3_rs::ic::KeyInfo;
 use serde::Serialize;
-use std::borrow::Cow;
+use std::{borrow::Cow, str::FromStr};
 
-use crate::{
-    types::{KeyEnvironment, NextIdType},
-    ECDSA_SIGN_CYCLES,
-};
+use crate::types::{Erc20Approval, EvmNonceState, KeyEnvironment, NextIdType, SupplyTransfer};
 
 #[derive(CandidType, Default, Debug, Serialize, Deserialize, Clone)]
 pub struct State {
@@ -40,6 +50,34 @@ pub struct State {
     pub key_env: KeyEnvironment,
     pub chain_config: Vec<BlockchainConfig>,
     pub next_ids: HashMap<String, u64>,
+    #[serde(default)]
+    pub evm_nonces: HashMap<String, EvmNonceState>, // "{chain_id}:{address}" → nonce state
+    #[serde(default)]
+    pub erc20_approvals: HashMap<String, Erc20Approval>, // "{chain_id}:{token}:{owner}:{spender}" → approval
+    #[serde(default)]
+    pub cketh_config: Option<CkEthConfig>,
+    #[serde(default)]
+    pub evm_deposit_cursors: HashMap<u64, u64>, // chain_id → last indexed block
+    #[serde(default)]
+    pub price_feed: Option<PriceFeedConfig>,
+    #[serde(default)]
+    pub token_prices: HashMap<String, TokenPrice>, // upper-case symbol → last fetched price
+    #[serde(default)]
+    pub base_token_deployment: Option<EvmTokenDeployment>, // latest ERC-20 base token deployment
+    #[serde(default)]
+    pub vesting_grants: BTreeMap<u64, VestingGrant>,
+    #[serde(default)]
+    pub airdrops: BTreeMap<u64, Airdrop>,
+    #[serde(default)]
+    pub stake_positions: BTreeMap<u64, StakePosition>,
+    #[serde(default)]
+    pub staking_config: Option<StakingConfig>,
+    #[serde(default)]
+    pub voting_rewards_config: Option<VotingRewardsConfig>,
+    #[serde(default)]
+    pub voting_rewards_since: u64, // votes cast earlier, before rewards were enabled, earn none
+    #[serde(default)]
+    pub supply_transfers: BTreeMap<u64, SupplyTransfer>, // proposal_id → mint or burn in flight
 }
 
 impl Storable for State {
@@ -73,7 +111,7 @@ impl State {
     pub fn get_chain_config_by_type(&self, chain_type: &ChainType) -> Option<BlockchainConfig> {
         self.chain_config
             .iter()
-            .find(|cfg| &cfg.chain_type == chain_type)
+            .find(|cfg| cfg.is_chain(chain_type))
             .cloned()
     }
 }
@@ -106,7 +144,9 @@ impl Storable for DistributionRecordWrapper {
     const BOUND: Bound = Bound::Unbounded;
 
     fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        Decode!(bytes.as_ref(), Self)
+            .or_else(|_| Decode!(bytes.as_ref(), LegacyDistributionRecordWrapper).map(Into::into))
+            .unwrap()
     }
 
     fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
@@ -120,10 +160,265 @@ impl DistributionRecordWrapper {
     }
 }
 
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct DistributionTotalWrapper(pub DistributionTotal);
+
+impl Storable for DistributionTotalWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl DistributionTotalWrapper {
+    pub fn into_inner(self) -> DistributionTotal {
+        self.0
+    }
+}
+
+/// Records were stored with a `tx_result` of "Success: {block_index}" or "Error: {error:?}".
+#[derive(CandidType, Deserialize)]
+struct LegacyDistributionRecord {
+    distribution_type: DistributionType,
+    timestamp: u64,
+    recipient: String,
+    amount: Nat,
+    tx_result: String,
+}
+
+#[derive(CandidType, Deserialize)]
+struct LegacyDistributionRecordWrapper(LegacyDistributionRecord);
+
+impl From<LegacyDistributionRecordWrapper> for DistributionRecordWrapper {
+    fn from(LegacyDistributionRecordWrapper(legacy): LegacyDistributionRecordWrapper) -> Self {
+        let (status, block_index, error_code) = match legacy.tx_result.strip_prefix("Success: ") {
+            Some(block_index) => (
+                DistributionStatus::Completed,
+                Nat::from_str(block_index).ok(),
+                None,
+            ),
+            None => {
+                let error = legacy
+                    .tx_result
+                    .strip_prefix("Error: ")
+                    .unwrap_or(&legacy.tx_result)
+                    .to_string();
+                let code: String = error
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric())
+                    .collect();
+                (
+                    DistributionStatus::Failed(error),
+                    None,
+                    Some(code).filter(|code| !code.is_empty()),
+                )
+            }
+        };
+        DistributionRecordWrapper(DistributionRecord {
+            distribution_type: legacy.distribution_type,
+            timestamp: legacy.timestamp,
+            recipient: legacy.recipient,
+            amount: legacy.amount,
+            status,
+            block_index,
+            error_code,
+            payout_id: None,
+            tx_hash: None,
+        })
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct EvmTransactionWrapper(pub EvmTransaction);
+
+impl Storable for EvmTransactionWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl EvmTransactionWrapper {
+    pub fn into_inner(self) -> EvmTransaction {
+        self.0
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct BridgeOperationWrapper(pub BridgeOperation);
+
+impl Storable for BridgeOperationWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl BridgeOperationWrapper {
+    pub fn into_inner(self) -> BridgeOperation {
+        self.0
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct EvmDepositWrapper(pub EvmDeposit);
+
+impl Storable for EvmDepositWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl EvmDepositWrapper {
+    pub fn into_inner(self) -> EvmDeposit {
+        self.0
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct DistributionPayoutWrapper(pub DistributionPayout);
+
+impl Storable for DistributionPayoutWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl DistributionPayoutWrapper {
+    pub fn into_inner(self) -> DistributionPayout {
+        self.0
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct ChainConfigChangeWrapper(pub ChainConfigChange);
+
+impl Storable for ChainConfigChangeWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl ChainConfigChangeWrapper {
+    pub fn into_inner(self) -> ChainConfigChange {
+        self.0
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct SupplyChangeWrapper(pub SupplyChange);
+
+impl Storable for SupplyChangeWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl SupplyChangeWrapper {
+    pub fn into_inner(self) -> SupplyChange {
+        self.0
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct LedgerUpgradeWrapper(pub LedgerUpgrade);
+
+impl Storable for LedgerUpgradeWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl LedgerUpgradeWrapper {
+    pub fn into_inner(self) -> LedgerUpgrade {
+        self.0
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct RewardEventWrapper(pub RewardEvent);
+
+impl Storable for RewardEventWrapper {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(Encode!(self).unwrap())
+    }
+}
+
+impl RewardEventWrapper {
+    pub fn into_inner(self) -> RewardEvent {
+        self.0
+    }
+}
+
 pub type Memory = VirtualMemory<DefaultMemoryImpl>;
 pub const STATE_MEMORY_ID: MemoryId = MemoryId::new(0);
 pub const PROPOSALS_MEMORY_ID: MemoryId = MemoryId::new(1);
 pub const DISTRIBUTION_MEMORY_ID: MemoryId = MemoryId::new(2);
+pub const EVM_TRANSACTIONS_MEMORY_ID: MemoryId = MemoryId::new(3);
+pub const EVM_TX_HASHES_MEMORY_ID: MemoryId = MemoryId::new(4);
+pub const BRIDGE_OPERATIONS_MEMORY_ID: MemoryId = MemoryId::new(5);
+pub const EVM_DEPOSITS_MEMORY_ID: MemoryId = MemoryId::new(6);
+pub const CHAIN_CONFIG_CHANGES_MEMORY_ID: MemoryId = MemoryId::new(7);
+pub const DISTRIBUTION_PAYOUTS_MEMORY_ID: MemoryId = MemoryId::new(8);
+pub const SUPPLY_CHANGES_MEMORY_ID: MemoryId = MemoryId::new(9);
+pub const LEDGER_UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(10);
+pub const REWARD_EVENTS_MEMORY_ID: MemoryId = MemoryId::new(11);
+pub const PAYOUT_TX_HASHES_MEMORY_ID: MemoryId = MemoryId::new(12);
+pub const OPEN_EVM_TRANSACTIONS_MEMORY_ID: MemoryId = MemoryId::new(13);
+pub const PENDING_PAYOUTS_MEMORY_ID: MemoryId = MemoryId::new(14);
+pub const VOTING_REWARD_PAYOUTS_MEMORY_ID: MemoryId = MemoryId::new(15);
+pub const DISTRIBUTION_TOTALS_MEMORY_ID: MemoryId = MemoryId::new(16);
 
 thread_local! {
     pub static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
@@ -149,7 +444,100 @@ thread_local! {
         )
     );
 
+    static EVM_TRANSACTIONS: RefCell<StableBTreeMap<u64, EvmTransactionWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(EVM_TRANSACTIONS_MEMORY_ID))
+        )
+    );
+
+    // Lower-case transaction hash → id in EVM_TRANSACTIONS.
+    static EVM_TX_HASHES: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(EVM_TX_HASHES_MEMORY_ID))
+        )
+    );
+
+    static BRIDGE_OPERATIONS: RefCell<StableBTreeMap<u64, BridgeOperationWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(BRIDGE_OPERATIONS_MEMORY_ID))
+        )
+    );
+
+    static EVM_DEPOSITS: RefCell<StableBTreeMap<u64, EvmDepositWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(EVM_DEPOSITS_MEMORY_ID))
+        )
+    );
+
+    static CHAIN_CONFIG_CHANGES: RefCell<StableBTreeMap<u64, ChainConfigChangeWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(CHAIN_CONFIG_CHANGES_MEMORY_ID))
+        )
+    );
+
+    static DISTRIBUTION_PAYOUTS: RefCell<StableBTreeMap<String, DistributionPayoutWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(DISTRIBUTION_PAYOUTS_MEMORY_ID))
+        )
+    );
+
+    static SUPPLY_CHANGES: RefCell<StableBTreeMap<u64, SupplyChangeWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(SUPPLY_CHANGES_MEMORY_ID))
+        )
+    );
+
+    static LEDGER_UPGRADES: RefCell<StableBTreeMap<u64, LedgerUpgradeWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(LEDGER_UPGRADES_MEMORY_ID))
+        )
+    );
+
+    static REWARD_EVENTS: RefCell<StableBTreeMap<u64, RewardEventWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(REWARD_EVENTS_MEMORY_ID))
+        )
+    );
+
+    // Lower-case `evm_tx_hash` → id in DISTRIBUTION_PAYOUTS.
+    static PAYOUT_TX_HASHES: RefCell<StableBTreeMap<String, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(PAYOUT_TX_HASHES_MEMORY_ID))
+        )
+    );
+
+    // Ids of the pending and replaced transactions in EVM_TRANSACTIONS.
+    static OPEN_EVM_TRANSACTIONS: RefCell<StableBTreeMap<u64, (), Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(OPEN_EVM_TRANSACTIONS_MEMORY_ID))
+        )
+    );
+
+    // Id → `scheduled_at` of the pending payouts in DISTRIBUTION_PAYOUTS.
+    static PENDING_PAYOUTS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(PENDING_PAYOUTS_MEMORY_ID))
+        )
+    );
+
+    // `{recipient} {id}` of the voting reward payouts in DISTRIBUTION_PAYOUTS.
+    static VOTING_REWARD_PAYOUTS: RefCell<StableBTreeMap<String, (), Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(VOTING_REWARD_PAYOUTS_MEMORY_ID))
+        )
+    );
+
+    // Recipient → running total over the records in DISTRIBUTION_HISTORY.
+    static DISTRIBUTION_TOTALS: RefCell<StableBTreeMap<String, DistributionTotalWrapper, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(DISTRIBUTION_TOTALS_MEMORY_ID))
+        )
+    );
+
     pub static TIMER_IDS: RefCell<Vec<TimerId>> = RefCell::new(Vec::new());
+
+    // Heap only: provider statistics start over after an upgrade.
+    static RPC_PROVIDER_HEALTH: RefCell<BTreeMap<(u64, String), RpcProviderHealth>> = RefCell::new(BTreeMap::new());
 }
 
 pub fn read_memory_manager<F, R>(f: F) -> R
@@ -262,7 +650,7 @@ pub mod state {
         state::with_mut(|s| {
             if s.chain_config
                 .iter()
-                .any(|c| c.chain_type == new_chain.chain_type)
+                .any(|c| c.is_chain(&new_chain.chain_type))
             {
                 return Err(format!("Chain {:?} already exists", new_chain.chain_type));
             }
@@ -277,11 +665,7 @@ pub mod state {
         f: impl FnOnce(&mut BlockchainConfig),
     ) -> Result<(), String> {
         state::with_mut(|s| {
-            if let Some(chain) = s
-                .chain_config
-                .iter_mut()
-                .find(|c| c.chain_type == chain_type)
-            {
+            if let Some(chain) = s.chain_config.iter_mut().find(|c| c.is_chain(&chain_type)) {
                 f(chain);
                 Ok(())
             } else {
@@ -290,49 +674,197 @@ pub mod state {
         })
     }
 
-    pub fn get_nonce(chain_type: &ChainType) -> Option<u64> {
-        let chain = get_chain_config(chain_type)?;
-        chain.nonce.clone()
+    pub fn remove_chain_config(chain_type: &ChainType) -> Result<BlockchainConfig, String> {
+        state::with_mut(|s| {
+            let index = s
+                .chain_config
+                .iter()
+                .position(|c| c.is_chain(chain_type))
+                .ok_or_else(|| format!("Chain {:?} not found", chain_type))?;
+            Ok(s.chain_config.remove(index))
+        })
+    }
+
+    pub fn record_chain_config_change(
+        chain_type: ChainType,
+        action: ChainConfigAction,
+        caller: Principal,
+    ) {
+        CHAIN_CONFIG_CHANGES.with_borrow_mut(|map| {
+            let id = map.len();
+            let change = ChainConfigChange {
+                id,
+                chain_type,
+                action,
+                caller,
+                changed_at: crate::utils::current_time_secs(),
+            };
+            map.insert(id, ChainConfigChangeWrapper(change));
+        });
     }
 
-    pub fn increment_nonce(chain_type: ChainType) -> Result<(), String> {
-        update_chain_config(chain_type, |chain_config| {
-            let next = chain_config.nonce.unwrap_or(0).saturating_add(1);
-            chain_config.nonce = Some(next);
+    pub fn list_chain_config_changes(start: u64, limit: usize) -> Vec<ChainConfigChange> {
+        CHAIN_CONFIG_CHANGES.with_borrow(|map| {
+            map.range(start..)
+                .take(limit)
+                .map(|(_, w)| w.into_inner())
+                .collect()
         })
     }
 
-    pub fn set_nonce(chain_type: ChainType, nonce: Option<u64>) -> Result<(), String> {
-        update_chain_config(chain_type, |chain_config| {
-            chain_config.nonce = nonce;
+    /// Starts the mint or burn of `proposal_id`, or resumes the one it left in flight. Returns
+    /// the transfer to send.
+    pub fn begin_supply_transfer(proposal_id: u64, transfer: SupplyTransfer) -> SupplyTransfer {
+        state::with_mut(|s| {
+            s.supply_transfers
+                .entry(proposal_id)
+                .or_insert(transfer)
+                .clone()
         })
     }
 
-    pub fn get_chain_id(chain_type: &ChainType) -> Option<u64> {
-        let chain = get_chain_config(chain_type)?;
-        chain.rpc_config.as_ref()?.chain_id
+    pub fn end_supply_transfer(proposal_id: u64) {
+        state::with_mut(|s| s.supply_transfers.remove(&proposal_id));
     }
 
-    pub fn get_key_info() -> Result<KeyInfo, String> {
-        let ecdsa_key = get_ecdsa_key_id();
-        let derivation_path = state::with(|r| r.derivation_path.clone());
+    /// Total of the mints in flight, other than that of `proposal_id`.
+    pub fn minting_supply(proposal_id: u64) -> Nat {
+        state::with(|s| {
+            s.supply_transfers
+                .iter()
+                .filter(|(id, transfer)| {
+                    **id != proposal_id && transfer.kind == SupplyChangeKind::Mint
+                })
+                .fold(Nat::from(0u64), |sum, (_, transfer)| {
+                    sum + transfer.amount.clone()
+                })
+        })
+    }
 
-        let key_info = KeyInfo {
-            derivation_path,
-            key_name: ecdsa_key.name.clone(),
-            ecdsa_sign_cycles: Some(ECDSA_SIGN_CYCLES),
-        };
+    /// Records a mint or burn, applies it to the base token's total supply and ends its transfer.
+    /// Returns the change with its id and the resulting supply filled in.
+    pub fn record_supply_change(mut change: SupplyChange) -> Result<SupplyChange, String> {
+        state::with_mut(|s| -> Result<(), String> {
+            let dao = s.org_info.as_mut().ok_or("DAO not initialized")?;
+            let supply = &mut dao.base_token.total_supply;
+            apply_supply_change(supply, &change.kind, &change.amount)?;
+            change.recorded_supply = supply.clone();
+            s.supply_transfers.remove(&change.proposal_id);
+            Ok(())
+        })?;
+        SUPPLY_CHANGES.with_borrow_mut(|map| {
+            change.id = map.len();
+            map.insert(change.id, SupplyChangeWrapper(change.clone()));
+        });
+        Ok(change)
+    }
 
-        Ok(key_info)
+    pub(super) fn apply_supply_change(
+        supply: &mut Nat,
+        kind: &SupplyChangeKind,
+        amount: &Nat,
+    ) -> Result<(), String> {
+        match kind {
+            SupplyChangeKind::Mint => *supply += amount.clone(),
+            SupplyChangeKind::Burn if *supply >= *amount => {
+                *supply = supply.clone() - amount.clone()
+            }
+            SupplyChangeKind::Burn => {
+                return Err(format!(
+                    "Burning {} exceeds the recorded supply of {}",
+                    amount, supply
+                ))
+            }
+        }
+        Ok(())
     }
 
-    /// Add a new committee to the DAO
-    pub fn add_committee(new_committee: Committee) -> Result<(), String> {
-        let mut dao = get_dao_info().ok_or_else(|| "DAO not initialized".to_string())?;
+    pub fn set_supply_change_ledger_supply(id: u64, ledger_supply: Option<Nat>) {
+        SUPPLY_CHANGES.with_borrow_mut(|map| {
+            if let Some(SupplyChangeWrapper(mut change)) = map.get(&id) {
+                change.ledger_supply = ledger_supply;
+                map.insert(id, SupplyChangeWrapper(change));
+            }
+        });
+    }
 
-        if dao
-            .committees
-            .iter()
+    pub fn list_supply_changes(start: u64, limit: usize) -> Vec<SupplyChange> {
+        SUPPLY_CHANGES.with_borrow(|map| {
+            map.range(start..)
+                .take(limit)
+                .map(|(_, w)| w.into_inner())
+                .collect()
+        })
+    }
+
+    pub fn record_ledger_upgrade(mut upgrade: LedgerUpgrade) -> u64 {
+        LEDGER_UPGRADES.with_borrow_mut(|map| {
+            upgrade.id = map.len();
+            map.insert(upgrade.id, LedgerUpgradeWrapper(upgrade.clone()));
+            upgrade.id
+        })
+    }
+
+    pub fn list_ledger_upgrades(start: u64, limit: usize) -> Vec<LedgerUpgrade> {
+        LEDGER_UPGRADES.with_borrow(|map| {
+            map.range(start..)
+                .take(limit)
+                .map(|(_, w)| w.into_inner())
+                .collect()
+        })
+    }
+
+    pub fn get_base_token_deployment() -> Option<EvmTokenDeployment> {
+        state::with(|s| s.base_token_deployment.clone())
+    }
+
+    pub fn set_base_token_deployment(deployment: EvmTokenDeployment) {
+        state::with_mut(|s| s.base_token_deployment = Some(deployment));
+    }
+
+    pub fn get_chain_id(chain_type: &ChainType) -> Option<u64> {
+        get_chain_config(chain_type)?.chain_id()
+    }
+
+    pub fn get_chain_config_by_chain_id(chain_id: u64) -> Option<BlockchainConfig> {
+        state::with(|s| {
+            s.chain_config
+                .iter()
+                .find(|c| c.chain_type.is_evm() && c.chain_id() == Some(chain_id))
+                .cloned()
+        })
+    }
+
+    /// Every registered EVM chain.
+    pub fn list_evm_chain_configs() -> Vec<BlockchainConfig> {
+        state::with(|s| {
+            s.chain_config
+                .iter()
+                .filter(|c| c.chain_type.is_evm())
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Finds the member registered with `principal`.
+    pub fn find_member_by_principal(principal: Principal) -> Option<DaoMember> {
+        state::with(|s| {
+            let dao_members = s.org_info.iter().flat_map(|dao| dao.members.iter());
+            s.members
+                .values()
+                .chain(dao_members)
+                .find(|m| m.icp_principal == Some(principal))
+                .cloned()
+        })
+    }
+
+    /// Add a new committee to the DAO
+    pub fn add_committee(new_committee: Committee) -> Result<(), String> {
+        let mut dao = get_dao_info().ok_or_else(|| "DAO not initialized".to_string())?;
+
+        if dao
+            .committees
+            .iter()
             .any(|c| c.committee_type == new_committee.committee_type)
         {
             return Err(format!(
@@ -374,7 +906,7 @@ pub mod state {
 pub mod proposals {
     use super::*;
     use crate::utils::current_time_secs;
-    use ic_govmind_types::dao::{Proposal, ProposalStatus};
+    use ic_govmind_types::dao::{Proposal, ProposalStatus, Vote};
 
     pub fn create_proposal(
         title: String,
@@ -396,7 +928,10 @@ pub mod proposals {
             status: ProposalStatus::Active,
             votes: Vec::new(),
             metadata: None,
-            committee_id: committee_id
+            committee_id: committee_id,
+            tx_previews: None,
+            reward_event_round: None,
+            action_hash: None,
         };
 
         PROPOSALS_STORE.with(|store| {
@@ -430,6 +965,119 @@ pub mod proposals {
         proposals
     }
 
+    /// Returns the proposal authorizing a treasury action, which must have passed and not yet
+    /// been executed.
+    pub fn get_executable_proposal(proposal_id: u64) -> Result<Proposal, String> {
+        let proposal = get_proposal(proposal_id)
+            .ok_or_else(|| format!("Proposal {} not found", proposal_id))?;
+
+        match proposal.status {
+            ProposalStatus::Passed => Ok(proposal),
+            status => Err(format!(
+                "Proposal {} is {:?}, only passed proposals can be executed",
+                proposal_id, status
+            )),
+        }
+    }
+
+    /// Attaches a transaction preview to a proposal that is still open for voting.
+    pub fn attach_tx_preview(proposal_id: u64, preview: EvmTxPreview) -> Result<(), String> {
+        PROPOSALS_STORE.with(|store| {
+            let mut store_mut = store.borrow_mut();
+            let mut wrapper = store_mut
+                .get(&proposal_id)
+                .ok_or_else(|| format!("Proposal {} not found", proposal_id))?;
+
+            match wrapper.0.status {
+                ProposalStatus::Draft | ProposalStatus::Active => {}
+                status => {
+                    return Err(format!(
+                        "Proposal {} is {:?}, previews can only be attached while voting",
+                        proposal_id, status
+                    ))
+                }
+            }
+
+            wrapper
+                .0
+                .tx_previews
+                .get_or_insert_with(Vec::new)
+                .push(preview);
+            store_mut.insert(proposal_id, wrapper);
+            Ok(())
+        })
+    }
+
+    /// Binds the treasury action a proposal authorizes. This is only possible once, and before
+    /// anyone has voted, so the votes approve one fixed action.
+    pub fn bind_action(proposal_id: u64, action_hash: String) -> Result<(), String> {
+        PROPOSALS_STORE.with(|store| {
+            let mut store_mut = store.borrow_mut();
+            let mut wrapper = store_mut
+                .get(&proposal_id)
+                .ok_or_else(|| format!("Proposal {} not found", proposal_id))?;
+
+            match wrapper.0.status {
+                ProposalStatus::Draft | ProposalStatus::Active => {}
+                status => {
+                    return Err(format!(
+                        "Proposal {} is {:?}, actions can only be bound while voting",
+                        proposal_id, status
+                    ))
+                }
+            }
+            if !wrapper.0.votes.is_empty() {
+                return Err(format!(
+                    "Proposal {} already has votes, its action can no longer be bound",
+                    proposal_id
+                ));
+            }
+            if wrapper.0.action_hash.is_some() {
+                return Err(format!("Proposal {} already has an action", proposal_id));
+            }
+
+            wrapper.0.action_hash = Some(action_hash);
+            store_mut.insert(proposal_id, wrapper);
+            Ok(())
+        })
+    }
+
+    /// Records a vote on a proposal that is still open, once per voter.
+    pub fn add_vote(proposal_id: u64, vote: Vote) -> Result<(), String> {
+        PROPOSALS_STORE.with(|store| {
+            let mut store_mut = store.borrow_mut();
+            let mut wrapper = store_mut
+                .get(&proposal_id)
+                .ok_or_else(|| format!("Proposal {} not found", proposal_id))?;
+
+            if !matches!(wrapper.0.status, ProposalStatus::Active)
+                || vote.voted_at >= wrapper.0.expires_at
+            {
+                return Err(format!("Proposal {} is not open for voting", proposal_id));
+            }
+            if wrapper.0.votes.iter().any(|v| v.voter_id == vote.voter_id) {
+                return Err(format!("Already voted on proposal {}", proposal_id));
+            }
+
+            wrapper.0.votes.push(vote);
+            store_mut.insert(proposal_id, wrapper);
+            Ok(())
+        })
+    }
+
+    /// Marks the votes of the given proposals as rewarded in `round`.
+    pub fn set_reward_event_round(proposal_ids: &[u64], round: u64) {
+        PROPOSALS_STORE.with(|store| {
+            let mut store_mut = store.borrow_mut();
+            for proposal_id in proposal_ids {
+                if let Some(mut wrapper) = store_mut.get(proposal_id) {
+                    wrapper.0.reward_event_round = Some(round);
+                    store_mut.insert(*proposal_id, wrapper);
+                }
+            }
+        })
+    }
+
     pub fn update_proposal_status(proposal_id: u64, status: ProposalStatus) -> Result<(), String> {
         PROPOSALS_STORE.with(|store| {
             let mut store_mut = store.borrow_mut();
@@ -473,7 +1121,1143 @@ pub mod distribution {
         })
     }
 
+    /// Records matching `filter` with an id of at least `start`, oldest first.
+    pub fn filter_distribution_records(
+        start: u64,
+        limit: usize,
+        filter: impl Fn(&DistributionRecord) -> bool,
+    ) -> Vec<(u64, DistributionRecord)> {
+        with_distribution_records(start, |records| {
+            records
+                .filter(|(_, record)| filter(record))
+                .take(limit)
+                .collect()
+        })
+    }
+
+    /// Runs `f` over the records with an id of at least `start`, oldest first, without loading
+    /// them all.
+    pub fn with_distribution_records<R>(
+        start: u64,
+        f: impl FnOnce(&mut dyn Iterator<Item = (u64, DistributionRecord)>) -> R,
+    ) -> R {
+        DISTRIBUTION_HISTORY
+            .with_borrow(|map| f(&mut map.range(start..).map(|(id, w)| (id, w.into_inner()))))
+    }
+
+    pub fn get_total(recipient: &str) -> Option<DistributionTotal> {
+        DISTRIBUTION_TOTALS
+            .with_borrow(|map| map.get(&recipient.to_string()).map(|w| w.into_inner()))
+    }
+
+    pub fn set_total(total: DistributionTotal) {
+        DISTRIBUTION_TOTALS.with_borrow_mut(|map| {
+            map.insert(total.recipient.clone(), DistributionTotalWrapper(total))
+        });
+    }
+
+    /// Running totals of every recipient, sorted by recipient.
+    pub fn list_totals() -> Vec<DistributionTotal> {
+        DISTRIBUTION_TOTALS.with_borrow(|map| map.iter().map(|(_, w)| w.into_inner()).collect())
+    }
+
+    pub fn has_totals() -> bool {
+        DISTRIBUTION_TOTALS.with_borrow(|map| !map.is_empty())
+    }
+
     pub fn get_next_distribution_id() -> u64 {
         state::with_mut(|s| s.get_next_id(NextIdType::Distribution))
     }
+
+    /// Applies `f` to the base token's distribution model, if there is one.
+    pub fn update_model<R>(f: impl FnOnce(&mut DistributionModel) -> R) -> Option<R> {
+        state::with_mut(|s| {
+            s.org_info
+                .as_mut()
+                .and_then(|dao| dao.base_token.distribution_model.as_mut())
+                .map(f)
+        })
+    }
+
+    /// Stores a payout unless one with the same id was already planned. Returns whether it is new.
+    pub fn add_payout(payout: DistributionPayout) -> bool {
+        DISTRIBUTION_PAYOUTS.with(|map| {
+            let mut map = map.borrow_mut();
+            if map.contains_key(&payout.id) {
+                return false;
+            }
+            if payout.status == DistributionStatus::Pending {
+                PENDING_PAYOUTS
+                    .with_borrow_mut(|index| index.insert(payout.id.clone(), payout.scheduled_at));
+            }
+            if payout.distribution_type == DistributionType::VotingReward {
+                VOTING_REWARD_PAYOUTS.with_borrow_mut(|index| {
+                    index.insert(format!("{} {}", payout.recipient, payout.id), ())
+                });
+            }
+            map.insert(payout.id.clone(), DistributionPayoutWrapper(payout));
+            true
+        })
+    }
+
+    pub fn get_payout(id: &str) -> Option<DistributionPayout> {
+        DISTRIBUTION_PAYOUTS.with(|map| map.borrow().get(&id.to_string()).map(|w| w.into_inner()))
+    }
+
+    pub fn update_payout(
+        id: &str,
+        f: impl FnOnce(&mut DistributionPayout),
+    ) -> Result<DistributionPayout, String> {
+        DISTRIBUTION_PAYOUTS.with(|map| {
+            let mut map = map.borrow_mut();
+            let mut wrapper = map
+                .get(&id.to_string())
+                .ok_or_else(|| format!("Distribution payout {} not found", id))?;
+            let previous_tx_hash = wrapper.0.evm_tx_hash.clone();
+            f(&mut wrapper.0);
+            wrapper.0.updated_at = crate::utils::current_time_secs();
+            map.insert(id.to_string(), wrapper.clone());
+            PENDING_PAYOUTS.with_borrow_mut(|index| {
+                if wrapper.0.status == DistributionStatus::Pending {
+                    index.insert(id.to_string(), wrapper.0.scheduled_at);
+                } else {
+                    index.remove(&id.to_string());
+                }
+            });
+            if wrapper.0.evm_tx_hash != previous_tx_hash {
+                PAYOUT_TX_HASHES.with_borrow_mut(|index| {
+                    if let Some(tx_hash) = previous_tx_hash {
+                        index.remove(&tx_hash.to_lowercase());
+                    }
+                    if let Some(tx_hash) = &wrapper.0.evm_tx_hash {
+                        index.insert(tx_hash.to_lowercase(), id.to_string());
+                    }
+                });
+            }
+            Ok(wrapper.into_inner())
+        })
+    }
+
+    /// The payout that sent the EVM transaction `tx_hash`, if any.
+    pub fn find_payout_by_tx_hash(tx_hash: &str) -> Option<DistributionPayout> {
+        PAYOUT_TX_HASHES
+            .with_borrow(|index| index.get(&tx_hash.to_lowercase()))
+            .and_then(|id| get_payout(&id))
+    }
+
+    pub fn list_payouts(start: u64, limit: usize) -> Vec<DistributionPayout> {
+        DISTRIBUTION_PAYOUTS.with(|map| {
+            map.borrow()
+                .iter()
+                .skip(start as usize)
+                .take(limit)
+                .map(|(_, w)| w.into_inner())
+                .collect()
+        })
+    }
+
+    /// Payouts the ledger has not accepted yet, oldest first.
+    pub fn list_pending_payouts() -> Vec<DistributionPayout> {
+        let mut pending: Vec<(String, u64)> =
+            PENDING_PAYOUTS.with_borrow(|index| index.iter().collect());
+        pending.sort_by_key(|(_, scheduled_at)| *scheduled_at);
+        pending
+            .into_iter()
+            .filter_map(|(id, _)| get_payout(&id))
+            .collect()
+    }
+
+    /// Whether every payout whose id starts with `prefix` was accepted by the ledger.
+    pub fn all_completed(prefix: &str) -> bool {
+        DISTRIBUTION_PAYOUTS.with(|map| {
+            map.borrow()
+                .range(prefix.to_string()..)
+                .take_while(|(id, _)| id.starts_with(prefix))
+                .all(|(_, w)| w.0.status == DistributionStatus::Completed)
+        })
+    }
+}
+
+pub mod vesting {
+    use super::*;
+
+    pub fn add_grant(mut grant: VestingGrant) -> u64 {
+        state::with_mut(|s| {
+            let id = s.get_next_id(NextIdType::VestingGrant);
+            grant.id = id;
+            s.vesting_grants.insert(id, grant);
+            id
+        })
+    }
+
+    pub fn get_grant(id: u64) -> Option<VestingGrant> {
+        state::with(|s| s.vesting_grants.get(&id).cloned())
+    }
+
+    pub fn update_grant<R>(
+        id: u64,
+        f: impl FnOnce(&mut VestingGrant) -> Result<R, String>,
+    ) -> Result<R, String> {
+        state::with_mut(|s| {
+            let grant = s
+                .vesting_grants
+                .get_mut(&id)
+                .ok_or_else(|| format!("Vesting grant {} not found", id))?;
+            f(grant)
+        })
+    }
+
+    pub fn list_grants() -> Vec<VestingGrant> {
+        state::with(|s| s.vesting_grants.values().cloned().collect())
+    }
+}
+
+pub mod airdrops {
+    use super::*;
+
+    pub fn add_airdrop(mut airdrop: Airdrop) -> u64 {
+        state::with_mut(|s| {
+            let id = s.get_next_id(NextIdType::Airdrop);
+            airdrop.id = id;
+            s.airdrops.insert(id, airdrop);
+            id
+        })
+    }
+
+    pub fn get_airdrop(id: u64) -> Option<Airdrop> {
+        state::with(|s| s.airdrops.get(&id).cloned())
+    }
+
+    pub fn update_airdrop<R>(
+        id: u64,
+        f: impl FnOnce(&mut Airdrop) -> Result<R, String>,
+    ) -> Result<R, String> {
+        state::with_mut(|s| {
+            let airdrop = s
+                .airdrops
+                .get_mut(&id)
+                .ok_or_else(|| format!("Airdrop {} not found", id))?;
+            f(airdrop)
+        })
+    }
+
+    pub fn list_airdrops() -> Vec<Airdrop> {
+        state::with(|s| s.airdrops.values().cloned().collect())
+    }
+}
+
+pub mod staking {
+    use super::*;
+
+    pub fn get_config() -> StakingConfig {
+        state::with(|s| s.staking_config.clone()).unwrap_or_default()
+    }
+
+    pub fn set_config(config: StakingConfig) {
+        state::with_mut(|s| s.staking_config = Some(config));
+    }
+
+    pub fn add_position(mut position: StakePosition) -> u64 {
+        state::with_mut(|s| {
+            let id = s.get_next_id(NextIdType::StakePosition);
+            position.id = id;
+            s.stake_positions.insert(id, position);
+            id
+        })
+    }
+
+    pub fn get_position(id: u64) -> Option<StakePosition> {
+        state::with(|s| s.stake_positions.get(&id).cloned())
+    }
+
+    pub fn update_position<R>(
+        id: u64,
+        f: impl FnOnce(&mut StakePosition) -> Result<R, String>,
+    ) -> Result<R, String> {
+        state::with_mut(|s| {
+            let position = s
+                .stake_positions
+                .get_mut(&id)
+                .ok_or_else(|| format!("Stake position {} not found", id))?;
+            f(position)
+        })
+    }
+
+    pub fn list_positions(owner: Option<Principal>) -> Vec<StakePosition> {
+        state::with(|s| {
+            s.stake_positions
+                .values()
+                .filter(|position| owner.is_none_or(|owner| position.owner == owner))
+                .cloned()
+                .collect()
+        })
+    }
+}
+
+pub mod rewards {
+    use super::*;
+
+    pub fn get_config() -> Option<VotingRewardsConfig> {
+        state::with(|s| s.voting_rewards_config.clone())
+    }
+
+    /// Only votes cast from `now` on earn rewards when they are enabled, not those cast before.
+    pub fn set_config(config: Option<VotingRewardsConfig>, now: u64) {
+        state::with_mut(|s| {
+            if s.voting_rewards_config.is_none() && config.is_some() {
+                s.voting_rewards_since = now;
+            }
+            s.voting_rewards_config = config;
+        });
+    }
+
+    /// When rewards were last enabled; earlier votes earn none.
+    pub fn rewarded_votes_since() -> u64 {
+        state::with(|s| s.voting_rewards_since)
+    }
+
+    /// The voting reward payouts to `recipient`, an ICRC-1 account.
+    pub fn list_payouts(recipient: &str) -> Vec<DistributionPayout> {
+        let prefix = format!("{} ", recipient);
+        let ids: Vec<String> = VOTING_REWARD_PAYOUTS.with_borrow(|index| {
+            index
+                .range(prefix.clone()..)
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .map(|(key, _)| key[prefix.len()..].to_string())
+                .collect()
+        });
+        ids.iter()
+            .filter_map(|id| distribution::get_payout(id))
+            .collect()
+    }
+
+    pub fn last_event() -> Option<RewardEvent> {
+        REWARD_EVENTS.with_borrow(|map| map.last_key_value().map(|(_, w)| w.into_inner()))
+    }
+
+    /// Events are keyed by their round.
+    pub fn add_event(event: RewardEvent) {
+        REWARD_EVENTS.with_borrow_mut(|map| map.insert(event.round, RewardEventWrapper(event)));
+    }
+
+    /// Lists events from the `start`-th one, oldest first. Rounds start at 1.
+    pub fn list_events(start: u64, limit: usize) -> Vec<RewardEvent> {
+        REWARD_EVENTS.with_borrow(|map| {
+            map.range(start.saturating_add(1)..)
+                .take(limit)
+                .map(|(_, w)| w.into_inner())
+                .collect()
+        })
+    }
+}
+
+pub mod nonces {
+    use super::*;
+
+    fn nonce_key(chain_id: u64, address: &str) -> String {
+        format!("{}:{}", chain_id, address.to_lowercase())
+    }
+
+    /// Reserves the next nonce for `address`, never handing out a nonce below the on-chain count.
+    /// Must not await between reading `onchain_nonce` and calling this, so that concurrent
+    /// transfers from the same address always get distinct nonces.
+    pub fn reserve_nonce(chain_id: u64, address: &str, onchain_nonce: u64) -> u64 {
+        state::with_mut(|s| {
+            let entry = s
+                .evm_nonces
+                .entry(nonce_key(chain_id, address))
+                .or_default();
+
+            entry.released.retain(|nonce| *nonce >= onchain_nonce);
+            if entry.next_nonce < onchain_nonce {
+                entry.next_nonce = onchain_nonce;
+            }
+
+            if !entry.released.is_empty() {
+                return entry.released.remove(0);
+            }
+
+            let nonce = entry.next_nonce;
+            entry.next_nonce += 1;
+            nonce
+        })
+    }
+
+    /// Hands back a nonce whose transaction was never broadcast, so the next transfer fills the gap.
+    pub fn release_nonce(chain_id: u64, address: &str, nonce: u64) {
+        state::with_mut(|s| {
+            let Some(entry) = s.evm_nonces.get_mut(&nonce_key(chain_id, address)) else {
+                return;
+            };
+
+            if nonce >= entry.next_nonce || entry.released.contains(&nonce) {
+                return;
+            }
+
+            entry.released.push(nonce);
+            entry.released.sort_unstable();
+
+            // Collapse released nonces at the tip back into `next_nonce`.
+            while entry.released.last() == Some(&(entry.next_nonce - 1)) {
+                entry.released.pop();
+                entry.next_nonce -= 1;
+            }
+        })
+    }
+
+    /// Takes a released nonce out of the free list, used when it is filled directly.
+    pub fn claim_released_nonce(chain_id: u64, address: &str, nonce: u64) {
+        state::with_mut(|s| {
+            if let Some(entry) = s.evm_nonces.get_mut(&nonce_key(chain_id, address)) {
+                entry.released.retain(|released| *released != nonce);
+            }
+        })
+    }
+
+    pub fn get_nonce_state(chain_id: u64, address: &str) -> EvmNonceState {
+        state::with(|s| {
+            s.evm_nonces
+                .get(&nonce_key(chain_id, address))
+                .cloned()
+                .unwrap_or_default()
+        })
+    }
+}
+
+pub mod approvals {
+    use super::*;
+
+    fn approval_key(chain_id: u64, token_address: &str, owner: &str, spender: &str) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            chain_id,
+            token_address.to_lowercase(),
+            owner.to_lowercase(),
+            spender.to_lowercase()
+        )
+    }
+
+    /// Records an approval, replacing any earlier one for the same token, owner and spender.
+    pub fn upsert_approval(approval: Erc20Approval) {
+        state::with_mut(|s| {
+            let key = approval_key(
+                approval.chain_id,
+                &approval.token_address,
+                &approval.owner,
+                &approval.spender,
+            );
+            s.erc20_approvals.insert(key, approval);
+        })
+    }
+
+    pub fn remove_approval(
+        chain_id: u64,
+        token_address: &str,
+        owner: &str,
+        spender: &str,
+    ) -> Option<Erc20Approval> {
+        state::with_mut(|s| {
+            s.erc20_approvals
+                .remove(&approval_key(chain_id, token_address, owner, spender))
+        })
+    }
+
+    /// Stores the allowance last read from the chain for a tracked approval.
+    pub fn set_allowance(
+        chain_id: u64,
+        token_address: &str,
+        owner: &str,
+        spender: &str,
+        allowance: Nat,
+        checked_at: u64,
+    ) {
+        state::with_mut(|s| {
+            let key = approval_key(chain_id, token_address, owner, spender);
+            if let Some(approval) = s.erc20_approvals.get_mut(&key) {
+                approval.allowance = Some(allowance);
+                approval.checked_at = Some(checked_at);
+            }
+        })
+    }
+
+    pub fn list_approvals() -> Vec<Erc20Approval> {
+        state::with(|s| s.erc20_approvals.values().cloned().collect())
+    }
+}
+
+pub mod bridge {
+    use super::*;
+    use crate::utils::current_time_secs;
+    use ic_govmind_types::cketh::BridgeStatus;
+
+    pub fn get_cketh_config() -> Result<CkEthConfig, String> {
+        state::with(|s| s.cketh_config.clone())
+            .ok_or_else(|| "ckETH bridge is not configured".to_string())
+    }
+
+    pub fn set_cketh_config(config: CkEthConfig) {
+        state::with_mut(|s| s.cketh_config = Some(config));
+    }
+
+    /// Stores a new bridge operation and returns its id.
+    pub fn add_operation(mut op: BridgeOperation) -> u64 {
+        let id = state::with_mut(|s| s.get_next_id(NextIdType::Bridge));
+        op.id = id;
+        BRIDGE_OPERATIONS.with(|map| map.borrow_mut().insert(id, BridgeOperationWrapper(op)));
+        id
+    }
+
+    pub fn get_operation(id: u64) -> Option<BridgeOperation> {
+        BRIDGE_OPERATIONS.with(|map| map.borrow().get(&id).map(|w| w.into_inner()))
+    }
+
+    pub fn update_operation(
+        id: u64,
+        f: impl FnOnce(&mut BridgeOperation),
+    ) -> Result<BridgeOperation, String> {
+        BRIDGE_OPERATIONS.with(|map| {
+            let mut map = map.borrow_mut();
+            let mut wrapper = map
+                .get(&id)
+                .ok_or_else(|| format!("Bridge operation {} not found", id))?;
+            f(&mut wrapper.0);
+            wrapper.0.updated_at = current_time_secs();
+            map.insert(id, wrapper.clone());
+            Ok(wrapper.into_inner())
+        })
+    }
+
+    pub fn list_operations(start: u64, limit: usize) -> Vec<BridgeOperation> {
+        BRIDGE_OPERATIONS.with(|map| {
+            map.borrow()
+                .range(start..)
+                .take(limit)
+                .map(|(_, w)| w.into_inner())
+                .collect()
+        })
+    }
+
+    /// Operations still waiting on an Ethereum receipt or on the minter.
+    pub fn list_open_operations() -> Vec<BridgeOperation> {
+        BRIDGE_OPERATIONS.with(|map| {
+            map.borrow()
+                .iter()
+                .map(|(_, w)| w.into_inner())
+                .filter(|op| {
+                    matches!(
+                        op.status,
+                        BridgeStatus::Pending
+                            | BridgeStatus::AwaitingMinter
+                            | BridgeStatus::WithdrawalQueued
+                            | BridgeStatus::WithdrawalSent
+                    )
+                })
+                .collect()
+        })
+    }
+}
+
+pub mod deposits {
+    use super::*;
+
+    pub fn get_cursor(chain_id: u64) -> Option<u64> {
+        state::with(|s| s.evm_deposit_cursors.get(&chain_id).copied())
+    }
+
+    pub fn set_cursor(chain_id: u64, block_number: u64) {
+        state::with_mut(|s| s.evm_deposit_cursors.insert(chain_id, block_number));
+    }
+
+    /// Stores a deposit unless the same log was already indexed, returning its id if new.
+    pub fn add_deposit(mut deposit: EvmDeposit) -> Option<u64> {
+        if exists(
+            deposit.chain_id,
+            &deposit.tx_hash,
+            deposit.log_index,
+            deposit.block_number,
+        ) {
+            return None;
+        }
+
+        let id = state::with_mut(|s| s.get_next_id(NextIdType::Deposit));
+        deposit.id = id;
+        EVM_DEPOSITS.with(|map| map.borrow_mut().insert(id, EvmDepositWrapper(deposit)));
+        Some(id)
+    }
+
+    /// Deposits are indexed in block order, so only the most recent ones can hold a block
+    /// being rescanned.
+    fn exists(chain_id: u64, tx_hash: &str, log_index: u64, block_number: u64) -> bool {
+        EVM_DEPOSITS.with(|map| {
+            map.borrow()
+                .iter()
+                .rev()
+                .map(|(_, w)| w.into_inner())
+                .filter(|d| d.chain_id == chain_id)
+                .take_while(|d| d.block_number >= block_number)
+                .any(|d| d.tx_hash == tx_hash && d.log_index == log_index)
+        })
+    }
+
+    pub fn list_deposits(start: u64, limit: usize) -> Vec<EvmDeposit> {
+        EVM_DEPOSITS.with(|map| {
+            map.borrow()
+                .range(start..)
+                .take(limit)
+                .map(|(_, w)| w.into_inner())
+                .collect()
+        })
+    }
+
+    pub fn list_deposits_by_member(user_id: &str) -> Vec<EvmDeposit> {
+        EVM_DEPOSITS.with(|map| {
+            map.borrow()
+                .iter()
+                .map(|(_, w)| w.into_inner())
+                .filter(|d| d.member_id.as_deref() == Some(user_id))
+                .collect()
+        })
+    }
+
+    /// Finds the member whose registered `eth_address` sent a deposit.
+    pub fn find_member_by_eth_address(address: &str) -> Option<String> {
+        state::with(|s| {
+            let dao_members = s.org_info.iter().flat_map(|dao| dao.members.iter());
+            s.members
+                .values()
+                .chain(dao_members)
+                .find(|m| {
+                    m.eth_address
+                        .as_deref()
+                        .is_some_and(|eth| eth.eq_ignore_ascii_case(address))
+                })
+                .map(|m| m.user_id.clone())
+        })
+    }
+}
+
+pub mod prices {
+    use super::*;
+    use ic_govmind_types::{
+        constants::{EXCHANGE_RATE_CANISTER_ID, PRICE_DEFAULT_TTL_SECS},
+        price::PriceSource,
+    };
+
+    /// The configured price feed, or the IC exchange-rate canister when none is set.
+    pub fn get_price_feed_config() -> PriceFeedConfig {
+        state::with(|s| s.price_feed.clone()).unwrap_or_else(|| PriceFeedConfig {
+            default_source: PriceSource::ExchangeRateCanister {
+                canister_id: Principal::from_text(EXCHANGE_RATE_CANISTER_ID)
+                    .expect("invalid exchange rate canister id"),
+                base_symbol: None,
+            },
+            sources: HashMap::new(),
+            ttl_secs: PRICE_DEFAULT_TTL_SECS,
+        })
+    }
+
+    pub fn set_price_feed_config(config: PriceFeedConfig) {
+        state::with_mut(|s| {
+            s.price_feed = Some(config);
+            // Prices from the previous sources must not outlive the change.
+            s.token_prices.clear();
+        });
+    }
+
+    pub fn get_price(symbol: &str) -> Option<TokenPrice> {
+        state::with(|s| s.token_prices.get(&symbol.to_uppercase()).cloned())
+    }
+
+    pub fn set_price(price: TokenPrice) {
+        state::with_mut(|s| s.token_prices.insert(price.symbol.to_uppercase(), price));
+    }
+
+    /// Treasury holdings: the canister's tracked assets and the DAO's declared treasury.
+    pub fn get_treasury_assets() -> Vec<DaoAsset> {
+        state::with(|s| {
+            let mut assets: Vec<DaoAsset> = s.assets.values().cloned().collect();
+            for asset in s.org_info.iter().flat_map(|dao| dao.treasury.iter()) {
+                if !assets
+                    .iter()
+                    .any(|a| a.chain == asset.chain && a.symbol == asset.symbol)
+                {
+                    assets.push(asset.clone());
+                }
+            }
+            assets
+        })
+    }
+
+    /// Decimals of a token, from the supported tokens of its chain.
+    pub fn get_token_decimals(chain: &ChainType, symbol: &str) -> Option<u8> {
+        state::with(|s| {
+            s.get_chain_config_by_type(chain)?
+                .supported_tokens
+                .iter()
+                .find(|t| t.symbol.eq_ignore_ascii_case(symbol))
+                .map(|t| t.decimal)
+        })
+    }
+}
+
+pub mod rpc_health {
+    use super::*;
+    use crate::utils::current_time_secs;
+    use ic_govmind_types::constants::{
+        RPC_PROVIDER_EXCLUSION_SECS, RPC_PROVIDER_MAX_CONSECUTIVE_FAILURES,
+    };
+
+    pub fn get(chain_id: u64, provider: &str) -> Option<RpcProviderHealth> {
+        RPC_PROVIDER_HEALTH.with_borrow(|map| map.get(&(chain_id, provider.to_string())).cloned())
+    }
+
+    pub fn list(chain_id: Option<u64>) -> Vec<RpcProviderHealth> {
+        RPC_PROVIDER_HEALTH.with_borrow(|map| {
+            map.values()
+                .filter(|health| chain_id.is_none_or(|id| health.chain_id == id))
+                .cloned()
+                .collect()
+        })
+    }
+
+    pub fn is_excluded(health: &RpcProviderHealth) -> bool {
+        health
+            .excluded_until
+            .is_some_and(|until| current_time_secs() < until)
+    }
+
+    pub fn record_success(chain_id: u64, provider: &str, latency_ms: u64) {
+        update(chain_id, provider, |health| {
+            health.successes += 1;
+            health.consecutive_failures = 0;
+            health.excluded_until = None;
+            health.average_latency_ms = (health.average_latency_ms * (health.successes - 1)
+                + latency_ms)
+                / health.successes;
+            health.last_success_at = Some(current_time_secs());
+        });
+    }
+
+    /// Counts a failed call and excludes the provider after too many failures in a row. Once the
+    /// exclusion ends, the next call decides whether it is excluded again.
+    pub fn record_failure(chain_id: u64, provider: &str, error: String) {
+        update(chain_id, provider, |health| {
+            let now = current_time_secs();
+            health.failures += 1;
+            health.consecutive_failures += 1;
+            health.last_error = Some(error);
+            health.last_failure_at = Some(now);
+            if health.consecutive_failures >= RPC_PROVIDER_MAX_CONSECUTIVE_FAILURES {
+                health.excluded_until = Some(now + RPC_PROVIDER_EXCLUSION_SECS);
+            }
+        });
+    }
+
+    fn update<F: FnOnce(&mut RpcProviderHealth)>(chain_id: u64, provider: &str, f: F) {
+        RPC_PROVIDER_HEALTH.with_borrow_mut(|map| {
+            let health = map
+                .entry((chain_id, provider.to_string()))
+                .or_insert_with(|| RpcProviderHealth {
+                    chain_id,
+                    provider: provider.to_string(),
+                    successes: 0,
+                    failures: 0,
+                    consecutive_failures: 0,
+                    average_latency_ms: 0,
+                    last_error: None,
+                    last_success_at: None,
+                    last_failure_at: None,
+                    excluded_until: None,
+                });
+            f(health);
+        });
+    }
+}
+
+pub mod transactions {
+    use super::*;
+    use crate::utils::current_time_secs;
+    use ic_govmind_types::chain::EvmTxStatus;
+
+    /// Stores a new outgoing transaction and returns its id.
+    pub fn add_transaction(mut tx: EvmTransaction) -> u64 {
+        let id = state::with_mut(|s| s.get_next_id(NextIdType::Transaction));
+        tx.id = id;
+        EVM_TX_HASHES.with_borrow_mut(|index| index.insert(tx.tx_hash.to_lowercase(), id));
+        if tx.status.is_open() {
+            OPEN_EVM_TRANSACTIONS.with_borrow_mut(|index| index.insert(id, ()));
+        }
+        EVM_TRANSACTIONS.with(|map| map.borrow_mut().insert(id, EvmTransactionWrapper(tx)));
+        id
+    }
+
+    pub fn get_transaction(id: u64) -> Option<EvmTransaction> {
+        EVM_TRANSACTIONS.with(|map| map.borrow().get(&id).map(|w| w.into_inner()))
+    }
+
+    pub fn find_transaction_by_hash(tx_hash: &str) -> Option<EvmTransaction> {
+        EVM_TX_HASHES
+            .with_borrow(|index| index.get(&tx_hash.to_lowercase()))
+            .and_then(get_transaction)
+    }
+
+    /// Transactions created at or after `since` (seconds), newest first.
+    pub fn list_transactions_since(since: u64) -> Vec<EvmTransaction> {
+        EVM_TRANSACTIONS.with(|map| {
+            map.borrow()
+                .iter()
+                .rev()
+                .map(|(_, w)| w.into_inner())
+                .take_while(|tx| tx.created_at >= since)
+                .collect()
+        })
+    }
+
+    pub fn update_transaction(
+        id: u64,
+        f: impl FnOnce(&mut EvmTransaction),
+    ) -> Result<EvmTransaction, String> {
+        EVM_TRANSACTIONS.with(|map| {
+            let mut map = map.borrow_mut();
+            let mut wrapper = map
+                .get(&id)
+                .ok_or_else(|| format!("Transaction {} not found", id))?;
+            f(&mut wrapper.0);
+            wrapper.0.updated_at = current_time_secs();
+            map.insert(id, wrapper.clone());
+            OPEN_EVM_TRANSACTIONS.with_borrow_mut(|index| {
+                if wrapper.0.status.is_open() {
+                    index.insert(id, ());
+                } else {
+                    index.remove(&id);
+                }
+            });
+            Ok(wrapper.into_inner())
+        })
+    }
+
+    pub fn set_status(id: u64, status: EvmTxStatus) -> Result<EvmTransaction, String> {
+        update_transaction(id, |tx| tx.status = status)
+    }
+
+    pub fn list_transactions(start: u64, limit: usize) -> Vec<EvmTransaction> {
+        EVM_TRANSACTIONS.with(|map| {
+            map.borrow()
+                .range(start..)
+                .take(limit)
+                .map(|(_, w)| w.into_inner())
+                .collect()
+        })
+    }
+
+    /// Transactions that are broadcast but not yet mined, i.e. pending or replaced.
+    pub fn list_open_transactions() -> Vec<EvmTransaction> {
+        OPEN_EVM_TRANSACTIONS.with_borrow(|index| {
+            index
+                .iter()
+                .filter_map(|(id, _)| get_transaction(id))
+                .collect()
+        })
+    }
+
+    /// Open transactions from `from` that share `nonce`, i.e. an original and its replacements.
+    pub fn list_open_transactions_by_nonce(
+        chain_id: u64,
+        from: &str,
+        nonce: u64,
+    ) -> Vec<EvmTransaction> {
+        list_open_transactions()
+            .into_iter()
+            .filter(|tx| {
+                tx.chain_id == chain_id && tx.nonce == nonce && tx.from.eq_ignore_ascii_case(from)
+            })
+            .collect()
+    }
+
+    /// Nonces of transactions from `from` that are broadcast but not yet mined.
+    pub fn list_in_flight_nonces(chain_id: u64, from: &str) -> Vec<u64> {
+        let mut nonces: Vec<u64> = list_open_transactions()
+            .into_iter()
+            .filter(|tx| tx.chain_id == chain_id && tx.from.eq_ignore_ascii_case(from))
+            .map(|tx| tx.nonce)
+            .collect();
+        nonces.sort_unstable();
+        nonces.dedup();
+        nonces
+    }
+
+    /// Whether a transaction of `proposal_id` with an id of at least `first_id` was not rejected
+    /// when broadcast, i.e. may still reach the chain.
+    pub fn has_broadcast_since(proposal_id: u64, first_id: u64) -> bool {
+        EVM_TRANSACTIONS.with(|map| {
+            map.borrow().range(first_id..).any(|(_, w)| {
+                w.0.proposal_id == Some(proposal_id)
+                    && !matches!(w.0.status, EvmTxStatus::Failed(_))
+            })
+        })
+    }
+
+    pub fn list_transactions_by_proposal(proposal_id: u64) -> Vec<EvmTransaction> {
+        EVM_TRANSACTIONS.with(|map| {
+            map.borrow()
+                .iter()
+                .map(|(_, w)| w.into_inner())
+                .filter(|tx| tx.proposal_id == Some(proposal_id))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_govmind_types::{
+        chain::EvmTxStatus,
+        dao::{Proposal, ProposalStatus, Vote, VoteChoice},
+    };
+
+    fn transaction(from: &str, nonce: u64, status: EvmTxStatus) -> EvmTransaction {
+        EvmTransaction {
+            id: 0,
+            chain_type: ChainType::Ethereum,
+            chain_id: 1,
+            from: from.to_string(),
+            to: None,
+            nonce,
+            value: Nat::from(0u64),
+            raw_tx: String::new(),
+            tx_hash: format!("0x{:064x}", nonce),
+            proposal_id: None,
+            status,
+            created_at: 0,
+            updated_at: 0,
+            block_number: None,
+            gas_used: None,
+            effective_gas_price: None,
+            data: None,
+            gas_limit: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            replaced_by: None,
+        }
+    }
+
+    #[test]
+    fn only_pending_and_replaced_transactions_are_open() {
+        let pending = transactions::add_transaction(transaction("0xA", 1, EvmTxStatus::Pending));
+        transactions::add_transaction(transaction("0xa", 0, EvmTxStatus::Confirmed));
+        let replaced = transactions::add_transaction(transaction("0xa", 2, EvmTxStatus::Replaced));
+        transactions::add_transaction(transaction("0xa", 3, EvmTxStatus::Dropped));
+
+        let open: Vec<u64> = transactions::list_open_transactions()
+            .iter()
+            .map(|tx| tx.id)
+            .collect();
+        assert_eq!(open, vec![pending, replaced]);
+        assert_eq!(transactions::list_in_flight_nonces(1, "0xa"), vec![1, 2]);
+        assert_eq!(
+            transactions::list_in_flight_nonces(2, "0xa"),
+            Vec::<u64>::new()
+        );
+    }
+
+    #[test]
+    fn open_transactions_by_nonce_match_the_sender_case_insensitively() {
+        let original = transactions::add_transaction(transaction("0xAb", 5, EvmTxStatus::Replaced));
+        let replacement =
+            transactions::add_transaction(transaction("0xab", 5, EvmTxStatus::Pending));
+        transactions::add_transaction(transaction("0xab", 6, EvmTxStatus::Pending));
+        transactions::add_transaction(transaction("0xcd", 5, EvmTxStatus::Pending));
+
+        let siblings: Vec<u64> = transactions::list_open_transactions_by_nonce(1, "0xAB", 5)
+            .iter()
+            .map(|tx| tx.id)
+            .collect();
+        assert_eq!(siblings, vec![original, replacement]);
+    }
+
+    #[test]
+    fn only_transactions_not_rejected_count_as_broadcast() {
+        transactions::add_transaction(transaction("0xa", 0, EvmTxStatus::Pending));
+        let first_id = state::with(|s| s.peek_next_id(NextIdType::Transaction));
+        let mut rejected = transaction("0xa", 1, EvmTxStatus::Failed("nonce too low".to_string()));
+        rejected.proposal_id = Some(7);
+        transactions::add_transaction(rejected);
+        assert!(!transactions::has_broadcast_since(7, first_id));
+
+        let mut other = transaction("0xa", 2, EvmTxStatus::Pending);
+        other.proposal_id = Some(8);
+        transactions::add_transaction(other);
+        assert!(!transactions::has_broadcast_since(7, first_id));
+
+        let mut pending = transaction("0xa", 1, EvmTxStatus::Pending);
+        pending.proposal_id = Some(7);
+        transactions::add_transaction(pending);
+        assert!(transactions::has_broadcast_since(7, first_id));
+    }
+
+    fn insert_proposal(id: u64, status: ProposalStatus) {
+        let proposal = Proposal {
+            id,
+            title: String::new(),
+            content: String::new(),
+            proposer: String::new(),
+            committee_id: None,
+            created_at: 0,
+            expires_at: 100,
+            status,
+            votes: Vec::new(),
+            metadata: None,
+            tx_previews: None,
+            reward_event_round: None,
+            action_hash: None,
+        };
+        PROPOSALS_STORE.with_borrow_mut(|store| store.insert(id, ProposalWrapper(proposal)));
+    }
+
+    #[test]
+    fn an_action_is_bound_once_before_voting() {
+        insert_proposal(1, ProposalStatus::Active);
+        proposals::bind_action(1, "aa".to_string()).unwrap();
+        assert!(proposals::bind_action(1, "bb".to_string()).is_err());
+        assert_eq!(
+            proposals::get_proposal(1).unwrap().action_hash,
+            Some("aa".to_string())
+        );
+
+        insert_proposal(2, ProposalStatus::Active);
+        proposals::add_vote(
+            2,
+            Vote {
+                voter_id: "voter".to_string(),
+                vote_choice: VoteChoice::Yes,
+                weight: 1,
+                voted_at: 10,
+            },
+        )
+        .unwrap();
+        assert!(proposals::bind_action(2, "aa".to_string()).is_err());
+
+        insert_proposal(3, ProposalStatus::Passed);
+        assert!(proposals::bind_action(3, "aa".to_string()).is_err());
+    }
+
+    #[test]
+    fn reserved_nonces_never_fall_below_the_chain() {
+        assert_eq!(nonces::reserve_nonce(1, "0xa", 5), 5);
+        assert_eq!(nonces::reserve_nonce(1, "0xA", 5), 6);
+        // Transactions sent from elsewhere moved the on-chain count past the local one.
+        assert_eq!(nonces::reserve_nonce(1, "0xa", 10), 10);
+        // Addresses on other chains count separately.
+        assert_eq!(nonces::reserve_nonce(2, "0xa", 0), 0);
+    }
+
+    #[test]
+    fn released_nonces_fill_gaps_before_new_ones() {
+        for _ in 0..4 {
+            nonces::reserve_nonce(1, "0xa", 0);
+        }
+        nonces::release_nonce(1, "0xa", 1);
+        nonces::release_nonce(1, "0xa", 1);
+        assert_eq!(nonces::get_nonce_state(1, "0xa").released, vec![1]);
+
+        assert_eq!(nonces::reserve_nonce(1, "0xa", 0), 1);
+        assert_eq!(nonces::reserve_nonce(1, "0xa", 0), 4);
+    }
+
+    #[test]
+    fn released_nonces_at_the_tip_are_collapsed() {
+        for _ in 0..4 {
+            nonces::reserve_nonce(1, "0xa", 0);
+        }
+        nonces::release_nonce(1, "0xa", 2);
+        nonces::release_nonce(1, "0xa", 3);
+        let state = nonces::get_nonce_state(1, "0xa");
+        assert_eq!((state.next_nonce, state.released), (2, vec![]));
+
+        // Nonces that were never handed out cannot be released.
+        nonces::release_nonce(1, "0xa", 7);
+        assert_eq!(nonces::get_nonce_state(1, "0xa").next_nonce, 2);
+    }
+
+    #[test]
+    fn released_nonces_mined_elsewhere_are_discarded() {
+        for _ in 0..4 {
+            nonces::reserve_nonce(1, "0xa", 0);
+        }
+        nonces::release_nonce(1, "0xa", 0);
+        nonces::release_nonce(1, "0xa", 2);
+        assert_eq!(nonces::reserve_nonce(1, "0xa", 1), 2);
+
+        nonces::release_nonce(1, "0xa", 1);
+        nonces::claim_released_nonce(1, "0xa", 1);
+        assert_eq!(nonces::reserve_nonce(1, "0xa", 1), 4);
+    }
+
+    fn supply_transfer(
+        kind: SupplyChangeKind,
+        amount: u64,
+        created_at_time: u64,
+    ) -> SupplyTransfer {
+        SupplyTransfer {
+            kind,
+            amount: Nat::from(amount),
+            created_at_time,
+        }
+    }
+
+    #[test]
+    fn a_retried_supply_transfer_keeps_its_time() {
+        let first =
+            state::begin_supply_transfer(7, supply_transfer(SupplyChangeKind::Mint, 100, 1));
+        let retry =
+            state::begin_supply_transfer(7, supply_transfer(SupplyChangeKind::Mint, 100, 2));
+        assert_eq!((first.created_at_time, retry.created_at_time), (1, 1));
+
+        state::end_supply_transfer(7);
+        let next = state::begin_supply_transfer(7, supply_transfer(SupplyChangeKind::Mint, 100, 3));
+        assert_eq!(next.created_at_time, 3);
+    }
+
+    #[test]
+    fn mints_in_flight_count_against_the_cap_of_others() {
+        state::begin_supply_transfer(1, supply_transfer(SupplyChangeKind::Mint, 100, 1));
+        state::begin_supply_transfer(2, supply_transfer(SupplyChangeKind::Mint, 40, 1));
+        state::begin_supply_transfer(3, supply_transfer(SupplyChangeKind::Burn, 500, 1));
+
+        assert_eq!(state::minting_supply(1), Nat::from(40u64));
+        assert_eq!(state::minting_supply(9), Nat::from(140u64));
+    }
+
+    #[test]
+    fn supply_changes_apply_to_the_recorded_supply() {
+        let mut supply = Nat::from(1_000u64);
+        state::apply_supply_change(&mut supply, &SupplyChangeKind::Mint, &Nat::from(500u64))
+            .unwrap();
+        state::apply_supply_change(&mut supply, &SupplyChangeKind::Burn, &Nat::from(1_500u64))
+            .unwrap();
+        assert_eq!(supply, Nat::from(0u64));
+
+        assert!(
+            state::apply_supply_change(&mut supply, &SupplyChangeKind::Burn, &Nat::from(1u64))
+                .is_err()
+        );
+        assert_eq!(supply, Nat::from(0u64));
+    }
+
+    #[test]
+    fn votes_earn_rewards_from_when_rewards_were_last_enabled() {
+        let config = VotingRewardsConfig {
+            period_secs: 10,
+            amount_per_period: Nat::from(1u64),
+            start_at: 0,
+        };
+        rewards::set_config(Some(config.clone()), 100);
+        rewards::set_config(Some(config.clone()), 200);
+        assert_eq!(rewards::rewarded_votes_since(), 100);
+
+        rewards::set_config(None, 300);
+        rewards::set_config(Some(config), 400);
+        assert_eq!(rewards::rewarded_votes_since(), 400);
+    }
 }
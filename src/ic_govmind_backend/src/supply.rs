@@ -0,0 +1,264 @@
+use candid::Nat;
+use ic_canister_log::log;
+use ic_govmind_types::dao::{
+    MintTokensArg, SupplyChange, SupplyChangeKind, SupplyReconciliation, HOLDER_SUBACCOUNT,
+    MINTING_SUBACCOUNT,
+};
+use icrc_ledger_types::icrc1::{
+    account::Account,
+    transfer::{Memo, TransferError},
+};
+use serde_bytes::ByteBuf;
+use std::str::FromStr;
+
+use crate::{
+    distribution::{base_token_ledger, subaccount_account},
+    ic_log::INFO,
+    services::token_icrc1::TokenICRC1,
+    store,
+    types::SupplyTransfer,
+    utils::{current_time_secs, sha256},
+};
+
+/// Mints base tokens by transferring them out of the minting account. The ledger's supply after
+/// the mint, with the other mints in flight, may not exceed `BaseToken.supply_cap`. The mint is
+/// reserved before the supply is read, so concurrent mints cannot pass the cap together. Returns
+/// the ledger block index.
+pub async fn mint(arg: MintTokensArg) -> Result<Nat, String> {
+    if arg.amount == 0u64 {
+        return Err("Mint amount must be positive".to_string());
+    }
+    let to = match &arg.to {
+        Some(account) => Account::from_str(account)
+            .map_err(|e| format!("Invalid account {}: {:?}", account, e))?,
+        None => subaccount_account(HOLDER_SUBACCOUNT),
+    };
+
+    let token_service = base_token_ledger()?;
+    let supply_cap = store::state::get_dao_info()
+        .ok_or("DAO not initialized")?
+        .base_token
+        .supply_cap;
+    let pending = begin(SupplyChangeKind::Mint, &arg.amount, arg.proposal_id);
+    if let Some(cap) = supply_cap {
+        let checked = match token_service.icrc1_total_supply().await {
+            Ok(supply) => check_cap(
+                &cap,
+                supply + store::state::minting_supply(arg.proposal_id),
+                &arg.amount,
+            ),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = checked {
+            store::state::end_supply_transfer(arg.proposal_id);
+            return Err(e);
+        }
+    }
+
+    let block_index = transfer(
+        &token_service,
+        &pending,
+        MINTING_SUBACCOUNT,
+        to,
+        arg.proposal_id,
+    )
+    .await?;
+    record(
+        &token_service,
+        SupplyChangeKind::Mint,
+        to,
+        arg.amount,
+        block_index.clone(),
+        arg.proposal_id,
+    )
+    .await?;
+    Ok(block_index)
+}
+
+/// Burns treasury tokens by transferring them to the minting account. Returns the ledger block
+/// index.
+pub async fn burn(amount: Nat, proposal_id: u64) -> Result<Nat, String> {
+    if amount == 0u64 {
+        return Err("Burn amount must be positive".to_string());
+    }
+    let token_service = base_token_ledger()?;
+    let treasury = subaccount_account(HOLDER_SUBACCOUNT);
+    let recorded_supply = store::state::get_dao_info()
+        .ok_or("DAO not initialized")?
+        .base_token
+        .total_supply;
+    if amount > recorded_supply {
+        return Err(format!(
+            "Burning {} exceeds the recorded supply of {}",
+            amount, recorded_supply
+        ));
+    }
+
+    let pending = begin(SupplyChangeKind::Burn, &amount, proposal_id);
+    let block_index = transfer(
+        &token_service,
+        &pending,
+        HOLDER_SUBACCOUNT,
+        subaccount_account(MINTING_SUBACCOUNT),
+        proposal_id,
+    )
+    .await?;
+    record(
+        &token_service,
+        SupplyChangeKind::Burn,
+        treasury,
+        amount,
+        block_index.clone(),
+        proposal_id,
+    )
+    .await?;
+    Ok(block_index)
+}
+
+/// Compares the base token's recorded supply with the ledger's `icrc1_total_supply`.
+pub async fn reconcile() -> Result<SupplyReconciliation, String> {
+    let token_service = base_token_ledger()?;
+    let ledger_supply = token_service.icrc1_total_supply().await?;
+    let recorded_supply = store::state::get_dao_info()
+        .ok_or("DAO not initialized")?
+        .base_token
+        .total_supply;
+
+    let (mut minted, mut burned) = (Nat::from(0u64), Nat::from(0u64));
+    for change in store::state::list_supply_changes(0, usize::MAX) {
+        match change.kind {
+            SupplyChangeKind::Mint => minted += change.amount,
+            SupplyChangeKind::Burn => burned += change.amount,
+        }
+    }
+    let zero = Nat::from(0u64);
+    let (untracked_burn, untracked_mint) = match recorded_supply >= ledger_supply {
+        true => (recorded_supply.clone() - ledger_supply.clone(), zero),
+        false => (zero, ledger_supply.clone() - recorded_supply.clone()),
+    };
+
+    Ok(SupplyReconciliation {
+        recorded_supply,
+        ledger_supply,
+        minted,
+        burned,
+        untracked_burn,
+        untracked_mint,
+    })
+}
+
+fn check_cap(cap: &Nat, supply: Nat, amount: &Nat) -> Result<(), String> {
+    let supply = supply + amount.clone();
+    if supply > *cap {
+        return Err(format!(
+            "Minting {} would raise the supply to {}, above the cap of {}",
+            amount, supply, cap
+        ));
+    }
+    Ok(())
+}
+
+/// Starts the transfer of `proposal_id`, or resumes the one an earlier attempt left in flight
+/// with its transaction time.
+fn begin(kind: SupplyChangeKind, amount: &Nat, proposal_id: u64) -> SupplyTransfer {
+    store::state::begin_supply_transfer(
+        proposal_id,
+        SupplyTransfer {
+            kind,
+            amount: amount.clone(),
+            created_at_time: ic_cdk::api::time(),
+        },
+    )
+}
+
+/// Sends a mint or burn with a memo and a transaction time fixed per proposal, so that the ledger
+/// reports a retry of a transfer that went through as a duplicate.
+async fn transfer(
+    token_service: &TokenICRC1,
+    pending: &SupplyTransfer,
+    from_subaccount: [u8; 32],
+    to: Account,
+    proposal_id: u64,
+) -> Result<Nat, String> {
+    let memo = sha256(format!("{:?}:{}", pending.kind, proposal_id).as_bytes());
+    let result = token_service
+        .icrc1_transfer(
+            Some(from_subaccount),
+            to,
+            pending.amount.clone(),
+            None,
+            Some(Memo(ByteBuf::from(memo))),
+            Some(pending.created_at_time),
+        )
+        .await;
+
+    match result {
+        Ok(block_index)
+        | Err(TransferError::Duplicate {
+            duplicate_of: block_index,
+        }) => Ok(block_index),
+        Err(e) => {
+            // A failed call may still have reached the ledger; only a rejection by the ledger
+            // itself ends the transfer.
+            if !matches!(e, TransferError::GenericError { .. }) {
+                store::state::end_supply_transfer(proposal_id);
+            }
+            Err(format!(
+                "{:?} of {} failed: {:?}",
+                pending.kind, pending.amount, e
+            ))
+        }
+    }
+}
+
+async fn record(
+    token_service: &TokenICRC1,
+    kind: SupplyChangeKind,
+    account: Account,
+    amount: Nat,
+    block_index: Nat,
+    proposal_id: u64,
+) -> Result<(), String> {
+    let change = store::state::record_supply_change(SupplyChange {
+        id: 0,
+        kind,
+        account: account.to_string(),
+        amount,
+        block_index,
+        recorded_supply: Nat::from(0u64),
+        ledger_supply: None,
+        proposal_id,
+        created_at: current_time_secs(),
+    })?;
+    store::state::save();
+
+    let ledger_supply = token_service.icrc1_total_supply().await.ok();
+    if ledger_supply.as_ref() != Some(&change.recorded_supply) {
+        log!(
+            INFO,
+            "{:?} {}: recorded supply {} differs from the ledger's {:?}",
+            change.kind,
+            change.id,
+            change.recorded_supply,
+            ledger_supply
+        );
+    }
+    store::state::set_supply_change_ledger_supply(change.id, ledger_supply);
+    store::state::save();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_cap_bounds_the_supply_after_the_mint() {
+        let cap = Nat::from(1_000u64);
+        assert!(check_cap(&cap, Nat::from(900u64), &Nat::from(100u64)).is_ok());
+        assert_eq!(
+            check_cap(&cap, Nat::from(900u64), &Nat::from(101u64)),
+            Err("Minting 101 would raise the supply to 1_001, above the cap of 1_000".to_string())
+        );
+    }
+}
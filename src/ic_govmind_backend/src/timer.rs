@@ -1,30 +1,70 @@
 use crate::ic_log::INFO;
 use crate::store;
-use crate::{services::token_icrc1::TokenICRC1, store::TIMER_IDS, utils::icrc1_account_from_str};
-use candid::{Nat, Principal};
+use crate::{
+    base_token::complete_deployment,
+    bridge::check_bridge_operation,
+    deposits::scan_deposits,
+    distribution::distribute_tokens,
+    oracle::refresh_treasury_prices,
+    services::{evm_providers, evm_service::EvmService},
+    store::TIMER_IDS,
+    utils::{current_time_secs, nat_to_u128},
+    wallet::WalletBlockchainConfig,
+};
+use evm_rpc_types::{MultiRpcResult, TransactionReceipt};
 use ic_canister_log::log;
 use ic_cdk::futures::spawn;
 use ic_cdk_timers::set_timer_interval;
-use ic_govmind_types::dao::{
-    DistributionModel, DistributionRecord, DistributionType, HOLDER_SUBACCOUNT,
-};
-use std::{
-    cell::RefCell,
-    rc::Rc,
-    {collections::HashMap, time::Duration},
+use ic_govmind_types::{
+    chain::{BlockchainConfig, EvmTransaction, EvmTxStatus},
+    constants::{
+        BRIDGE_STATUS_POLL_INTERVAL_SECS, EVM_DEPOSIT_SCAN_INTERVAL_SECS, EVM_RPC_CANISTER_ID,
+        EVM_TX_PENDING_TIMEOUT_SECS, EVM_TX_RECEIPT_POLL_INTERVAL_SECS,
+        PRICE_REFRESH_INTERVAL_SECS,
+    },
+    nat_to_u64,
 };
+use std::{cell::Cell, thread::LocalKey, time::Duration};
 
-pub fn setup_token_distribution_timer(model: DistributionModel, token_canister_id: Principal) {
-    let model = Rc::new(RefCell::new(model));
-    let interval = Duration::from_secs(60);
+thread_local! {
+    static DISTRIBUTION_TIMER_STARTED: Cell<bool> = const { Cell::new(false) };
+    static DISTRIBUTION_IN_PROGRESS: Cell<bool> = const { Cell::new(false) };
+    static RECEIPT_POLL_IN_PROGRESS: Cell<bool> = const { Cell::new(false) };
+    static BRIDGE_POLL_IN_PROGRESS: Cell<bool> = const { Cell::new(false) };
+    static DEPOSIT_SCAN_IN_PROGRESS: Cell<bool> = const { Cell::new(false) };
+    static PRICE_REFRESH_IN_PROGRESS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Holds one of the in-progress flags of a periodic job. The flag is cleared when the guard is
+/// dropped, which also happens when the job traps after an await, so a trap cannot stop the job
+/// from ever running again.
+struct InProgressGuard(&'static LocalKey<Cell<bool>>);
+
+impl InProgressGuard {
+    /// Sets `flag`, or returns None if it is already set by a run that has not finished.
+    fn acquire(flag: &'static LocalKey<Cell<bool>>) -> Option<Self> {
+        match flag.with(|in_progress| in_progress.replace(true)) {
+            true => None,
+            false => Some(Self(flag)),
+        }
+    }
+}
+
+impl Drop for InProgressGuard {
+    fn drop(&mut self) {
+        self.0.with(|in_progress| in_progress.set(false));
+    }
+}
 
-    let model_clone = model.clone();
-    let timer_id = set_timer_interval(interval, move || {
-        let model_clone = model_clone.clone();
-        let token_canister_id = token_canister_id;
-        spawn(async move {
-            distribute_tokens_shared(model_clone, token_canister_id).await;
-        });
+/// Starts the distribution timer unless it already runs.
+pub fn setup_token_distribution_timer() {
+    if DISTRIBUTION_TIMER_STARTED.with(|started| started.replace(true)) {
+        return;
+    }
+
+    let interval = Duration::from_secs(60);
+    let timer_id = set_timer_interval(interval, || {
+        spawn(run_token_distribution());
     });
 
     TIMER_IDS.with(|timer_ids| timer_ids.borrow_mut().push(timer_id));
@@ -32,28 +72,32 @@ pub fn setup_token_distribution_timer(model: DistributionModel, token_canister_i
 
 pub fn restore_token_distribution_timer() {
     if let Some(dao) = store::state::get_dao_info() {
-        if let Some(model) = dao.base_token.distribution_model.clone() {
-            if let Some(token_canister_id) = dao.base_token.token_location.canister_id {
+        if dao.base_token.distribution_model.is_some()
+            || !store::vesting::list_grants().is_empty()
+            || !store::airdrops::list_airdrops().is_empty()
+            || !store::staking::list_positions(None).is_empty()
+            || store::rewards::get_config().is_some()
+        {
+            let location = &dao.base_token.token_location;
+            if location.canister_id.is_some() || location.contract_address.is_some() {
                 log!(
                     INFO,
-                    "success calling store: restoring token distribution timer for base token [{}], canister_id = {:?}",
+                    "success calling store: restoring token distribution timer for base token [{}], location = {:?}",
                     dao.base_token.symbol,
-                    token_canister_id
+                    location
                 );
 
-                ic_cdk_timers::set_timer(Duration::from_secs(2), move || {
-                    setup_token_distribution_timer(model, token_canister_id);
-                });
+                ic_cdk_timers::set_timer(Duration::from_secs(2), setup_token_distribution_timer);
             } else {
                 log!(
                     INFO,
-                    "success calling store: base token has no canister_id, skipping distribution timer setup."
+                    "success calling store: base token has no canister_id or contract_address, skipping distribution timer setup."
                 );
             }
         } else {
             log!(
                 INFO,
-                "success calling store: base token has no distribution_model, skipping timer restoration."
+                "success calling store: base token has no distribution_model, vesting grants, airdrops, stakes or voting rewards, skipping timer restoration."
             );
         }
     } else {
@@ -64,128 +108,275 @@ pub fn restore_token_distribution_timer() {
     }
 }
 
-async fn distribute_tokens_shared(
-    model: Rc<RefCell<DistributionModel>>,
-    token_canister_id: Principal,
-) {
-    let now = ic_cdk::api::time();
-    let token_service = TokenICRC1 {
-        principal: token_canister_id,
+/// Runs the distribution engine against the base token's current model, vesting grants,
+/// airdrops and ledger, all read from stable state on every tick.
+async fn run_token_distribution() {
+    let Some(_guard) = InProgressGuard::acquire(&DISTRIBUTION_IN_PROGRESS) else {
+        return;
     };
 
-    let mut m = model.borrow_mut();
-
-    // ---- Initial Distribution ----
-    if m.initial_executed_at.is_none() && !m.initial_distribution.is_empty() {
-        distribute_to_all(
-            &token_service,
-            &m.initial_distribution,
-            None,
-            DistributionType::Initial,
-            now,
-        )
-        .await;
-        m.initial_executed_at = Some(now);
-    }
-
-    // ---- Emission ----
-    if let Some(rate) = m.emission_rate {
-        let period_sec = m.emission_period.unwrap_or(60); // default 60s
-        let period_ns = period_sec * 1_000_000_000;
-
-        let should_emit = match m.last_emission_time {
-            Some(last) => now.saturating_sub(last) >= period_ns,
-            None => true,
-        };
-        if should_emit {
-            let amount = Nat::from(rate);
-            distribute_to_all(
-                &token_service,
-                &m.initial_distribution,
-                Some(amount),
-                DistributionType::Emission,
-                now,
-            )
-            .await;
-            m.last_emission_time = Some(now);
+    if let Some(dao) = store::state::get_dao_info() {
+        distribute_tokens(dao.base_token.token_location.canister_id).await;
+    }
+}
+
+pub fn setup_evm_tx_receipt_timer() {
+    let interval = Duration::from_secs(EVM_TX_RECEIPT_POLL_INTERVAL_SECS);
+    let timer_id = set_timer_interval(interval, || {
+        spawn(poll_pending_evm_transactions());
+    });
+
+    TIMER_IDS.with(|timer_ids| timer_ids.borrow_mut().push(timer_id));
+}
+
+async fn poll_pending_evm_transactions() {
+    // Skip this tick if the previous poll is still waiting on the EVM RPC canister.
+    let Some(_guard) = InProgressGuard::acquire(&RECEIPT_POLL_IN_PROGRESS) else {
+        return;
+    };
+
+    // Replaced transactions are polled too: the original may still be mined before its replacement.
+    for tx in store::transactions::list_open_transactions() {
+        let (id, tx_hash) = (tx.id, tx.tx_hash.clone());
+        if let Err(e) = check_evm_transaction_receipt(tx).await {
+            log!(
+                INFO,
+                "Failed to check receipt of transaction {} ({}): {}",
+                id,
+                tx_hash,
+                e
+            );
         }
     }
+}
+
+pub fn setup_bridge_status_timer() {
+    let interval = Duration::from_secs(BRIDGE_STATUS_POLL_INTERVAL_SECS);
+    let timer_id = set_timer_interval(interval, || {
+        spawn(poll_open_bridge_operations());
+    });
+
+    TIMER_IDS.with(|timer_ids| timer_ids.borrow_mut().push(timer_id));
+}
+
+async fn poll_open_bridge_operations() {
+    let Some(_guard) = InProgressGuard::acquire(&BRIDGE_POLL_IN_PROGRESS) else {
+        return;
+    };
+
+    for op in store::bridge::list_open_operations() {
+        let id = op.id;
+        if let Err(e) = check_bridge_operation(op).await {
+            log!(INFO, "Failed to check bridge operation {}: {}", id, e);
+        }
+    }
+}
+
+pub fn setup_evm_deposit_indexer_timer() {
+    let interval = Duration::from_secs(EVM_DEPOSIT_SCAN_INTERVAL_SECS);
+    let timer_id = set_timer_interval(interval, || {
+        spawn(index_evm_deposits());
+    });
+
+    TIMER_IDS.with(|timer_ids| timer_ids.borrow_mut().push(timer_id));
+}
+
+async fn index_evm_deposits() {
+    let Some(_guard) = InProgressGuard::acquire(&DEPOSIT_SCAN_IN_PROGRESS) else {
+        return;
+    };
+
+    match scan_deposits().await {
+        Ok(0) => {}
+        Ok(added) => log!(INFO, "Indexed {} new EVM deposits", added),
+        Err(e) => log!(INFO, "Failed to index EVM deposits: {}", e),
+    }
+}
+
+pub fn setup_price_refresh_timer() {
+    let interval = Duration::from_secs(PRICE_REFRESH_INTERVAL_SECS);
+    let timer_id = set_timer_interval(interval, || {
+        spawn(refresh_prices());
+    });
+
+    TIMER_IDS.with(|timer_ids| timer_ids.borrow_mut().push(timer_id));
+}
+
+async fn refresh_prices() {
+    let Some(_guard) = InProgressGuard::acquire(&PRICE_REFRESH_IN_PROGRESS) else {
+        return;
+    };
 
-    // ---- Unlock Schedule ----
-    if let Some(schedule) = &mut m.unlock_schedule {
-        for item in schedule.iter_mut() {
-            let scheduled_time_ns = item.timestamp * 1_000_000_000;
-            if !item.executed && scheduled_time_ns <= now {
-                let mut single_map = HashMap::new();
-                single_map.insert(item.addr.clone(), item.amount);
-
-                distribute_to_all(
-                    &token_service,
-                    &single_map,
-                    Some(Nat::from(item.amount)),
-                    DistributionType::Scheduled,
-                    now,
-                )
-                .await;
-
-                item.executed = true;
+    refresh_treasury_prices().await;
+}
+
+/// Fetches the receipt of a pending or replaced transaction and records its final status once it is mined.
+pub async fn check_evm_transaction_receipt(tx: EvmTransaction) -> Result<EvmTxStatus, String> {
+    let chain_config = store::state::get_chain_config_by_chain_id(tx.chain_id)
+        .ok_or_else(|| format!("Chain {} is not registered", tx.chain_id))?;
+
+    match fetch_receipt(&chain_config, &tx.tx_hash).await? {
+        Some(receipt) => {
+            let status = receipt_status(&receipt);
+            let mined = store::transactions::update_transaction(tx.id, |stored| {
+                stored.status = status.clone();
+                stored.block_number = Some(nat_to_u64(receipt.block_number.as_ref()));
+                stored.gas_used = nat_to_u128(receipt.gas_used.as_ref());
+                stored.effective_gas_price = nat_to_u128(receipt.effective_gas_price.as_ref());
+            })?;
+
+            // Only one transaction per nonce can be mined; the others sharing it are void.
+            for sibling in store::transactions::list_open_transactions_by_nonce(
+                tx.chain_id,
+                &tx.from,
+                tx.nonce,
+            ) {
+                if sibling.id != tx.id {
+                    let dropped =
+                        store::transactions::set_status(sibling.id, EvmTxStatus::Dropped)?;
+                    complete_base_token_deployment(&dropped, None).await;
+                }
+            }
+            complete_base_token_deployment(&mined, Some(&receipt)).await;
+            log!(
+                INFO,
+                "Transaction {} ({}) finalized with status {:?}",
+                tx.id,
+                tx.tx_hash,
+                status
+            );
+            Ok(status)
+        }
+        // A transaction without a receipt for long may still sit in the mempool, e.g. under a
+        // fee cap. It is only void once its nonce was used by a transaction that is not tracked.
+        None if current_time_secs().saturating_sub(tx.created_at) > EVM_TX_PENDING_TIMEOUT_SECS => {
+            if !nonce_taken_by_untracked(&chain_config, &tx).await? {
+                return Ok(tx.status);
             }
+            let dropped = store::transactions::set_status(tx.id, EvmTxStatus::Dropped)?;
+            complete_base_token_deployment(&dropped, None).await;
+            Ok(EvmTxStatus::Dropped)
         }
+        None => Ok(tx.status),
     }
 }
 
-async fn distribute_to_all(
-    token_service: &TokenICRC1,
-    distribution_map: &HashMap<String, u128>,
-    amount: Option<Nat>,
-    dist_type: DistributionType,
-    now: u64,
-) {
-    for (addr, val) in distribution_map.iter() {
-        let send_amount = match &amount {
-            Some(a) => a.clone(),
-            None => Nat::from(*val),
-        };
-
-        let account = icrc1_account_from_str(addr);
-        let result = token_service
-            .icrc1_transfer(
-                Some(HOLDER_SUBACCOUNT),
-                account,
-                send_amount.clone(),
-                None,
-                None,
-                None,
-            )
-            .await;
-
-        match &result {
-            Ok(res) => ic_cdk::println!(
-                "[{:?}] Distributed {} tokens to {} at time {}: {:?}",
-                dist_type,
-                send_amount,
-                addr,
-                now,
-                res
-            ),
-            Err(e) => ic_cdk::println!(
-                "[{:?}] Failed to distribute {} tokens to {}: {:?}",
-                dist_type,
-                send_amount,
-                addr,
-                e
-            ),
+/// Whether `tx` is void: it has no receipt, and its nonce was used on chain by a transaction that
+/// is not tracked.
+pub async fn is_dropped(tx: &EvmTransaction) -> Result<bool, String> {
+    let chain_config = store::state::get_chain_config_by_chain_id(tx.chain_id)
+        .ok_or_else(|| format!("Chain {} is not registered", tx.chain_id))?;
+    if fetch_receipt(&chain_config, &tx.tx_hash).await?.is_some() {
+        return Ok(false);
+    }
+    nonce_taken_by_untracked(&chain_config, tx).await
+}
+
+/// Whether the nonce of `tx` was used on chain by a transaction that is not tracked, i.e. no open
+/// transaction sharing it but `tx` has a receipt.
+async fn nonce_taken_by_untracked(
+    chain_config: &BlockchainConfig,
+    tx: &EvmTransaction,
+) -> Result<bool, String> {
+    let onchain_nonce = WalletBlockchainConfig(chain_config.clone())
+        .fetch_onchain_nonce(&tx.from)
+        .await? as u64;
+    if onchain_nonce <= tx.nonce {
+        return Ok(false);
+    }
+    for sibling in
+        store::transactions::list_open_transactions_by_nonce(tx.chain_id, &tx.from, tx.nonce)
+    {
+        // A mined sibling is recorded by its own poll, which voids this transaction.
+        if sibling.id != tx.id
+            && fetch_receipt(chain_config, &sibling.tx_hash)
+                .await?
+                .is_some()
+        {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+async fn fetch_receipt(
+    chain_config: &BlockchainConfig,
+    tx_hash: &str,
+) -> Result<Option<TransactionReceipt>, String> {
+    let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
+    let tx_hash = tx_hash.to_string();
+    let result = evm_providers::call_multi(chain_config, |rpc_services, rpc_config| async move {
+        evm_service
+            .eth_get_transaction_receipt(&rpc_services, rpc_config, tx_hash)
+            .await
+    })
+    .await?;
+    match result {
+        MultiRpcResult::Consistent(Ok(receipt)) => Ok(receipt),
+        MultiRpcResult::Consistent(Err(rpc_err)) => {
+            Err(format!("RPC error fetching receipt: {:?}", rpc_err))
         }
+        MultiRpcResult::Inconsistent(_) => {
+            Err("Receipt is inconsistent across services".to_string())
+        }
+    }
+}
+
+async fn complete_base_token_deployment(tx: &EvmTransaction, receipt: Option<&TransactionReceipt>) {
+    if let Err(e) = complete_deployment(tx, receipt).await {
+        log!(INFO, "Failed to complete base token deployment: {}", e);
+    }
+}
 
-        store::distribution::add_distribution_record(DistributionRecord {
-            timestamp: now,
-            distribution_type: dist_type.clone(),
-            recipient: addr.clone(),
-            amount: send_amount.clone(),
-            tx_result: match &result {
-                Ok(res) => format!("Success: {:?}", res),
-                Err(e) => format!("Error: {:?}", e),
-            },
-        });
+fn receipt_status(receipt: &TransactionReceipt) -> EvmTxStatus {
+    match &receipt.status {
+        // Receipts without a status predate Byzantium, where inclusion implies success.
+        None => EvmTxStatus::Confirmed,
+        Some(status) if nat_to_u64(status.as_ref()) == 1 => EvmTxStatus::Confirmed,
+        Some(_) => EvmTxStatus::Failed("Transaction reverted".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guard_clears_its_flag_when_dropped() {
+        let guard = InProgressGuard::acquire(&RECEIPT_POLL_IN_PROGRESS);
+        assert!(guard.is_some());
+        assert!(InProgressGuard::acquire(&RECEIPT_POLL_IN_PROGRESS).is_none());
+
+        drop(guard);
+        assert!(InProgressGuard::acquire(&RECEIPT_POLL_IN_PROGRESS).is_some());
+    }
+
+    fn receipt(status: Option<u64>) -> TransactionReceipt {
+        TransactionReceipt {
+            block_hash: [0u8; 32].into(),
+            block_number: 1u64.into(),
+            effective_gas_price: 1u64.into(),
+            gas_used: 21_000u64.into(),
+            status: status.map(Into::into),
+            transaction_hash: [0u8; 32].into(),
+            contract_address: None,
+            from: [0u8; 20].into(),
+            logs: vec![],
+            logs_bloom: [0u8; 256].into(),
+            to: None,
+            transaction_index: 0u64.into(),
+            tx_type: 2u8.into(),
+        }
+    }
+
+    #[test]
+    fn receipt_status_follows_the_status_field() {
+        assert_eq!(receipt_status(&receipt(Some(1))), EvmTxStatus::Confirmed);
+        assert_eq!(
+            receipt_status(&receipt(Some(0))),
+            EvmTxStatus::Failed("Transaction reverted".to_string())
+        );
+        // Pre-Byzantium receipts carry no status.
+        assert_eq!(receipt_status(&receipt(None)), EvmTxStatus::Confirmed);
     }
 }
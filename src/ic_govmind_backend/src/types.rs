@@ -1,9 +1,13 @@
-use candid::{CandidType, Deserialize, Principal};
-use evm_rpc_types::{EthMainnetService, EthSepoliaService, RpcApi, RpcService};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use ic_cdk::management_canister::{
     EcdsaCurve, EcdsaKeyId, SchnorrAlgorithm::Bip340secp256k1, SchnorrKeyId,
 };
-use ic_govmind_types::dao::{ChainType, HOLDER_SUBACCOUNT};
+use ic_govmind_types::{
+    dao::{
+        AirdropArg, ChainType, MintTokensArg, SupplyChangeKind, VestingGrantArg, HOLDER_SUBACCOUNT,
+    },
+    icrc::LedgerUpgradeArg,
+};
 use ic_ledger_types::{AccountIdentifier, Subaccount};
 use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
@@ -12,6 +16,7 @@ use tiny_keccak::{Hasher, Sha3};
 
 use crate::{
     chain::{bitcoin::account_to_p2pkh_address, ethereum::account_to_eth_address},
+    utils::sha256,
     BITCOIN_NETWORK,
 };
 
@@ -40,22 +45,10 @@ pub enum KeyEnvironment {
 }
 
 impl KeyEnvironment {
-    pub fn get_rpc_service(&self) -> RpcService {
-        match self {
-            KeyEnvironment::Staging => RpcService::EthSepolia(EthSepoliaService::PublicNode),
-            KeyEnvironment::Local => RpcService::Custom(RpcApi {
-                url: "http://127.0.0.1:8545".to_string(),
-                headers: None,
-            }),
-            // KeyEnvironment::Production => RpcService::EthMainnet(EthMainnetService::PublicNode),
-            KeyEnvironment::Production => RpcService::EthSepolia(EthSepoliaService::PublicNode),
-        }
-    }
-
+    /// The Ethereum network served by the ckETH minter of this environment.
     pub fn get_eth_chain_type(&self) -> ChainType {
         match self {
-            // KeyEnvironment::Production => ChainType::Ethereum,
-            KeyEnvironment::Production => ChainType::EthSepolia,
+            KeyEnvironment::Production => ChainType::Ethereum,
             KeyEnvironment::Staging => ChainType::EthSepolia,
             KeyEnvironment::Local => ChainType::EthLocal,
         }
@@ -169,6 +162,12 @@ pub enum NextIdType {
     Order,
     Payment,
     Committee,
+    Transaction,
+    Bridge,
+    Deposit,
+    VestingGrant,
+    Airdrop,
+    StakePosition,
 }
 
 impl ToString for NextIdType {
@@ -179,6 +178,12 @@ impl ToString for NextIdType {
             NextIdType::Order => "order",
             NextIdType::Payment => "payment",
             NextIdType::Committee => "committee",
+            NextIdType::Transaction => "transaction",
+            NextIdType::Bridge => "bridge",
+            NextIdType::Deposit => "deposit",
+            NextIdType::VestingGrant => "vesting_grant",
+            NextIdType::Airdrop => "airdrop",
+            NextIdType::StakePosition => "stake_position",
         }
         .to_string()
     }
@@ -193,6 +198,12 @@ impl FromStr for NextIdType {
             "proposal" => Ok(NextIdType::Proposal),
             "order" => Ok(NextIdType::Order),
             "payment" => Ok(NextIdType::Payment),
+            "transaction" => Ok(NextIdType::Transaction),
+            "bridge" => Ok(NextIdType::Bridge),
+            "deposit" => Ok(NextIdType::Deposit),
+            "vesting_grant" => Ok(NextIdType::VestingGrant),
+            "airdrop" => Ok(NextIdType::Airdrop),
+            "stake_position" => Ok(NextIdType::StakePosition),
             _ => Err(()),
         }
     }
@@ -220,5 +231,141 @@ pub struct TokenTransferArg {
     pub wallet_subaccount: Option<Subaccount>,
     pub recipient_address: String,
     pub recipient_subaccount: Option<Subaccount>,
-    pub amount: u64,
+    pub amount: Nat,
+    pub proposal_id: Option<u64>,
+}
+
+/// A mint or burn of the base token sent to the ledger but not recorded yet.
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct SupplyTransfer {
+    pub kind: SupplyChangeKind,
+    pub amount: Nat,
+    pub created_at_time: u64, // nanoseconds, reused so that the ledger deduplicates a retry
+}
+
+/// Nonce allocation for one EVM address on one chain.
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EvmNonceState {
+    pub next_nonce: u64,
+    pub released: Vec<u64>, // reserved but never broadcast, reused before `next_nonce`
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub struct EvmNonceReport {
+    pub chain_type: ChainType,
+    pub address: String,
+    pub onchain_nonce: u64,
+    pub next_nonce: u64,
+    pub in_flight: Vec<u64>,
+    pub gaps: Vec<u64>,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub struct Erc20ApproveArg {
+    pub chain_type: ChainType,
+    pub token_name: String,
+    pub wallet_address: String,
+    pub spender: String,
+    pub amount: Nat,
+    pub proposal_id: u64, // passed proposal authorizing the approval
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub struct Erc20RevokeArg {
+    pub chain_type: ChainType,
+    pub token_name: String,
+    pub wallet_address: String,
+    pub spender: String,
+    pub proposal_id: Option<u64>,
+}
+
+/// An outstanding ERC-20 allowance granted from a treasury address.
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct Erc20Approval {
+    pub chain_type: ChainType,
+    pub chain_id: u64,
+    pub token_name: String,
+    pub token_address: String,
+    pub owner: String,
+    pub spender: String,
+    pub approved_amount: Nat,
+    pub unlimited: bool, // approved amount is 2^256 - 1
+    pub proposal_id: u64,
+    pub tx_hash: String,
+    pub approved_at: u64,       // seconds
+    pub allowance: Option<Nat>, // last allowance read from the chain
+    pub checked_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub struct BridgeDepositArg {
+    pub chain_type: ChainType,
+    pub token_name: String, // ETH or ERC-20 token of the EVM chain config
+    pub wallet_address: String,
+    pub amount: Nat,
+    pub proposal_id: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub struct BridgeWithdrawArg {
+    pub token_name: String, // ckETH or ckERC20 token of the Internet Computer chain config
+    pub recipient: String,
+    pub amount: Nat,
+    pub max_cketh_fee: Option<Nat>, // ckETH the minter may burn for a ckERC20 withdrawal
+    pub proposal_id: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub struct EvmContractCallArg {
+    pub chain_type: ChainType,
+    pub wallet_address: String,
+    pub contract_address: String,
+    pub abi_json: String,      // ABI array or single function fragment
+    pub function_name: String, // name, or full signature of an overloaded function
+    pub args: Vec<String>,     // one value per input, parsed against its ABI type
+    pub value: Option<Nat>,    // wei sent along with a payable function
+    pub gas_limit: Option<u64>,
+    pub proposal_id: u64,
+}
+
+/// A treasury action a proposal authorizes. It is bound to the proposal before voting starts, and
+/// only the exact same action, arguments included, can be executed once the proposal passes.
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub enum ProposalAction {
+    CreateVestingGrant(VestingGrantArg),
+    RevokeVestingGrant { grant_id: u64 },
+    CreateAirdrop(AirdropArg),
+    MintBaseToken(MintTokensArg),
+    BurnBaseToken { amount: Nat },
+    UpgradeBaseTokenLedger(LedgerUpgradeArg),
+    Erc20Approve(Erc20ApproveArg),
+    Erc20RevokeApproval(Erc20RevokeArg),
+    EvmContractCall(EvmContractCallArg),
+    BridgeToIc(BridgeDepositArg),
+    BridgeToEvm(BridgeWithdrawArg),
+}
+
+impl ProposalAction {
+    /// Hex sha256 of the action's candid encoding.
+    pub fn hash(&self) -> String {
+        hex::encode(sha256(&candid::encode_one(self).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proposal_action_hash_covers_the_arguments() {
+        let burn = |amount: u64| ProposalAction::BurnBaseToken {
+            amount: Nat::from(amount),
+        };
+        assert_eq!(burn(100).hash(), burn(100).hash());
+        assert_ne!(burn(100).hash(), burn(101).hash());
+        assert_ne!(
+            ProposalAction::RevokeVestingGrant { grant_id: 100 }.hash(),
+            burn(100).hash()
+        );
+    }
 }
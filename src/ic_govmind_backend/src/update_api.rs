@@ -1,23 +1,41 @@
-use candid::Principal;
+use candid::{Nat, Principal};
 
+use evm_rpc_types::{ConsensusStrategy, RpcServices};
 use ic_cdk::update;
 use ic_govmind_types::{
-    chain::{TokenConfig, TokenStandard},
+    chain::{
+        BlockchainConfig, ChainConfigAction, EvmTxPreview, FeeConfig, RpcConfig, TokenConfig,
+        TokenStandard,
+    },
+    cketh::CkEthConfig,
+    constants::ICRC1_LEDGER_DEFAULT_FEE,
     dao::{
-        BaseToken, ChainType, CommitteeArg, CreateBaseTokenArg, Dao, ProposalStatus, TokenLocation,
+        AirdropArg, BaseToken, ChainType, CommitteeArg, CreateBaseTokenArg, CreateEvmBaseTokenArg,
+        Dao, MintTokensArg, ProposalStatus, StakeReconciliation, StakingConfig,
+        SupplyReconciliation, TokenLocation, VestingGrantArg, Vote, VoteChoice, VoteWeightType,
+        VotingRewardsConfig,
     },
-    icrc::CreateCanisterArg,
+    icrc::{CreateCanisterArg, LedgerUpgradeArg},
+    nat_to_u64,
+    price::{PriceFeedConfig, PriceSource, TokenPrice},
 };
 use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use std::future::Future;
 
 use crate::{
-    guards::not_anonymous,
+    airdrop, base_token, bridge, distribution, emission,
+    guards::{admin_or_controller_guard, not_anonymous},
     init::init_eth_local_chain,
-    store::{self, state},
+    ledger, oracle, staking, store, supply,
     timer::setup_token_distribution_timer,
-    types::{BalanceResult, QueryBalanceArg, TokenTransferArg},
-    utils::create_icrc1_canister,
-    wallet::WalletBlockchainConfig,
+    types::{
+        BalanceResult, BridgeDepositArg, BridgeWithdrawArg, Erc20Approval, Erc20ApproveArg,
+        Erc20RevokeArg, EvmContractCallArg, EvmNonceReport, NextIdType, ProposalAction,
+        QueryBalanceArg, TokenTransferArg,
+    },
+    utils::{create_icrc1_canister, current_time_secs, nat_to_be_bytes32},
+    vesting,
+    wallet::{TxReplacement, WalletBlockchainConfig},
     ICRC1_WASM,
 };
 
@@ -52,6 +70,18 @@ pub async fn create_dao_base_token(
     arg: CreateBaseTokenArg,
     logo: MetadataValue,
 ) -> Result<Principal, String> {
+    if let Some(cap) = &arg.supply_cap {
+        if arg.total_supply > *cap {
+            return Err(format!(
+                "Total supply {} exceeds the supply cap {}",
+                arg.total_supply, cap
+            ));
+        }
+    }
+    if let Some(model) = &arg.distribution_model {
+        distribution::validate_recipients(model, &ChainType::InternetComputer)?;
+        emission::validate_schedule(model)?;
+    }
     let icrc_arg = arg.to_create_canister_arg(logo, None);
     let token_canister_id = create_icrc1_canister(icrc_arg, ICRC1_WASM.to_vec()).await?;
 
@@ -66,6 +96,7 @@ pub async fn create_dao_base_token(
             canister_id: Some(token_canister_id),
             contract_address: None,
         },
+        supply_cap: arg.supply_cap,
     };
 
     let old_dao = store::state::get_dao_info().ok_or("DAO not initialized")?;
@@ -83,7 +114,10 @@ pub async fn create_dao_base_token(
         decimal: arg.decimals,
         chain_name: "Internet Computer".to_string(),
         standard: TokenStandard::ICRC1,
-        fee: 10_000,
+        fee: arg
+            .transfer_fee
+            .as_ref()
+            .map_or(ICRC1_LEDGER_DEFAULT_FEE as u64, nat_to_u64),
         ..Default::default()
     };
 
@@ -91,15 +125,20 @@ pub async fn create_dao_base_token(
         icp_chain.add_token_config(token_cfg);
     })?;
 
-    if let Some(ref model) = arg.distribution_model {
-        if model.emission_rate.is_some() || model.unlock_schedule.is_some() {
-            setup_token_distribution_timer(model.clone(), token_canister_id);
-        }
+    if arg.distribution_model.is_some() {
+        setup_token_distribution_timer();
     }
 
     Ok(token_canister_id)
 }
 
+/// Deploys the base token as an ERC-20 on an EVM chain. It is registered as the DAO's base token
+/// once the deployment is mined. Returns the deployment transaction hash.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn create_dao_evm_base_token(arg: CreateEvmBaseTokenArg) -> Result<String, String> {
+    base_token::deploy_evm_base_token(arg).await
+}
+
 #[update]
 pub async fn create_proposal(title: String, content: String, committee_id: Option<u16>) -> Result<u64, String> {
     // Ensure caller is not anonymous
@@ -143,6 +182,40 @@ pub async fn create_proposal(title: String, content: String, committee_id: Optio
     )
 }
 
+/// Casts the caller's vote, weighted according to the DAO's `vote_weight_type`. Returns the
+/// weight of the vote.
+#[update]
+pub async fn vote_on_proposal(proposal_id: u64, vote_choice: VoteChoice) -> Result<u64, String> {
+    not_anonymous()?;
+    let caller = ic_cdk::api::msg_caller();
+    let dao = store::state::get_dao_info().ok_or("DAO not initialized")?;
+    let now = current_time_secs();
+    let proposal = store::proposals::get_proposal(proposal_id)
+        .ok_or_else(|| format!("Proposal {} not found", proposal_id))?;
+
+    let member = store::state::find_member_by_principal(caller);
+    let weight = match dao.governance.vote_weight_type {
+        VoteWeightType::OnePersonOneVote => member.map(|_| 1),
+        VoteWeightType::ReputationWeighted => member.map(|member| member.reputation),
+        VoteWeightType::TokenWeighted => {
+            Some(staking::vote_weight(caller, now, proposal.expires_at))
+        }
+    }
+    .filter(|weight| *weight > 0)
+    .ok_or("The caller has no voting power")?;
+
+    store::proposals::add_vote(
+        proposal_id,
+        Vote {
+            voter_id: caller.to_text(),
+            vote_choice,
+            weight,
+            voted_at: now,
+        },
+    )?;
+    Ok(weight)
+}
+
 #[update]
 pub async fn update_proposal_status(
     proposal_id: u64,
@@ -174,6 +247,53 @@ pub async fn wallet_query_balance(arg: QueryBalanceArg) -> Result<BalanceResult,
     })
 }
 
+#[update(guard = "admin_or_controller_guard")]
+pub async fn get_evm_nonce_report(
+    chain_type: ChainType,
+    wallet_address: String,
+) -> Result<EvmNonceReport, String> {
+    let chain_config = store::state::get_chain_config(&chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", chain_type))?;
+
+    WalletBlockchainConfig(chain_config)
+        .nonce_report(&wallet_address)
+        .await
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn fill_evm_nonce_gaps(
+    chain_type: ChainType,
+    wallet_address: String,
+) -> Result<Vec<String>, String> {
+    let chain_config = store::state::get_chain_config(&chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", chain_type))?;
+
+    WalletBlockchainConfig(chain_config)
+        .fill_nonce_gaps(&wallet_address)
+        .await
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn speed_up_evm_transaction(tx_id: u64) -> Result<String, String> {
+    replace_evm_transaction(tx_id, TxReplacement::SpeedUp).await
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn cancel_evm_transaction(tx_id: u64) -> Result<String, String> {
+    replace_evm_transaction(tx_id, TxReplacement::Cancel).await
+}
+
+async fn replace_evm_transaction(tx_id: u64, kind: TxReplacement) -> Result<String, String> {
+    let tx = store::transactions::get_transaction(tx_id)
+        .ok_or_else(|| format!("Transaction {} not found", tx_id))?;
+    let chain_config = store::state::get_chain_config(&tx.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", tx.chain_type))?;
+
+    WalletBlockchainConfig(chain_config)
+        .replace_transaction(tx_id, kind)
+        .await
+}
+
 #[update]
 pub async fn wallet_token_transfer(arg: TokenTransferArg) -> Result<String, String> {
     let chain_config = store::state::get_chain_config(&arg.chain_type)
@@ -190,6 +310,7 @@ pub async fn wallet_token_transfer(arg: TokenTransferArg) -> Result<String, Stri
             &arg.recipient_address,
             &arg.recipient_subaccount,
             arg.amount,
+            arg.proposal_id,
         )
         .await;
 
@@ -200,6 +321,448 @@ pub async fn wallet_token_transfer(arg: TokenTransferArg) -> Result<String, Stri
     }
 }
 
+/// Sets aside treasury tokens that vest to a beneficiary. Returns the grant id.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn create_vesting_grant(arg: VestingGrantArg) -> Result<u64, String> {
+    let grant_id = execute_proposal(
+        arg.proposal_id,
+        ProposalAction::CreateVestingGrant(arg.clone()),
+        vesting::create_grant(arg),
+    )
+    .await?;
+    setup_token_distribution_timer();
+    Ok(grant_id)
+}
+
+/// Revokes a vesting grant and returns its unvested tokens to the treasury.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn revoke_vesting_grant(grant_id: u64, proposal_id: u64) -> Result<Nat, String> {
+    execute_proposal(
+        proposal_id,
+        ProposalAction::RevokeVestingGrant { grant_id },
+        async { vesting::revoke_grant(grant_id) },
+    )
+    .await
+}
+
+/// Releases the caller's vested tokens of a grant.
+#[update]
+pub async fn claim_vested_tokens(grant_id: u64) -> Result<Nat, String> {
+    not_anonymous()?;
+    vesting::claim(grant_id).await
+}
+
+/// Sets aside treasury tokens that recipients claim with a Merkle proof. Returns the airdrop id.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn create_airdrop(arg: AirdropArg) -> Result<u64, String> {
+    let airdrop_id = execute_proposal(
+        arg.proposal_id,
+        ProposalAction::CreateAirdrop(arg.clone()),
+        airdrop::create_airdrop(arg),
+    )
+    .await?;
+    setup_token_distribution_timer();
+    Ok(airdrop_id)
+}
+
+/// Pays the caller's airdrop allocation, given the Merkle proof of its (account, amount) leaf.
+#[update]
+pub async fn claim_airdrop(
+    airdrop_id: u64,
+    amount: Nat,
+    proof: Vec<String>,
+) -> Result<Nat, String> {
+    not_anonymous()?;
+    airdrop::claim(airdrop_id, amount, proof).await
+}
+
+/// Mints base tokens from the minting account. Returns the ledger block index.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn mint_base_token(arg: MintTokensArg) -> Result<Nat, String> {
+    execute_proposal(
+        arg.proposal_id,
+        ProposalAction::MintBaseToken(arg.clone()),
+        supply::mint(arg),
+    )
+    .await
+}
+
+/// Burns treasury base tokens. Returns the ledger block index.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn burn_base_token(amount: Nat, proposal_id: u64) -> Result<Nat, String> {
+    execute_proposal(
+        proposal_id,
+        ProposalAction::BurnBaseToken {
+            amount: amount.clone(),
+        },
+        supply::burn(amount, proposal_id),
+    )
+    .await
+}
+
+/// Upgrades the base token's ledger, e.g. to change its fee, logo or fee collector. Returns the
+/// id of the recorded upgrade.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn upgrade_base_token_ledger(arg: LedgerUpgradeArg) -> Result<u64, String> {
+    execute_proposal(
+        arg.proposal_id,
+        ProposalAction::UpgradeBaseTokenLedger(arg.clone()),
+        ledger::upgrade_ledger(arg),
+    )
+    .await
+}
+
+#[update]
+pub async fn reconcile_token_supply() -> Result<SupplyReconciliation, String> {
+    supply::reconcile().await
+}
+
+/// Locks tokens the caller sent to their staking account. Returns the stake position id.
+#[update]
+pub async fn stake_tokens(amount: Nat, dissolve_delay_secs: u64) -> Result<u64, String> {
+    not_anonymous()?;
+    staking::stake(amount, dissolve_delay_secs).await
+}
+
+/// Starts dissolving a stake position. Returns when its tokens can be unstaked.
+#[update]
+pub async fn start_dissolving_stake(position_id: u64) -> Result<u64, String> {
+    not_anonymous()?;
+    staking::start_dissolving(position_id)
+}
+
+/// Sends the tokens of a dissolved stake position back to the caller.
+#[update]
+pub async fn unstake_tokens(position_id: u64) -> Result<Nat, String> {
+    not_anonymous()?;
+    let result = staking::unstake(position_id).await;
+    // Retries an unstake transfer that failed.
+    setup_token_distribution_timer();
+    result
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn set_staking_config(config: StakingConfig) -> Result<(), String> {
+    if config.min_dissolve_delay_secs > config.max_dissolve_delay_secs {
+        return Err("Minimum dissolve delay exceeds the maximum".to_string());
+    }
+    store::staking::set_config(config);
+    store::state::save();
+    Ok(())
+}
+
+/// Sets or, given None, stops the voting participation rewards. Periods already settled are kept.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn set_voting_rewards_config(config: Option<VotingRewardsConfig>) -> Result<(), String> {
+    if let Some(config) = &config {
+        if config.period_secs == 0 {
+            return Err("Reward period must be positive".to_string());
+        }
+    }
+    let enabled = config.is_some();
+    store::rewards::set_config(config, current_time_secs());
+    store::state::save();
+    if enabled {
+        setup_token_distribution_timer();
+    }
+    Ok(())
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn reconcile_stakes() -> Result<Vec<StakeReconciliation>, String> {
+    staking::reconcile().await
+}
+
+/// Binds the treasury action a proposal authorizes. Returns the action hash stored on it.
+#[update]
+pub fn bind_proposal_action(proposal_id: u64, action: ProposalAction) -> Result<String, String> {
+    check_preview_caller(proposal_id)?;
+    let action_hash = action.hash();
+    store::proposals::bind_action(proposal_id, action_hash.clone())?;
+    Ok(action_hash)
+}
+
+/// Runs `action`, which must be the one bound to `proposal_id`. The proposal is marked executed
+/// before the action starts, so concurrent calls cannot reuse it. It is restored to passed if the
+/// action fails, unless a transaction of the action was broadcast and may still be mined.
+async fn execute_proposal<T>(
+    proposal_id: u64,
+    action: ProposalAction,
+    execution: impl Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    let proposal = store::proposals::get_executable_proposal(proposal_id)?;
+    if proposal.action_hash != Some(action.hash()) {
+        return Err(format!(
+            "Proposal {} does not authorize this action",
+            proposal_id
+        ));
+    }
+    store::proposals::update_proposal_status(proposal_id, ProposalStatus::Executed)?;
+
+    let first_tx_id = store::state::with(|s| s.peek_next_id(NextIdType::Transaction));
+    let result = execution.await;
+    if result.is_err() && !store::transactions::has_broadcast_since(proposal_id, first_tx_id) {
+        store::proposals::update_proposal_status(proposal_id, ProposalStatus::Passed)?;
+    }
+    result
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn erc20_approve(arg: Erc20ApproveArg) -> Result<String, String> {
+    let chain_config = store::state::get_chain_config(&arg.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", arg.chain_type))?;
+    let token_address = chain_config
+        .get_token_config(&arg.token_name)?
+        .contract_address
+        .clone()
+        .ok_or("ERC20 contract address is missing")?;
+    let unlimited = nat_to_be_bytes32(&arg.amount)? == [0xff; 32];
+
+    let wallet_chain = WalletBlockchainConfig(chain_config);
+    let tx_hash = execute_proposal(
+        arg.proposal_id,
+        ProposalAction::Erc20Approve(arg.clone()),
+        wallet_chain.approve_erc20(
+            &arg.token_name,
+            &arg.wallet_address,
+            &arg.spender,
+            &arg.amount,
+            Some(arg.proposal_id),
+        ),
+    )
+    .await?;
+
+    let tx = store::transactions::find_transaction_by_hash(&tx_hash)
+        .ok_or_else(|| format!("Approval transaction {} not found", tx_hash))?;
+    store::approvals::upsert_approval(Erc20Approval {
+        chain_type: arg.chain_type,
+        chain_id: tx.chain_id,
+        token_name: arg.token_name,
+        token_address,
+        owner: arg.wallet_address,
+        spender: arg.spender,
+        approved_amount: arg.amount,
+        unlimited,
+        proposal_id: arg.proposal_id,
+        tx_hash: tx_hash.clone(),
+        approved_at: tx.created_at,
+        allowance: None,
+        checked_at: None,
+    });
+
+    Ok(tx_hash)
+}
+
+/// Revokes an allowance. Admins may revoke without a proposal so exposure can be cut quickly.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn erc20_revoke_approval(arg: Erc20RevokeArg) -> Result<String, String> {
+    let chain_config = store::state::get_chain_config(&arg.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", arg.chain_type))?;
+    let token_address = chain_config
+        .get_token_config(&arg.token_name)?
+        .contract_address
+        .clone()
+        .ok_or("ERC20 contract address is missing")?;
+
+    let wallet_chain = WalletBlockchainConfig(chain_config);
+    let zero = Nat::from(0u64);
+    let revoke = wallet_chain.approve_erc20(
+        &arg.token_name,
+        &arg.wallet_address,
+        &arg.spender,
+        &zero,
+        arg.proposal_id,
+    );
+    let tx_hash = match arg.proposal_id {
+        Some(proposal_id) => {
+            execute_proposal(
+                proposal_id,
+                ProposalAction::Erc20RevokeApproval(arg.clone()),
+                revoke,
+            )
+            .await?
+        }
+        None => revoke.await?,
+    };
+
+    if let Some(tx) = store::transactions::find_transaction_by_hash(&tx_hash) {
+        store::approvals::remove_approval(
+            tx.chain_id,
+            &token_address,
+            &arg.wallet_address,
+            &arg.spender,
+        );
+    }
+
+    Ok(tx_hash)
+}
+
+#[update]
+pub async fn erc20_allowance(
+    chain_type: ChainType,
+    token_name: String,
+    owner: String,
+    spender: String,
+) -> Result<Nat, String> {
+    let chain_config = store::state::get_chain_config(&chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", chain_type))?;
+    let token_address = chain_config
+        .get_token_config(&token_name)?
+        .contract_address
+        .clone()
+        .ok_or("ERC20 contract address is missing")?;
+
+    let allowance = WalletBlockchainConfig(chain_config)
+        .query_erc20_allowance(&token_name, &owner, &spender)
+        .await?;
+
+    let chain_id = store::state::get_chain_id(&chain_type)
+        .ok_or_else(|| format!("Chain id not configured for {:?}", chain_type))?;
+    store::approvals::set_allowance(
+        chain_id,
+        &token_address,
+        &owner,
+        &spender,
+        allowance.clone(),
+        current_time_secs(),
+    );
+
+    Ok(allowance)
+}
+
+/// Calls a function of an arbitrary contract from the DAO's EVM address once a proposal passes.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn evm_contract_call(arg: EvmContractCallArg) -> Result<String, String> {
+    let chain_config = store::state::get_chain_config(&arg.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", arg.chain_type))?;
+
+    let wallet_chain = WalletBlockchainConfig(chain_config);
+    execute_proposal(
+        arg.proposal_id,
+        ProposalAction::EvmContractCall(arg.clone()),
+        wallet_chain.call_contract(&arg),
+    )
+    .await
+}
+
+/// Decodes and simulates a contract call and attaches the preview to its proposal.
+#[update]
+pub async fn preview_evm_contract_call(arg: EvmContractCallArg) -> Result<EvmTxPreview, String> {
+    check_preview_caller(arg.proposal_id)?;
+    let chain_config = store::state::get_chain_config(&arg.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", arg.chain_type))?;
+
+    let preview = WalletBlockchainConfig(chain_config)
+        .preview_contract_call(&arg)
+        .await?;
+    store::proposals::attach_tx_preview(arg.proposal_id, preview.clone())?;
+    Ok(preview)
+}
+
+/// Decodes and simulates an EVM token transfer and attaches the preview to its proposal.
+#[update]
+pub async fn preview_token_transfer(arg: TokenTransferArg) -> Result<EvmTxPreview, String> {
+    let proposal_id = arg
+        .proposal_id
+        .ok_or("A proposal is required to attach the preview")?;
+    check_preview_caller(proposal_id)?;
+    let chain_config = store::state::get_chain_config(&arg.chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", arg.chain_type))?;
+
+    let preview = WalletBlockchainConfig(chain_config)
+        .preview_token_transfer(
+            &arg.token_name,
+            &arg.wallet_address,
+            &arg.recipient_address,
+            &arg.amount,
+        )
+        .await?;
+    store::proposals::attach_tx_preview(proposal_id, preview.clone())?;
+    Ok(preview)
+}
+
+/// Previews can be attached by the proposer or by an admin.
+fn check_preview_caller(proposal_id: u64) -> Result<(), String> {
+    let proposal = store::proposals::get_proposal(proposal_id)
+        .ok_or_else(|| format!("Proposal {} not found", proposal_id))?;
+
+    if proposal.proposer == ic_cdk::api::msg_caller().to_text() {
+        return Ok(());
+    }
+    admin_or_controller_guard()
+}
+
+/// Moves the deposit indexer's cursor, e.g. back to backfill deposits made before it ran.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn set_evm_deposit_cursor(
+    chain_type: ChainType,
+    block_number: u64,
+) -> Result<(), String> {
+    let chain_id = store::state::get_chain_id(&chain_type)
+        .ok_or_else(|| format!("Chain id not configured for {:?}", chain_type))?;
+
+    store::deposits::set_cursor(chain_id, block_number);
+    store::state::save();
+    Ok(())
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn set_price_feed_config(config: PriceFeedConfig) -> Result<(), String> {
+    if config.ttl_secs == 0 {
+        return Err("Price TTL must be positive".to_string());
+    }
+    for source in std::iter::once(&config.default_source).chain(config.sources.values()) {
+        if let PriceSource::Http { url, .. } = source {
+            if !url.starts_with("https://") {
+                return Err(format!("Price source URL must use https: {}", url));
+            }
+        }
+    }
+
+    store::prices::set_price_feed_config(config);
+    store::state::save();
+    Ok(())
+}
+
+/// Fetches a fresh price from the token's source, bypassing the cache.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn refresh_token_price(symbol: String) -> Result<TokenPrice, String> {
+    oracle::refresh_token_price(&symbol).await
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn set_cketh_config(config: CkEthConfig) -> Result<(), String> {
+    if !config.helper_contract_address.starts_with("0x")
+        || config.helper_contract_address.len() != 42
+    {
+        return Err("Invalid helper contract address".to_string());
+    }
+
+    store::bridge::set_cketh_config(config);
+    store::state::save();
+    Ok(())
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn bridge_to_ic(arg: BridgeDepositArg) -> Result<u64, String> {
+    execute_proposal(
+        arg.proposal_id,
+        ProposalAction::BridgeToIc(arg.clone()),
+        bridge::deposit_to_ic(arg),
+    )
+    .await
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn bridge_to_evm(arg: BridgeWithdrawArg) -> Result<u64, String> {
+    execute_proposal(
+        arg.proposal_id,
+        ProposalAction::BridgeToEvm(arg.clone()),
+        bridge::withdraw_to_evm(arg),
+    )
+    .await
+}
+
 #[update]
 pub async fn add_committee(arg: CommitteeArg) -> Result<String, String> {
     let id = store::state::get_next_committee_id();
@@ -232,10 +795,329 @@ pub async fn update_eth_local_chain() -> Result<(), String> {
     Ok(())
 }
 
-#[update]
-async fn set_chain_nonce(chain_type: ChainType, nonce: Option<u64>) -> Result<(), String> {
-    match store::state::set_nonce(chain_type, nonce) {
-        Ok(_) => Ok(()),
-        Err(error) => Err(format!("Failed to update nonce: {}", error)),
+#[update(guard = "admin_or_controller_guard")]
+async fn set_chain_fee_config(chain_type: ChainType, fee_config: FeeConfig) -> Result<(), String> {
+    validate_fee_config(&fee_config)?;
+
+    store::state::update_chain_config(chain_type.clone(), |chain_config| {
+        chain_config.fee_config = Some(fee_config);
+    })?;
+    record_chain_config_change(chain_type, ChainConfigAction::SetFeeConfig);
+    store::state::save();
+    Ok(())
+}
+
+/// Registers a chain. ERC-20 tokens of an EVM chain are checked against their contracts.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn add_chain_config(config: BlockchainConfig) -> Result<(), String> {
+    if store::state::get_chain_config(&config.chain_type).is_some() {
+        return Err(format!("Chain {:?} already exists", config.chain_type));
+    }
+    if config.chain_type.is_evm() {
+        let rpc_config = config
+            .rpc_config
+            .as_ref()
+            .ok_or("EVM chains need an RPC config")?;
+        validate_rpc_config(&config.chain_type, rpc_config)?;
+        config.chain_id().ok_or("EVM chains need a chain id")?;
+    }
+    for token in &config.supported_tokens {
+        validate_token_config(&config, token).await?;
+    }
+
+    let chain_type = config.chain_type.clone();
+    store::state::add_chain_config(config)?;
+    record_chain_config_change(chain_type, ChainConfigAction::AddChain);
+    store::state::save();
+    Ok(())
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn remove_chain_config(chain_type: ChainType) -> Result<(), String> {
+    if chain_type == ChainType::InternetComputer {
+        return Err("The Internet Computer chain cannot be removed".to_string());
+    }
+    // The receipt poller resolves pending transactions through the registry.
+    if let Some(chain_id) = store::state::get_chain_id(&chain_type) {
+        let has_pending = store::transactions::list_open_transactions()
+            .iter()
+            .any(|tx| tx.chain_id == chain_id);
+        if has_pending {
+            return Err(format!("Chain {:?} has pending transactions", chain_type));
+        }
+    }
+
+    store::state::remove_chain_config(&chain_type)?;
+    record_chain_config_change(chain_type, ChainConfigAction::RemoveChain);
+    store::state::save();
+    Ok(())
+}
+
+/// Sets the RPC providers, consensus strategy and custom provider headers of a chain.
+#[update(guard = "admin_or_controller_guard")]
+pub async fn set_chain_rpc_config(
+    chain_type: ChainType,
+    rpc_config: RpcConfig,
+) -> Result<(), String> {
+    validate_rpc_config(&chain_type, &rpc_config)?;
+
+    store::state::update_chain_config(chain_type.clone(), |chain_config| {
+        chain_config.rpc_config = Some(rpc_config);
+    })?;
+    record_chain_config_change(chain_type, ChainConfigAction::SetRpcConfig);
+    store::state::save();
+    Ok(())
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn add_chain_token(chain_type: ChainType, token: TokenConfig) -> Result<(), String> {
+    let chain_config = store::state::get_chain_config(&chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", chain_type))?;
+    if chain_config.get_token_config(&token.token_name).is_ok() {
+        return Err(format!("Token {} already exists", token.token_name));
+    }
+    if let Some(address) = &token.contract_address {
+        if chain_config.has_contract(address) {
+            return Err(format!("Contract {} is already registered", address));
+        }
+    }
+    validate_token_config(&chain_config, &token).await?;
+
+    let token_name = token.token_name.clone();
+    store::state::update_chain_config(chain_type.clone(), |chain_config| {
+        chain_config.supported_tokens.push(token);
+    })?;
+    record_chain_config_change(chain_type, ChainConfigAction::AddToken(token_name));
+    store::state::save();
+    Ok(())
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn update_chain_token(
+    chain_type: ChainType,
+    token_name: String,
+    token: TokenConfig,
+) -> Result<(), String> {
+    let chain_config = store::state::get_chain_config(&chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", chain_type))?;
+    chain_config.get_token_config(&token_name)?;
+    if token.token_name != token_name && chain_config.get_token_config(&token.token_name).is_ok() {
+        return Err(format!("Token {} already exists", token.token_name));
+    }
+    validate_token_config(&chain_config, &token).await?;
+
+    store::state::update_chain_config(chain_type.clone(), |chain_config| {
+        if let Some(existing) = chain_config
+            .supported_tokens
+            .iter_mut()
+            .find(|t| t.token_name == token_name)
+        {
+            *existing = token;
+        }
+    })?;
+    record_chain_config_change(chain_type, ChainConfigAction::UpdateToken(token_name));
+    store::state::save();
+    Ok(())
+}
+
+#[update(guard = "admin_or_controller_guard")]
+pub async fn remove_chain_token(chain_type: ChainType, token_name: String) -> Result<(), String> {
+    let chain_config = store::state::get_chain_config(&chain_type)
+        .ok_or_else(|| format!("Chain config not found for {:?}", chain_type))?;
+    let token = chain_config.get_token_config(&token_name)?;
+    if matches!(token.standard, TokenStandard::Native) {
+        return Err("The native token of a chain cannot be removed".to_string());
+    }
+
+    store::state::update_chain_config(chain_type.clone(), |chain_config| {
+        chain_config
+            .supported_tokens
+            .retain(|t| t.token_name != token_name);
+    })?;
+    record_chain_config_change(chain_type, ChainConfigAction::RemoveToken(token_name));
+    store::state::save();
+    Ok(())
+}
+
+fn record_chain_config_change(chain_type: ChainType, action: ChainConfigAction) {
+    store::state::record_chain_config_change(chain_type, action, ic_cdk::api::msg_caller());
+}
+
+fn validate_fee_config(fee_config: &FeeConfig) -> Result<(), String> {
+    if fee_config.min_priority_fee_per_gas > fee_config.max_priority_fee_per_gas_cap {
+        return Err("Min priority fee cannot exceed the priority fee cap".to_string());
+    }
+    if fee_config.max_priority_fee_per_gas_cap > fee_config.max_fee_per_gas_cap {
+        return Err("Priority fee cap cannot exceed the max fee cap".to_string());
+    }
+    if fee_config.reward_percentile > 100 {
+        return Err("Reward percentile must be between 0 and 100".to_string());
+    }
+    Ok(())
+}
+
+fn validate_rpc_config(chain_type: &ChainType, rpc_config: &RpcConfig) -> Result<(), String> {
+    let expected_chain_id = chain_type.evm_chain_id();
+    if let (Some(expected), Some(chain_id)) = (expected_chain_id, rpc_config.chain_id) {
+        if expected != chain_id {
+            return Err(format!(
+                "Chain id {} does not match {:?}",
+                chain_id, chain_type
+            ));
+        }
+    }
+
+    let provider_count = match &rpc_config.rpc_services {
+        Some(RpcServices::Custom { chain_id, services }) => {
+            if expected_chain_id.is_some_and(|expected| expected != *chain_id) {
+                return Err(format!("Custom providers are for chain {}", chain_id));
+            }
+            if services.is_empty() {
+                return Err("At least one custom provider is required".to_string());
+            }
+            // Only a local development chain may be reached over plain HTTP.
+            let allow_http = *chain_type == ChainType::EthLocal;
+            if let Some(api) = services
+                .iter()
+                .find(|api| !(api.url.starts_with("https://") || allow_http))
+            {
+                return Err(format!("Provider URL must use https: {}", api.url));
+            }
+            Some(services.len())
+        }
+        Some(RpcServices::EthMainnet(services)) => services.as_ref().map(Vec::len),
+        Some(RpcServices::EthSepolia(services)) => services.as_ref().map(Vec::len),
+        Some(RpcServices::ArbitrumOne(services))
+        | Some(RpcServices::BaseMainnet(services))
+        | Some(RpcServices::OptimismMainnet(services)) => services.as_ref().map(Vec::len),
+        None => None,
+    };
+
+    if let Some(ConsensusStrategy::Threshold { total, min }) = &rpc_config.consensus {
+        if *min == 0 || total.is_some_and(|total| *min > total) {
+            return Err("Consensus threshold must be between 1 and the provider count".to_string());
+        }
+        if let (Some(total), Some(count)) = (total, provider_count) {
+            if *total as usize != count {
+                return Err(format!(
+                    "Consensus total {} does not match the {} configured providers",
+                    total, count
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn validate_token_config(
+    chain_config: &BlockchainConfig,
+    token: &TokenConfig,
+) -> Result<(), String> {
+    match token.standard {
+        TokenStandard::ERC20 if !chain_config.chain_type.is_evm() => {
+            Err("ERC20 tokens are only supported on EVM chains".to_string())
+        }
+        TokenStandard::ERC20 => {
+            WalletBlockchainConfig(chain_config.clone())
+                .validate_erc20_token(token)
+                .await
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evm_rpc_types::{EthMainnetService, RpcApi};
+
+    fn custom_rpc(chain_id: u64, urls: &[&str], consensus: Option<ConsensusStrategy>) -> RpcConfig {
+        RpcConfig {
+            rpc_url: String::new(),
+            chain_id: Some(chain_id),
+            rpc_services: Some(RpcServices::Custom {
+                chain_id,
+                services: urls
+                    .iter()
+                    .map(|url| RpcApi {
+                        url: url.to_string(),
+                        headers: None,
+                    })
+                    .collect(),
+            }),
+            consensus,
+        }
+    }
+
+    #[test]
+    fn rpc_config_must_match_the_chain() {
+        let config = custom_rpc(1, &["https://a.example.com"], None);
+        assert!(validate_rpc_config(&ChainType::Ethereum, &config).is_ok());
+        assert!(validate_rpc_config(&ChainType::Evm(1), &config).is_ok());
+        assert!(validate_rpc_config(&ChainType::BNBChain, &config).is_err());
+
+        let mut mismatched = config.clone();
+        mismatched.chain_id = Some(56);
+        assert!(validate_rpc_config(&ChainType::Ethereum, &mismatched).is_err());
+    }
+
+    #[test]
+    fn custom_providers_need_https_outside_local_chains() {
+        let http = custom_rpc(31_337, &["http://localhost:8545"], None);
+        assert!(validate_rpc_config(&ChainType::EthLocal, &http).is_ok());
+
+        let http = custom_rpc(1, &["https://a.example.com", "http://b.example.com"], None);
+        assert!(validate_rpc_config(&ChainType::Ethereum, &http).is_err());
+        assert!(validate_rpc_config(&ChainType::Ethereum, &custom_rpc(1, &[], None)).is_err());
+    }
+
+    #[test]
+    fn consensus_threshold_must_fit_the_providers() {
+        let threshold = |total, min| Some(ConsensusStrategy::Threshold { total, min });
+        let urls = [
+            "https://a.example.com",
+            "https://b.example.com",
+            "https://c.example.com",
+        ];
+
+        let valid = custom_rpc(1, &urls, threshold(Some(3), 2));
+        assert!(validate_rpc_config(&ChainType::Ethereum, &valid).is_ok());
+        for invalid in [
+            threshold(Some(3), 0),
+            threshold(Some(2), 3),
+            threshold(Some(2), 2),
+        ] {
+            let config = custom_rpc(1, &urls, invalid);
+            assert!(validate_rpc_config(&ChainType::Ethereum, &config).is_err());
+        }
+
+        // Without an explicit provider list, the total is not checked against it.
+        let mut all_providers = custom_rpc(1, &[], threshold(Some(4), 3));
+        all_providers.rpc_services = Some(RpcServices::EthMainnet(None));
+        assert!(validate_rpc_config(&ChainType::Ethereum, &all_providers).is_ok());
+        all_providers.rpc_services = Some(RpcServices::EthMainnet(Some(vec![
+            EthMainnetService::Alchemy,
+            EthMainnetService::Ankr,
+        ])));
+        assert!(validate_rpc_config(&ChainType::Ethereum, &all_providers).is_err());
+    }
+
+    #[test]
+    fn fee_config_caps_must_be_ordered() {
+        assert!(validate_fee_config(&FeeConfig::default()).is_ok());
+
+        let mut fee_config = FeeConfig::default();
+        fee_config.min_priority_fee_per_gas = fee_config.max_priority_fee_per_gas_cap + 1;
+        assert!(validate_fee_config(&fee_config).is_err());
+
+        let mut fee_config = FeeConfig::default();
+        fee_config.max_priority_fee_per_gas_cap = fee_config.max_fee_per_gas_cap + 1;
+        assert!(validate_fee_config(&fee_config).is_err());
+
+        let fee_config = FeeConfig {
+            reward_percentile: 101,
+            ..FeeConfig::default()
+        };
+        assert!(validate_fee_config(&fee_config).is_err());
     }
 }
@@ -111,13 +111,19 @@ pub async fn create_icrc1_canister(
     ic_cdk::println!("new icrc1 canister: {}", canister);
 
     let metadata = vec![("icrc1:logo".to_string(), arg.logo)];
-    let init_arg = ICRC1InitArgs::new(
+    let mut init_arg = ICRC1InitArgs::new(
         arg.token_symbol,
         arg.minting_account,
         arg.initial_balances,
         arg.token_name,
         metadata,
     );
+    if let Some(transfer_fee) = arg.transfer_fee {
+        init_arg.transfer_fee = transfer_fee;
+    }
+    if let Some(archive_options) = arg.archive_options {
+        init_arg.archive_options = archive_options;
+    }
     let ledger_arg = LedgerArgument::Init(init_arg);
 
     let init_arg = Encode!(&ledger_arg).unwrap();
@@ -133,17 +139,6 @@ pub async fn create_icrc1_canister(
     .map_err(|e| format!("Failed to create icrc1 canister: {:?}", e))
 }
 
-pub fn icrc1_account_from_str(addr: &str) -> icrc_ledger_types::icrc1::account::Account {
-    use candid::Principal;
-    use icrc_ledger_types::icrc1::account::Account;
-
-    let principal = Principal::from_text(addr).unwrap_or_else(|_| Principal::anonymous());
-    Account {
-        owner: principal,
-        subaccount: None,
-    }
-}
-
 pub fn sha256(data: &[u8]) -> Vec<u8> {
     let mut hasher = sha2::Sha256::new();
     hasher.update(data);
@@ -225,6 +220,18 @@ pub fn nat_to_u128(nat: &Nat) -> Option<u128> {
     nat.0.clone().try_into().ok()
 }
 
+/// Big-endian 256-bit encoding of `nat`, as used for EVM `uint256` values.
+pub fn nat_to_be_bytes32(nat: &Nat) -> Result<[u8; 32], String> {
+    let bytes = nat.0.to_bytes_be();
+    if bytes.len() > 32 {
+        return Err(format!("Amount {} does not fit in 256 bits", nat));
+    }
+
+    let mut be_bytes = [0u8; 32];
+    be_bytes[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(be_bytes)
+}
+
 pub fn convert_network(net: IcBitcoinNetwork) -> BtcNetwork {
     match net {
         IcBitcoinNetwork::Mainnet => BtcNetwork::Bitcoin,
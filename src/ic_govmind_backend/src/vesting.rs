@@ -0,0 +1,430 @@
+use candid::Nat;
+use ic_canister_log::log;
+use ic_govmind_types::constants::VESTING_MAX_RELEASES;
+use ic_govmind_types::dao::{
+    DistributionType, VestingGrant, VestingGrantArg, VestingGrantStatus, HOLDER_SUBACCOUNT,
+    VESTING_SUBACCOUNT,
+};
+use icrc_ledger_types::icrc1::account::Account;
+use std::str::FromStr;
+
+use crate::{
+    distribution::{self, base_token_ledger, queue_payout, subaccount_account},
+    ic_log::INFO,
+    store,
+    utils::current_time_secs,
+};
+
+/// Moves the grant's tokens from the treasury into `VESTING_SUBACCOUNT`, along with the ledger
+/// fees of its releases and of a revocation, and records the grant. The funding is a payout
+/// named after the proposal, so a retried execution resends the same transfer rather than
+/// funding the grant twice. Returns the grant id.
+pub async fn create_grant(arg: VestingGrantArg) -> Result<u64, String> {
+    Account::from_str(&arg.beneficiary)
+        .map_err(|e| format!("Invalid beneficiary account {}: {:?}", arg.beneficiary, e))?;
+    if arg.total_amount == 0u64 {
+        return Err("Grant amount must be positive".to_string());
+    }
+    if arg.cliff_secs > arg.duration_secs {
+        return Err("Cliff cannot be longer than the vesting duration".to_string());
+    }
+    let releases = release_count(&arg)?;
+
+    let token_service = base_token_ledger()?;
+    // One transfer per release, one for the final remainder and one for a revocation.
+    let transfers = releases + 2;
+    let funding_id = format!("vesting-fund:{}", arg.proposal_id);
+    if store::distribution::get_payout(&funding_id).is_none() {
+        let fees = token_service.icrc1_fee().await? * Nat::from(transfers);
+        queue_payout(
+            funding_id.clone(),
+            DistributionType::Vesting,
+            subaccount_account(VESTING_SUBACCOUNT).to_string(),
+            arg.total_amount.clone() + fees,
+            Some(HOLDER_SUBACCOUNT),
+        );
+        store::state::save();
+    }
+    let funded = distribution::pay_claim(&token_service, &funding_id)
+        .await
+        .map_err(|e| format!("Failed to fund the vesting grant: {}", e))?;
+    // The fees are those of the first attempt, whatever the ledger charges now.
+    let fee_reserve = funded - arg.total_amount.clone();
+
+    let id = store::vesting::add_grant(VestingGrant {
+        id: 0,
+        beneficiary: arg.beneficiary,
+        total_amount: arg.total_amount,
+        released: Nat::from(0u64),
+        start: arg.start,
+        cliff_secs: arg.cliff_secs,
+        duration_secs: arg.duration_secs,
+        release_interval_secs: arg.release_interval_secs,
+        revocable: arg.revocable,
+        revoked_at: None,
+        fee: fee_reserve.clone() / Nat::from(transfers),
+        fee_reserve,
+        proposal_id: arg.proposal_id,
+        created_at: current_time_secs(),
+    });
+    store::state::save();
+    Ok(id)
+}
+
+/// Number of release intervals in the grant. The grant pre-funds a ledger fee for each one, so
+/// grants with more than `VESTING_MAX_RELEASES` releases are rejected.
+fn release_count(arg: &VestingGrantArg) -> Result<u64, String> {
+    if arg.release_interval_secs == 0 {
+        return Err("Release interval must be positive".to_string());
+    }
+    let releases = arg.duration_secs.div_ceil(arg.release_interval_secs);
+    if releases > VESTING_MAX_RELEASES {
+        return Err(format!(
+            "Grant would vest in {} releases, more than the maximum of {}",
+            releases, VESTING_MAX_RELEASES
+        ));
+    }
+    Ok(releases)
+}
+
+/// Stops a revocable grant and returns its unvested remainder to the treasury, with the fees
+/// prefunded for the releases that will not happen. Tokens vested before the revocation stay
+/// claimable. Returns the amount sent back.
+pub fn revoke_grant(grant_id: u64) -> Result<Nat, String> {
+    let now = current_time_secs();
+    let returned = store::vesting::update_grant(grant_id, |grant| {
+        if !grant.revocable {
+            return Err(format!("Vesting grant {} is not revocable", grant_id));
+        }
+        if grant.revoked_at.is_some() {
+            return Err(format!("Vesting grant {} is already revoked", grant_id));
+        }
+        grant.revoked_at = Some(now);
+        Ok(revoke(grant, now))
+    })?;
+
+    if returned > 0u64 {
+        let treasury = subaccount_account(HOLDER_SUBACCOUNT);
+        queue_payout(
+            format!("vesting-revoke:{}", grant_id),
+            DistributionType::Vesting,
+            treasury.to_string(),
+            returned.clone(),
+            Some(VESTING_SUBACCOUNT),
+        );
+    }
+    store::state::save();
+    Ok(returned)
+}
+
+/// The amount a revocation returns: the unvested tokens and the fee reserve, less the fees of
+/// the return itself and of the release of what vested before it.
+fn revoke(grant: &mut VestingGrant, now: u64) -> Nat {
+    let status = grant_status(grant, now);
+    let kept = match status.releasable > 0u64 {
+        true => grant.fee.clone(),
+        false => Nat::from(0u64),
+    };
+    let available = status.unvested + grant.fee_reserve.clone();
+    let spent = kept.clone() + grant.fee.clone();
+    grant.fee_reserve = kept;
+    match available > spent {
+        true => available - spent,
+        false => Nat::from(0u64),
+    }
+}
+
+/// Reprices the fee reserves of the grants after the ledger fee changed to `fee`. A grant whose
+/// reserve cannot pay its remaining transfers at the new fee is topped up from the treasury;
+/// surplus reserves are returned once the grant is fully released.
+pub fn reprice_fees(fee: &Nat, proposal_id: u64, now: u64) {
+    let mut top_up = Nat::from(0u64);
+    for grant in store::vesting::list_grants() {
+        let shortfall =
+            store::vesting::update_grant(grant.id, |grant| Ok(reprice(grant, fee, now)));
+        top_up += shortfall.unwrap_or_default();
+    }
+
+    if top_up > 0u64 {
+        queue_payout(
+            format!("vesting-fees:{}", proposal_id),
+            DistributionType::Vesting,
+            subaccount_account(VESTING_SUBACCOUNT).to_string(),
+            top_up,
+            Some(HOLDER_SUBACCOUNT),
+        );
+    }
+}
+
+/// Sets the grant's fee to `fee`. Returns how much its reserve falls short of paying its
+/// remaining transfers at that fee, which is added to the reserve.
+fn reprice(grant: &mut VestingGrant, fee: &Nat, now: u64) -> Nat {
+    grant.fee = fee.clone();
+    let needed = fee.clone() * Nat::from(transfers_left(grant, now));
+    if needed <= grant.fee_reserve {
+        return Nat::from(0u64);
+    }
+    let shortfall = needed.clone() - grant.fee_reserve.clone();
+    grant.fee_reserve = needed;
+    shortfall
+}
+
+/// Upper bound of the transfers the grant still makes: a release per interval not yet vested,
+/// one for the final remainder and one for a revocation.
+fn transfers_left(grant: &VestingGrant, now: u64) -> u64 {
+    if grant.revoked_at.is_some() {
+        return (grant_status(grant, now).releasable > 0u64) as u64;
+    }
+    if grant.released >= grant.total_amount {
+        return 0;
+    }
+    let end = grant.start.saturating_add(grant.duration_secs);
+    let intervals = end
+        .saturating_sub(now.max(grant.start))
+        .div_ceil(grant.release_interval_secs.max(1));
+    intervals + 1 + grant.revocable as u64
+}
+
+/// Releases the vested tokens of `grant_id` to the calling beneficiary right away. Returns the
+/// amount released.
+pub async fn claim(grant_id: u64) -> Result<Nat, String> {
+    let grant = store::vesting::get_grant(grant_id)
+        .ok_or_else(|| format!("Vesting grant {} not found", grant_id))?;
+    let beneficiary = Account::from_str(&grant.beneficiary)
+        .map_err(|e| format!("Invalid beneficiary account: {:?}", e))?;
+    if beneficiary.owner != ic_cdk::api::msg_caller() {
+        return Err("Only the beneficiary can claim a vesting grant".to_string());
+    }
+
+    let token_service = base_token_ledger()?;
+    let (payout_id, _) =
+        release(grant_id, current_time_secs())?.ok_or("No vested tokens to claim")?;
+    store::state::save();
+
+    distribution::pay_claim(&token_service, &payout_id).await
+}
+
+/// Plans a release for every grant with vested tokens that were not released yet.
+pub fn plan_releases(now: u64) {
+    for grant in store::vesting::list_grants() {
+        if let Err(e) = release(grant.id, now) {
+            log!(INFO, "Failed to release vesting grant {}: {}", grant.id, e);
+        }
+    }
+}
+
+/// Hands the releasable tokens of a grant to a distribution payout, which is identified by the
+/// total released so far and therefore planned only once. The last release also returns what is
+/// left of the fee reserve to the treasury.
+fn release(grant_id: u64, now: u64) -> Result<Option<(String, Nat)>, String> {
+    let (release, surplus) = store::vesting::update_grant(grant_id, |grant| {
+        let releasable = grant_status(grant, now).releasable;
+        if releasable == 0u64 {
+            return Ok((None, None));
+        }
+        grant.released += releasable.clone();
+        grant.fee_reserve = match grant.fee_reserve > grant.fee {
+            true => grant.fee_reserve.clone() - grant.fee.clone(),
+            false => Nat::from(0u64),
+        };
+        let release = (
+            format!("vesting:{}:{}", grant_id, grant.released),
+            grant.beneficiary.clone(),
+            releasable,
+        );
+        Ok((Some(release), take_surplus(grant, now)))
+    })?;
+
+    if let Some(surplus) = surplus {
+        queue_payout(
+            format!("vesting-fees-return:{}", grant_id),
+            DistributionType::Vesting,
+            subaccount_account(HOLDER_SUBACCOUNT).to_string(),
+            surplus,
+            Some(VESTING_SUBACCOUNT),
+        );
+    }
+    Ok(release.map(|(payout_id, beneficiary, amount)| {
+        queue_payout(
+            payout_id.clone(),
+            DistributionType::Vesting,
+            beneficiary,
+            amount.clone(),
+            Some(VESTING_SUBACCOUNT),
+        );
+        (payout_id, amount)
+    }))
+}
+
+/// Empties the fee reserve of a grant that makes no more transfers. Returns the amount worth
+/// sending back to the treasury, net of the fee of that transfer.
+fn take_surplus(grant: &mut VestingGrant, now: u64) -> Option<Nat> {
+    if transfers_left(grant, now) > 0 || grant.fee_reserve <= grant.fee {
+        return None;
+    }
+    let surplus = grant.fee_reserve.clone() - grant.fee.clone();
+    grant.fee_reserve = Nat::from(0u64);
+    Some(surplus)
+}
+
+pub fn grant_status(grant: &VestingGrant, now: u64) -> VestingGrantStatus {
+    let vested = vested_amount(grant, now);
+    let unvested = match grant.total_amount > vested {
+        true => grant.total_amount.clone() - vested.clone(),
+        false => Nat::from(0u64),
+    };
+    let releasable = match vested > grant.released {
+        true => vested.clone() - grant.released.clone(),
+        false => Nat::from(0u64),
+    };
+
+    VestingGrantStatus {
+        grant: grant.clone(),
+        vested,
+        unvested,
+        releasable,
+    }
+}
+
+/// Tokens vested at `now`: nothing before the cliff, then a linear share of the grant that grows
+/// in whole release intervals, frozen at the revocation time.
+fn vested_amount(grant: &VestingGrant, now: u64) -> Nat {
+    let now = grant
+        .revoked_at
+        .map_or(now, |revoked_at| now.min(revoked_at));
+    if now < grant.start.saturating_add(grant.cliff_secs) {
+        return Nat::from(0u64);
+    }
+    if grant.duration_secs == 0 {
+        return grant.total_amount.clone();
+    }
+
+    let elapsed = (now - grant.start).min(grant.duration_secs);
+    let elapsed = match elapsed < grant.duration_secs && grant.release_interval_secs > 0 {
+        true => elapsed - elapsed % grant.release_interval_secs,
+        false => elapsed,
+    };
+    grant.total_amount.clone() * Nat::from(elapsed) / Nat::from(grant.duration_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant() -> VestingGrant {
+        VestingGrant {
+            id: 1,
+            beneficiary: "aaaaa-aa".to_string(),
+            total_amount: Nat::from(1_200u64),
+            released: Nat::from(0u64),
+            start: 1_000,
+            cliff_secs: 300,
+            duration_secs: 1_200,
+            release_interval_secs: 100,
+            revocable: true,
+            revoked_at: None,
+            fee: Nat::from(10u64),
+            fee_reserve: Nat::from(140u64),
+            proposal_id: 1,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn nothing_vests_before_the_cliff() {
+        assert_eq!(vested_amount(&grant(), 999), Nat::from(0u64));
+        assert_eq!(vested_amount(&grant(), 1_299), Nat::from(0u64));
+        assert_eq!(vested_amount(&grant(), 1_300), Nat::from(300u64));
+    }
+
+    #[test]
+    fn vests_in_whole_intervals_until_the_end() {
+        assert_eq!(vested_amount(&grant(), 1_450), Nat::from(400u64));
+        assert_eq!(vested_amount(&grant(), 2_199), Nat::from(1_100u64));
+        assert_eq!(vested_amount(&grant(), 2_200), Nat::from(1_200u64));
+        assert_eq!(vested_amount(&grant(), 9_999), Nat::from(1_200u64));
+    }
+
+    #[test]
+    fn rejects_grants_with_too_many_releases() {
+        let mut arg = VestingGrantArg {
+            beneficiary: "aaaaa-aa".to_string(),
+            total_amount: Nat::from(1_200u64),
+            start: 1_000,
+            cliff_secs: 0,
+            duration_secs: VESTING_MAX_RELEASES * 100,
+            release_interval_secs: 100,
+            revocable: false,
+            proposal_id: 1,
+        };
+        assert_eq!(release_count(&arg), Ok(VESTING_MAX_RELEASES));
+
+        arg.release_interval_secs = 99;
+        assert!(release_count(&arg).is_err());
+        arg.release_interval_secs = 0;
+        assert!(release_count(&arg).is_err());
+    }
+
+    #[test]
+    fn revocation_freezes_vesting() {
+        let mut grant = grant();
+        grant.revoked_at = Some(1_650);
+        grant.released = Nat::from(200u64);
+
+        let status = grant_status(&grant, 5_000);
+        assert_eq!(status.vested, Nat::from(600u64));
+        assert_eq!(status.unvested, Nat::from(600u64));
+        assert_eq!(status.releasable, Nat::from(400u64));
+    }
+
+    #[test]
+    fn revocation_returns_the_fees_of_cancelled_releases() {
+        let mut grant = grant();
+        grant.released = Nat::from(300u64);
+        grant.fee_reserve = Nat::from(110u64);
+
+        // 400 vested, 100 of it not released yet: one fee is kept for that release and one pays
+        // for the return.
+        assert_eq!(revoke(&mut grant, 1_450), Nat::from(800u64 + 90));
+        assert_eq!(grant.fee_reserve, Nat::from(10u64));
+
+        let mut grant = self::grant();
+        grant.released = Nat::from(400u64);
+        grant.fee_reserve = Nat::from(110u64);
+        assert_eq!(revoke(&mut grant, 1_450), Nat::from(800u64 + 100));
+        assert_eq!(grant.fee_reserve, Nat::from(0u64));
+    }
+
+    #[test]
+    fn a_higher_ledger_fee_is_topped_up() {
+        let mut grant = grant();
+        // 8 intervals left, the final remainder and a revocation.
+        assert_eq!(transfers_left(&grant, 1_450), 10);
+
+        assert_eq!(
+            reprice(&mut grant, &Nat::from(20u64), 1_450),
+            Nat::from(60u64)
+        );
+        assert_eq!(grant.fee_reserve, Nat::from(200u64));
+        assert_eq!(grant.fee, Nat::from(20u64));
+
+        // A lower fee leaves the surplus in the reserve until the grant is done.
+        assert_eq!(
+            reprice(&mut grant, &Nat::from(5u64), 1_450),
+            Nat::from(0u64)
+        );
+        assert_eq!(grant.fee_reserve, Nat::from(200u64));
+    }
+
+    #[test]
+    fn the_fee_surplus_is_returned_after_the_last_release() {
+        let mut grant = grant();
+        grant.released = Nat::from(1_100u64);
+        grant.fee_reserve = Nat::from(50u64);
+        assert_eq!(take_surplus(&mut grant, 2_200), None);
+
+        grant.released = Nat::from(1_200u64);
+        assert_eq!(take_surplus(&mut grant, 2_200), Some(Nat::from(40u64)));
+        assert_eq!(grant.fee_reserve, Nat::from(0u64));
+    }
+}
@@ -1,40 +1,53 @@
 use crate::{
+    chain::evm_tx::{EvmTxFees, EvmTxRequest},
     ic_log::INFO,
     services::{
         evm_abi::{
-            extract_json_result, generate_create_token, generate_erc20_transfer_data,
-            parse_nonce_hex,
+            decode_erc20_decimals, decode_erc20_symbol, describe_function_call,
+            encode_function_call, extract_json_result, find_function, format_token,
+            format_token_amount, generate_cketh_deposit_erc20, generate_cketh_deposit_eth,
+            generate_create_token, generate_erc20_approve, generate_erc20_transfer_data,
+            generate_token_deployment, parse_abi, parse_call_response, parse_hex_nat,
+            parse_nonce_hex, try_parse_abi, u256_to_nat, ERC20_WRITE_ABI_JSON,
         },
+        evm_fee::{estimate_gas_limit, resolve_eip1559_fees, Eip1559Fees},
+        evm_providers,
         evm_service::EvmService,
         token_icrc1::TokenICRC1,
     },
     signer::signing,
-    store::{self, state},
-    utils::{account_id, convert_subaccount, nat_to_u128, owner_wallet_pid},
-    ETH_CREATE_TOKEN_CONTRACT, ETH_CREATE_TOKEN_GAS, ETH_DEFAULT_GAS_PRICE, ETH_ERC20_TRANSFER_GAS,
-    ETH_TRANSFER_GAS, WEB3_URL,
+    store,
+    types::{EvmContractCallArg, EvmNonceReport},
+    utils::{
+        account_id, convert_subaccount, current_time_secs, nat_to_be_bytes32, nat_to_u128,
+        owner_wallet_pid,
+    },
+    ETH_CKERC20_DEPOSIT_GAS, ETH_CREATE_TOKEN_CONTRACT, ETH_ERC20_APPROVE_GAS, ETH_TRANSFER_GAS,
 };
 use base58::ToBase58;
 use bitcoin_hashes::{ripemd160, sha256, Hash as BitcoinHash};
 use candid::{CandidType, Deserialize, Nat, Principal};
-use ethers_core::types::H160;
+use ethers_core::{
+    abi::{Function, FunctionExt, Token},
+    types::{Address, H160, U256},
+};
 use evm_rpc_types::{MultiRpcResult, SendRawTransactionStatus};
 use ic_canister_log::log;
 use ic_cdk::api::debug_print;
 use ic_govmind_types::{
-    chain::{BlockchainConfig, TokenConfig, TokenStandard},
-    constants::{EVM_RPC_CANISTER_ID, LEDGER_CANISTER_ID},
+    chain::{
+        BlockchainConfig, EvmTransaction, EvmTxPreview, EvmTxStatus, FeeConfig, TokenConfig,
+        TokenStandard,
+    },
+    constants::{EVM_RPC_CANISTER_ID, EVM_TX_REPLACEMENT_FEE_BUMP_PERCENT, LEDGER_CANISTER_ID},
     dao::ChainType,
 };
 use ic_ledger_types::{account_balance, AccountBalanceArgs, Memo, Subaccount, Tokens, DEFAULT_FEE};
-use ic_web3_rs::{
-    ethabi::ethereum_types::Address, futures::io::Chain, ic::KeyInfo, transports::ICHttp, types::{TransactionParameters, U256}, Web3
-};
 use icrc_ledger_types::icrc1::account::Account;
 use libsecp256k1::{PublicKey, PublicKeyFormat};
 use serde::{de::Error as DeError, Serialize};
 use serde_json::{self, Value};
-use std::str::FromStr;
+use std::{future::Future, str::FromStr};
 
 #[derive(CandidType, Clone, Deserialize, Serialize, Debug, Default)]
 pub struct WalletConfig {
@@ -68,7 +81,8 @@ impl WalletConfig {
             ChainType::BNBChain
             | ChainType::Ethereum
             | ChainType::EthSepolia
-            | ChainType::EthLocal => {
+            | ChainType::EthLocal
+            | ChainType::Evm(_) => {
                 // For Ethereum, derive the Ethereum address
                 Self::derive_eth_address(public_key)
             }
@@ -132,6 +146,17 @@ impl WalletConfig {
     }
 }
 
+/// Chain, nonce and fee parameters shared by every transaction sent from the DAO's EVM address.
+#[derive(Debug, Clone)]
+pub struct EvmTxContext {
+    pub chain_type: ChainType,
+    pub chain_id: u64,
+    pub nonce: u128,
+    pub fee_config: FeeConfig,
+    pub fees: Eip1559Fees,
+    pub proposal_id: Option<u64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct WalletBlockchainConfig(pub BlockchainConfig);
 
@@ -151,7 +176,7 @@ impl WalletBlockchainConfig {
                 self.query_balance_internet_computer(&token_config, wallet_address, subaccount)
                     .await
             }
-            ChainType::Ethereum | ChainType::EthSepolia | ChainType::EthLocal => {
+            _ if self.0.chain_type.is_evm() => {
                 self.query_balance_ethereum(&token_config, wallet_address)
                     .await
             }
@@ -168,7 +193,8 @@ impl WalletBlockchainConfig {
         subaccount: &Option<Subaccount>,
         recipient: &str,
         recipient_subaccount: &Option<Subaccount>,
-        amount: u64,
+        amount: Nat,
+        proposal_id: Option<u64>,
     ) -> Result<String, String> {
         // Find the token by name
         let token_config = self.0.get_token_config(token_name)?;
@@ -185,16 +211,13 @@ impl WalletBlockchainConfig {
                 )
                 .await
             }
-            ChainType::Ethereum | ChainType::EthSepolia | ChainType::EthLocal => {
-                let env = state::get_env();
-                let chain_type = env.get_eth_chain_type();
-
+            _ if self.0.chain_type.is_evm() => {
                 self.transfer_ethereum(
-                    &chain_type,
                     token_config,
                     wallet_address,
                     recipient,
-                    amount,
+                    &amount,
+                    proposal_id,
                 )
                 .await
             }
@@ -267,16 +290,15 @@ impl WalletBlockchainConfig {
         wallet_address: &str,
     ) -> Result<u128, String> {
         let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
-        let env = state::get_env();
-        let rpc_service = env.get_rpc_service();
 
         match token.standard {
             TokenStandard::Native => {
-                match evm_service
-                    .get_eth_balance(rpc_service, wallet_address)
-                    .await
+                match evm_providers::request(&self.0, |rpc_service| {
+                    evm_service.get_eth_balance(rpc_service, wallet_address)
+                })
+                .await
                 {
-                    Ok((Ok(balance_json),)) => {
+                    Ok(balance_json) => {
                         let balance_hex = match serde_json::from_str::<Value>(&balance_json) {
                             Ok(json) => {
                                 match json.get("result").and_then(|result| result.as_str()) {
@@ -302,8 +324,7 @@ impl WalletBlockchainConfig {
 
                         Ok(balance)
                     }
-                    Ok((Err(e),)) => Err(format!("Error querying balance: {:?}", e)),
-                    Err(e) => Err(format!("Failed to call get_eth_balance: {:?}", e)),
+                    Err(e) => Err(format!("Error querying balance: {}", e)),
                 }
             }
             TokenStandard::ERC20 => {
@@ -315,16 +336,17 @@ impl WalletBlockchainConfig {
                 let wallet_address = H160::from_str(wallet_address)
                     .map_err(|_| "Invalid wallet_address".to_string())?;
 
-                match evm_service
-                    .get_erc20_balance(
+                match evm_providers::request(&self.0, |rpc_service| {
+                    evm_service.get_erc20_balance(
                         rpc_service,
                         token_address.to_string(),
                         wallet_address,
                         "latest",
                     )
-                    .await
+                })
+                .await
                 {
-                    Ok((Ok(balance_json),)) => {
+                    Ok(balance_json) => {
                         let balance_hex = match serde_json::from_str::<Value>(&balance_json) {
                             Ok(json) => {
                                 match json.get("result").and_then(|result| result.as_str()) {
@@ -350,8 +372,7 @@ impl WalletBlockchainConfig {
 
                         Ok(balance)
                     }
-                    Ok((Err(e),)) => Err(format!("Error querying balance: {:?}", e)),
-                    Err(e) => Err(format!("Failed to call get_erc20_balance: {:?}", e)),
+                    Err(e) => Err(format!("Error querying balance: {}", e)),
                 }
             }
             _ => Err("Token standard not supported on Ethereum".to_string()),
@@ -363,7 +384,7 @@ impl WalletBlockchainConfig {
         token: &TokenConfig,
         recipient: &str,
         recipient_subaccount: &Option<Subaccount>,
-        amount: u64,
+        amount: Nat,
         subaccount: &Option<Subaccount>,
     ) -> Result<String, String> {
         let recipient_pid =
@@ -372,7 +393,12 @@ impl WalletBlockchainConfig {
         match token.standard {
             TokenStandard::Native => {
                 let recipient_account_id = account_id(recipient_pid, recipient_subaccount.clone());
-                let transfer_amount = Tokens::from_e8s(amount);
+                let e8s: u64 = amount
+                    .0
+                    .clone()
+                    .try_into()
+                    .map_err(|_| format!("ICP amount {} exceeds the ledger's range", amount))?;
+                let transfer_amount = Tokens::from_e8s(e8s);
                 let canister_service = TokenICRC1::new(LEDGER_CANISTER_ID)?;
 
                 match canister_service
@@ -417,7 +443,7 @@ impl WalletBlockchainConfig {
                     .icrc1_transfer(
                         icrc1_subaccount,
                         recipient_account,
-                        Nat::from(amount),
+                        amount.clone(),
                         None,
                         None,
                         None,
@@ -438,112 +464,97 @@ impl WalletBlockchainConfig {
 
     async fn transfer_ethereum(
         &self,
-        chain_type: &ChainType,
         token: &TokenConfig,
         wallet_address: &str,
         recipient: &str,
-        amount: u64,
+        amount: &Nat,
+        proposal_id: Option<u64>,
     ) -> Result<String, String> {
-        let chain_id = store::state::get_chain_id(chain_type).unwrap_or(1);
-        let key_info = store::state::get_key_info()?;
-        log!(INFO, "key_info: {:?}", &key_info);
-
-        let wallet_nonce = self
-            .resolve_account_nonce(chain_type, wallet_address)
+        let ctx = self
+            .prepare_eth_tx_context(wallet_address, proposal_id)
             .await?;
 
-        // Initialize the web3 connection
-        let w3: Web3<ICHttp> = match ICHttp::new(WEB3_URL, None) {
-            Ok(v) => Web3::new(v),
-            Err(e) => return Err(e.to_string()),
-        };
-
         // Match based on token standard
         let result = match token.standard {
             TokenStandard::Native => {
-                self.handle_eth_native_transfer(
-                    w3,
-                    wallet_address,
-                    recipient,
-                    amount,
-                    wallet_nonce,
-                    chain_id,
-                    key_info,
-                )
-                .await
+                self.handle_eth_native_transfer(&ctx, wallet_address, recipient, amount)
+                    .await
             }
             TokenStandard::ERC20 => {
-                self.handle_eth_erc20_transfer(
-                    w3,
-                    token,
-                    wallet_address,
-                    recipient,
-                    amount,
-                    wallet_nonce,
-                    chain_id,
-                    key_info,
-                )
-                .await
+                self.handle_eth_erc20_transfer(&ctx, token, wallet_address, recipient, amount)
+                    .await
             }
             _ => Err("Token standard not supported on Ethereum".to_string()),
         };
 
-        if result.is_ok() {
-            store::state::increment_nonce(chain_type.clone())
-                .map_err(|e| format!("Failed to increment nonce: {}", e))?;
+        if result.is_err() {
+            release_nonce_if_unused(&ctx, wallet_address);
         }
 
         result
     }
 
+    /// Resolves the chain id, nonce and fees for the next transaction from `wallet_address`.
+    pub async fn prepare_eth_tx_context(
+        &self,
+        wallet_address: &str,
+        proposal_id: Option<u64>,
+    ) -> Result<EvmTxContext, String> {
+        let chain_id = self.evm_chain_id()?;
+
+        let nonce = self.reserve_account_nonce(chain_id, wallet_address).await?;
+
+        let fee_config = self.0.get_fee_config();
+        let fees = resolve_eip1559_fees(&self.0).await?;
+
+        Ok(EvmTxContext {
+            chain_type: self.0.chain_type.clone(),
+            chain_id,
+            nonce,
+            fee_config,
+            fees,
+            proposal_id,
+        })
+    }
+
     async fn handle_eth_native_transfer(
         &self,
-        w3: Web3<ICHttp>,
+        ctx: &EvmTxContext,
         wallet_address: &str,
         recipient: &str,
-        amount: u64,
-        wallet_nonce: u128,
-        chain_id: u64,
-        key_info: KeyInfo,
+        amount: &Nat,
     ) -> Result<String, String> {
         let to = Address::from_str(recipient)
             .map_err(|e| format!("Invalid recipient address: {:?}", e))?;
 
-        let tx = TransactionParameters {
+        let gas_limit =
+            estimate_gas_limit(&self.0, wallet_address, Some(recipient), amount, &[]).await?;
+
+        let tx = EvmTxRequest {
             to: Some(to),
-            nonce: Some(U256::from(wallet_nonce)),
-            value: U256::from(amount),
-            gas_price: Some(U256::from(ETH_DEFAULT_GAS_PRICE)),
-            gas: U256::from(ETH_TRANSFER_GAS),
-            ..Default::default()
+            value: U256::from_big_endian(&nat_to_be_bytes32(amount)?),
+            gas_limit,
+            ..eip1559_tx(ctx)
         };
 
         debug_print(&format!("handle_eth_native_transfer tx: {:?}", tx));
         debug_print(&format!("handle_eth_native_transfer wallet_address: {:?}", wallet_address));
         debug_print(&format!("handle_eth_native_transfer recipient: {:?}", recipient));
-        debug_print(&format!("handle_eth_native_transfer key_info: {:?}", key_info));
-        debug_print(&format!("handle_eth_native_transfer chain_id: {:?}", chain_id));
+        debug_print(&format!(
+            "handle_eth_native_transfer chain_id: {:?}",
+            ctx.chain_id
+        ));
 
-        let signed_tx = w3
-            .accounts()
-            .sign_transaction(tx, wallet_address.to_string(), key_info, chain_id)
-            .await
-            .map_err(|e| format!("sign tx error: {}", e))?;
-
-        let signed_tx_hash = hex::encode(signed_tx.raw_transaction.0);
-        self.send_raw_tx_ethereum(signed_tx_hash).await
+        self.sign_and_send_eth_tx(ctx, wallet_address, tx).await
     }
 
     async fn handle_eth_erc20_transfer(
         &self,
-        w3: Web3<ICHttp>,
+        ctx: &EvmTxContext,
         token: &TokenConfig,
         wallet_address: &str,
         recipient: &str,
-        amount: u64,
-        wallet_nonce: u128,
-        chain_id: u64,
-        key_info: KeyInfo,
+        amount: &Nat,
     ) -> Result<String, String> {
         let contract_address = token
             .contract_address
@@ -552,134 +563,1059 @@ impl WalletBlockchainConfig {
 
         let data = generate_erc20_transfer_data(recipient, amount)?;
 
-        let tx = TransactionParameters {
+        // Surface the revert reason, e.g. an insufficient balance, before signing.
+        self.simulate_contract_call(wallet_address, contract_address, &Nat::from(0u64), &data)
+            .await?;
+
+        let gas_limit = estimate_gas_limit(
+            &self.0,
+            wallet_address,
+            Some(contract_address.as_str()),
+            &Nat::from(0u64),
+            &data,
+        )
+        .await?;
+
+        let tx = EvmTxRequest {
             to: Some(
                 Address::from_str(contract_address)
                     .map_err(|e| format!("Invalid contract address: {:?}", e))?,
             ),
-            nonce: Some(U256::from(wallet_nonce)),
             value: U256::zero(),
-            gas_price: Some(U256::from(ETH_DEFAULT_GAS_PRICE)),
-            gas: U256::from(ETH_ERC20_TRANSFER_GAS),
-            data: data.into(),
-            ..Default::default()
+            gas_limit,
+            data,
+            ..eip1559_tx(ctx)
         };
 
-        let signed_tx = w3
-            .accounts()
-            .sign_transaction(tx, wallet_address.to_string(), key_info, chain_id)
-            .await
-            .map_err(|e| format!("sign tx error: {}", e))?;
+        self.sign_and_send_eth_tx(ctx, wallet_address, tx).await
+    }
+
+    /// Sets the allowance of `spender` over the ERC-20 balance of `wallet_address`.
+    /// An amount of zero revokes the allowance.
+    pub async fn approve_erc20(
+        &self,
+        token_name: &str,
+        wallet_address: &str,
+        spender: &str,
+        amount: &Nat,
+        proposal_id: Option<u64>,
+    ) -> Result<String, String> {
+        let token = self.erc20_token_config(token_name)?;
+        let contract_address = token
+            .contract_address
+            .as_ref()
+            .ok_or("ERC20 contract address is missing")?;
+
+        let ctx = self
+            .prepare_eth_tx_context(wallet_address, proposal_id)
+            .await?;
 
-        let signed_tx_hash = hex::encode(signed_tx.raw_transaction.0);
-        self.send_raw_tx_ethereum(signed_tx_hash).await
+        let result = self
+            .handle_eth_erc20_approve(&ctx, contract_address, wallet_address, spender, amount)
+            .await;
+
+        if result.is_err() {
+            release_nonce_if_unused(&ctx, wallet_address);
+        }
+
+        result
     }
 
-    pub async fn create_token_ethereum(
+    async fn handle_eth_erc20_approve(
         &self,
-        w3: Web3<ICHttp>,
-        token: &TokenConfig,
-        name: String,
-        symbol: String,
-        supply: u64,
-        owner: String,
-        wallet_nonce: u128,
-        chain_id: u64,
-        key_info: KeyInfo,
+        ctx: &EvmTxContext,
+        contract_address: &str,
+        wallet_address: &str,
+        spender: &str,
+        amount: &Nat,
     ) -> Result<String, String> {
-        let data = generate_create_token(name, symbol, supply, owner.clone())?;
+        let data = generate_erc20_approve(spender, amount)?;
+
+        let gas_limit = match estimate_gas_limit(
+            &self.0,
+            wallet_address,
+            Some(contract_address),
+            &Nat::from(0u64),
+            &data,
+        )
+        .await
+        {
+            Ok(gas_limit) => gas_limit,
+            Err(e) => {
+                log!(INFO, "Approve gas estimation failed, using default: {}", e);
+                ETH_ERC20_APPROVE_GAS as u128
+            }
+        };
 
-        let tx = TransactionParameters {
+        let tx = EvmTxRequest {
             to: Some(
-                Address::from_str(ETH_CREATE_TOKEN_CONTRACT)
+                Address::from_str(contract_address)
                     .map_err(|e| format!("Invalid contract address: {:?}", e))?,
             ),
-            nonce: Some(U256::from(wallet_nonce)),
             value: U256::zero(),
-            gas_price: Some(U256::from(ETH_DEFAULT_GAS_PRICE)),
-            gas: U256::from(ETH_CREATE_TOKEN_GAS),
-            data: data.into(),
-            ..Default::default()
+            gas_limit,
+            data,
+            ..eip1559_tx(ctx)
         };
 
-        let signed_tx = w3
-            .accounts()
-            .sign_transaction(tx, owner.to_string(), key_info, chain_id)
+        self.sign_and_send_eth_tx(ctx, wallet_address, tx).await
+    }
+
+    /// Deposits ETH or an ERC-20 token from `wallet_address` into the ckETH helper contract,
+    /// minting ckETH/ckERC20 to `principal`. ERC-20 deposits first approve the helper contract
+    /// for `amount`. Returns the hashes of the broadcast transactions in order.
+    pub async fn deposit_to_cketh(
+        &self,
+        token_name: &str,
+        wallet_address: &str,
+        helper_contract: &str,
+        principal: Principal,
+        amount: &Nat,
+        proposal_id: Option<u64>,
+    ) -> Result<Vec<String>, String> {
+        let cketh_chains = [
+            ChainType::Ethereum,
+            ChainType::EthSepolia,
+            ChainType::EthLocal,
+        ];
+        if !cketh_chains.iter().any(|chain| self.0.is_chain(chain)) {
+            return Err("ckETH deposits are only supported from Ethereum".to_string());
+        }
+
+        let token = self.0.get_token_config(token_name)?;
+        let helper_address = Address::from_str(helper_contract)
+            .map_err(|e| format!("Invalid helper contract address: {:?}", e))?;
+
+        let mut tx_hashes = vec![];
+        let data = match token.standard {
+            TokenStandard::Native => generate_cketh_deposit_eth(principal, [0u8; 32])?,
+            TokenStandard::ERC20 => {
+                let token_address = token
+                    .contract_address
+                    .as_deref()
+                    .ok_or("ERC20 contract address is missing")?;
+                let erc20_address = H160::from_str(token_address)
+                    .map_err(|_| "Invalid ERC20 contract address".to_string())?;
+
+                tx_hashes.push(
+                    self.approve_erc20(
+                        token_name,
+                        wallet_address,
+                        helper_contract,
+                        amount,
+                        proposal_id,
+                    )
+                    .await?,
+                );
+                generate_cketh_deposit_erc20(erc20_address, amount, principal, [0u8; 32])?
+            }
+            _ => return Err("Token standard not supported by the ckETH bridge".to_string()),
+        };
+        let value = match token.standard {
+            TokenStandard::Native => amount.clone(),
+            _ => Nat::from(0u64),
+        };
+
+        let ctx = self
+            .prepare_eth_tx_context(wallet_address, proposal_id)
+            .await?;
+
+        // The ERC-20 deposit cannot be simulated until its approval is mined.
+        let gas_limit = match estimate_gas_limit(
+            &self.0,
+            wallet_address,
+            Some(helper_contract),
+            &value,
+            &data,
+        )
+        .await
+        {
+            Ok(gas_limit) => gas_limit,
+            Err(e) => {
+                log!(INFO, "Deposit gas estimation failed, using default: {}", e);
+                ETH_CKERC20_DEPOSIT_GAS as u128
+            }
+        };
+
+        let tx = EvmTxRequest {
+            to: Some(helper_address),
+            value: U256::from_big_endian(&nat_to_be_bytes32(&value)?),
+            gas_limit,
+            data,
+            ..eip1559_tx(&ctx)
+        };
+
+        match self.sign_and_send_eth_tx(&ctx, wallet_address, tx).await {
+            Ok(tx_hash) => {
+                tx_hashes.push(tx_hash);
+                Ok(tx_hashes)
+            }
+            Err(e) => {
+                release_nonce_if_unused(&ctx, wallet_address);
+                match tx_hashes.first() {
+                    Some(approve_hash) => Err(format!(
+                        "Deposit failed after approval {} was sent: {}",
+                        approve_hash, e
+                    )),
+                    None => Err(e),
+                }
+            }
+        }
+    }
+
+    /// Reads the current allowance of `spender` over the ERC-20 balance of `owner`.
+    pub async fn query_erc20_allowance(
+        &self,
+        token_name: &str,
+        owner: &str,
+        spender: &str,
+    ) -> Result<Nat, String> {
+        let token = self.erc20_token_config(token_name)?;
+        let token_address = token
+            .contract_address
+            .as_deref()
+            .ok_or("ERC20 contract address is missing")?;
+
+        let owner = H160::from_str(owner).map_err(|_| "Invalid owner address".to_string())?;
+        let spender = H160::from_str(spender).map_err(|_| "Invalid spender address".to_string())?;
+
+        let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
+        let resp = evm_providers::request(&self.0, |rpc_service| {
+            evm_service.get_erc20_allowance(
+                rpc_service,
+                token_address.to_string(),
+                owner,
+                spender,
+                "latest",
+            )
+        })
+        .await
+        .map_err(|e| format!("Error querying allowance: {}", e))?;
+        parse_hex_nat(&extract_json_result(&resp)?)
+    }
+
+    /// Reads `symbol()` and `decimals()` of the ERC-20 contract at `contract_address`.
+    pub async fn read_erc20_metadata(
+        &self,
+        contract_address: &str,
+    ) -> Result<(String, u8), String> {
+        let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
+
+        let mut values = vec![];
+        for function_name in ["symbol", "decimals"] {
+            let resp = evm_providers::request(&self.0, |rpc_service| {
+                evm_service.get_erc20_metadata(
+                    rpc_service,
+                    contract_address.to_string(),
+                    function_name,
+                )
+            })
             .await
-            .map_err(|e| format!("sign tx error: {}", e))?;
+            .map_err(|e| format!("Error reading {}(): {}", function_name, e))?;
+            let data = hex::decode(extract_json_result(&resp)?.trim_start_matches("0x"))
+                .map_err(|e| format!("Invalid {}() return data: {:?}", function_name, e))?;
+            values.push(data);
+        }
+
+        let symbol = decode_erc20_symbol(&values[0])
+            .ok_or_else(|| format!("{} does not implement symbol()", contract_address))?;
+        let decimals = decode_erc20_decimals(&values[1])
+            .ok_or_else(|| format!("{} does not implement decimals()", contract_address))?;
+        Ok((symbol, decimals))
+    }
+
+    /// Checks an ERC-20 token config against the contract it points to, so a typo in the
+    /// address or decimals cannot misprice transfers.
+    pub async fn validate_erc20_token(&self, token: &TokenConfig) -> Result<(), String> {
+        let contract_address = token
+            .contract_address
+            .as_deref()
+            .ok_or("ERC20 contract address is missing")?;
+        H160::from_str(contract_address)
+            .map_err(|_| format!("Invalid ERC20 contract address {}", contract_address))?;
+
+        let (symbol, decimals) = self.read_erc20_metadata(contract_address).await?;
+        if !symbol.eq_ignore_ascii_case(&token.symbol) {
+            return Err(format!(
+                "Contract {} has symbol {}, expected {}",
+                contract_address, symbol, token.symbol
+            ));
+        }
+        if decimals != token.decimal {
+            return Err(format!(
+                "Contract {} has {} decimals, expected {}",
+                contract_address, decimals, token.decimal
+            ));
+        }
+        Ok(())
+    }
+
+    /// Encodes a call to a function of an arbitrary contract, simulates it with `eth_call`
+    /// and, when it does not revert, signs and broadcasts it. Returns the transaction hash.
+    pub async fn call_contract(&self, arg: &EvmContractCallArg) -> Result<String, String> {
+        if !self.0.chain_type.is_evm() {
+            return Err("Contract calls are only supported on EVM chains".to_string());
+        }
+
+        let abi = try_parse_abi(&arg.abi_json)?;
+        let function = find_function(&abi, &arg.function_name)?;
+        let data = encode_function_call(function, &arg.args)?;
+        let value = arg.value.clone().unwrap_or_default();
+        let to = Address::from_str(&arg.contract_address)
+            .map_err(|e| format!("Invalid contract address: {:?}", e))?;
+
+        self.simulate_contract_call(&arg.wallet_address, &arg.contract_address, &value, &data)
+            .await?;
+
+        let ctx = self
+            .prepare_eth_tx_context(&arg.wallet_address, Some(arg.proposal_id))
+            .await?;
+
+        let result = async {
+            let gas_limit = match arg.gas_limit {
+                Some(gas_limit) => gas_limit as u128,
+                None => {
+                    estimate_gas_limit(
+                        &self.0,
+                        &arg.wallet_address,
+                        Some(arg.contract_address.as_str()),
+                        &value,
+                        &data,
+                    )
+                    .await?
+                }
+            };
 
-        let signed_tx_hash = hex::encode(signed_tx.raw_transaction.0);
-        self.send_raw_tx_ethereum(signed_tx_hash).await
+            let tx = EvmTxRequest {
+                to: Some(to),
+                value: U256::from_big_endian(&nat_to_be_bytes32(&value)?),
+                gas_limit,
+                data,
+                ..eip1559_tx(&ctx)
+            };
+
+            self.sign_and_send_eth_tx(&ctx, &arg.wallet_address, tx)
+                .await
+        }
+        .await;
+
+        if result.is_err() {
+            release_nonce_if_unused(&ctx, &arg.wallet_address);
+        }
+
+        result
     }
 
-    async fn send_raw_tx_ethereum(&self, signed_tx_hash: String) -> Result<String, String> {
+    /// Runs a call through `eth_call` and returns its raw return data, or the revert reason.
+    pub async fn simulate_contract_call(
+        &self,
+        from: &str,
+        to: &str,
+        value: &Nat,
+        data: &[u8],
+    ) -> Result<String, String> {
         let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
-        let env = state::get_env();
-        let rpc_service = env.get_rpc_service();
-        let rpc_services = rpc_service.to_rpc_services();
+        let resp = evm_providers::request(&self.0, |rpc_service| {
+            evm_service.simulate_call(rpc_service, from, to, value, data)
+        })
+        .await
+        .map_err(|e| format!("Simulation failed: {}", e))?;
+        parse_call_response(&resp).map_err(|e| format!("Simulation failed: {}", e))
+    }
 
-        match evm_service
-            .eth_send_raw_transaction(&rpc_services, None, signed_tx_hash)
+    /// Previews the contract call of `arg` without signing it.
+    pub async fn preview_contract_call(
+        &self,
+        arg: &EvmContractCallArg,
+    ) -> Result<EvmTxPreview, String> {
+        let abi = try_parse_abi(&arg.abi_json)?;
+        let function = find_function(&abi, &arg.function_name)?;
+        let data = encode_function_call(function, &arg.args)?;
+        let value = arg.value.clone().unwrap_or_default();
+
+        self.preview_transaction(
+            &arg.wallet_address,
+            &arg.contract_address,
+            &value,
+            &data,
+            Some(function),
+        )
+        .await
+    }
+
+    /// Previews a native or ERC-20 transfer from `wallet_address` without signing it.
+    pub async fn preview_token_transfer(
+        &self,
+        token_name: &str,
+        wallet_address: &str,
+        recipient: &str,
+        amount: &Nat,
+    ) -> Result<EvmTxPreview, String> {
+        let token = self.0.get_token_config(token_name)?;
+        match token.standard {
+            TokenStandard::Native => {
+                self.preview_transaction(wallet_address, recipient, amount, &[], None)
+                    .await
+            }
+            TokenStandard::ERC20 => {
+                let contract_address = token
+                    .contract_address
+                    .as_deref()
+                    .ok_or("ERC20 contract address is missing")?;
+                let data = generate_erc20_transfer_data(recipient, amount)?;
+                self.preview_transaction(
+                    wallet_address,
+                    contract_address,
+                    &Nat::from(0u64),
+                    &data,
+                    None,
+                )
+                .await
+            }
+            _ => Err("Token standard not supported on Ethereum".to_string()),
+        }
+    }
+
+    /// Decodes a transaction for voters, simulates it at the latest block and estimates its gas.
+    /// A reverting transaction still yields a preview, carrying the revert reason.
+    pub async fn preview_transaction(
+        &self,
+        from: &str,
+        to: &str,
+        value: &Nat,
+        data: &[u8],
+        function: Option<&Function>,
+    ) -> Result<EvmTxPreview, String> {
+        if !self.0.chain_type.is_evm() {
+            return Err("Transaction previews are only supported on EVM chains".to_string());
+        }
+
+        let erc20_abi = parse_abi(ERC20_WRITE_ABI_JSON);
+        let function = function.or_else(|| {
+            erc20_abi
+                .functions()
+                .find(|f| data.len() >= 4 && f.short_signature() == data[..4])
+        });
+        let description = self.describe_transaction(to, value, data, function);
+
+        let (success, return_data, revert_reason) =
+            match self.simulate_contract_call(from, to, value, data).await {
+                Ok(return_data) => (true, Some(return_data), None),
+                Err(reason) => (false, None, Some(reason)),
+            };
+        let gas_estimate = match success {
+            true => estimate_gas_limit(&self.0, from, Some(to), value, data)
+                .await
+                .ok(),
+            false => None,
+        };
+
+        Ok(EvmTxPreview {
+            chain_type: self.0.chain_type.clone(),
+            from: from.to_string(),
+            to: to.to_string(),
+            value: value.clone(),
+            data: format!("0x{}", hex::encode(data)),
+            function: function.map(|f| f.abi_signature()),
+            description,
+            success,
+            return_data,
+            revert_reason,
+            gas_estimate,
+            simulated_at: current_time_secs(),
+        })
+    }
+
+    /// Describes a transaction in words, e.g. "transfer 500 USDT to 0x…", using the decimals
+    /// and symbols of the chain's supported tokens.
+    fn describe_transaction(
+        &self,
+        to: &str,
+        value: &Nat,
+        data: &[u8],
+        function: Option<&Function>,
+    ) -> String {
+        let native_value = match self.0.native_token() {
+            Some(t) => format!("{} {}", format_token_amount(value, t.decimal), t.symbol),
+            None => format!("{} wei", value),
+        };
+
+        if data.is_empty() {
+            return format!("send {} to {}", native_value, to);
+        }
+
+        let token = self.0.supported_tokens.iter().find(|t| {
+            matches!(t.standard, TokenStandard::ERC20)
+                && t.contract_address
+                    .as_deref()
+                    .is_some_and(|address| address.eq_ignore_ascii_case(to))
+        });
+        let call = match (function, token) {
+            (Some(f), Some(token)) => describe_erc20_call(f, data, token),
+            _ => None,
+        };
+        let call = call.unwrap_or_else(|| {
+            let decoded = function
+                .map(|f| describe_function_call(f, data))
+                .unwrap_or_else(|| Err("unknown function".to_string()));
+            match decoded {
+                Ok(decoded) => format!("call {} on {}", decoded, to),
+                Err(_) => format!(
+                    "call 0x{} on {}",
+                    hex::encode(&data[..data.len().min(4)]),
+                    to
+                ),
+            }
+        });
+
+        match value == &Nat::from(0u64) {
+            true => call,
+            false => format!("{}, sending {}", call, native_value),
+        }
+    }
+
+    fn erc20_token_config(&self, token_name: &str) -> Result<&TokenConfig, String> {
+        if !self.0.chain_type.is_evm() {
+            return Err("ERC20 tokens are only supported on EVM chains".to_string());
+        }
+
+        let token = self.0.get_token_config(token_name)?;
+        match token.standard {
+            TokenStandard::ERC20 => Ok(token),
+            _ => Err(format!("Token {} is not an ERC20 token", token_name)),
+        }
+    }
+
+    fn evm_chain_id(&self) -> Result<u64, String> {
+        self.0
+            .chain_id()
+            .ok_or_else(|| format!("No chain id configured for {:?}", self.0.chain_type))
+    }
+
+    /// Deploys an ERC-20 token minting `supply` to `owner`, from `bytecode` when given, otherwise
+    /// through `factory` or the default token factory. Returns the transaction hash.
+    pub async fn deploy_erc20_token(
+        &self,
+        owner: &str,
+        name: String,
+        symbol: String,
+        supply: &Nat,
+        factory: Option<&str>,
+        bytecode: Option<&str>,
+        proposal_id: Option<u64>,
+    ) -> Result<String, String> {
+        if !self.0.chain_type.is_evm() {
+            return Err("Token deployments are only supported on EVM chains".to_string());
+        }
+
+        let (to, data) = match bytecode {
+            Some(bytecode) => (
+                None,
+                generate_token_deployment(bytecode, name, symbol, supply, owner.to_string())?,
+            ),
+            None => (
+                Some(factory.unwrap_or(ETH_CREATE_TOKEN_CONTRACT)),
+                generate_create_token(name, symbol, supply, owner.to_string())?,
+            ),
+        };
+        let to_address = to
+            .map(Address::from_str)
+            .transpose()
+            .map_err(|e| format!("Invalid factory address: {:?}", e))?;
+
+        let ctx = self.prepare_eth_tx_context(owner, proposal_id).await?;
+
+        let result = async {
+            let gas_limit = estimate_gas_limit(&self.0, owner, to, &Nat::from(0u64), &data).await?;
+
+            let tx = EvmTxRequest {
+                to: to_address,
+                value: U256::zero(),
+                gas_limit,
+                data,
+                ..eip1559_tx(&ctx)
+            };
+
+            self.sign_and_send_eth_tx(&ctx, owner, tx).await
+        }
+        .await;
+
+        if result.is_err() {
+            release_nonce_if_unused(&ctx, owner);
+        }
+
+        result
+    }
+
+    /// Signs `tx` with the canister's ECDSA key, records it in the transaction log and broadcasts it.
+    pub async fn sign_and_send_eth_tx(
+        &self,
+        ctx: &EvmTxContext,
+        from: &str,
+        tx: EvmTxRequest,
+    ) -> Result<String, String> {
+        let to = tx.to.map(|address| format!("{:#x}", address));
+        let value = tx
+            .value
+            .to_string()
+            .parse::<Nat>()
+            .map_err(|e| format!("Invalid transaction value: {:?}", e))?;
+        let data = (!tx.data.is_empty()).then(|| format!("0x{}", hex::encode(&tx.data)));
+        let gas_limit = tx.gas_limit;
+
+        let raw_tx = tx
+            .sign()
             .await
-        {
-            Ok((multi_rpc_result,)) => match multi_rpc_result {
+            .map_err(|e| format!("sign tx error: {}", e))?;
+        let now = current_time_secs();
+        let evm_tx = EvmTransaction {
+            id: 0,
+            chain_type: ctx.chain_type.clone(),
+            chain_id: ctx.chain_id,
+            from: from.to_string(),
+            to,
+            nonce: ctx.nonce as u64,
+            value,
+            raw_tx: format!("0x{}", hex::encode(&raw_tx)),
+            tx_hash: format!("0x{}", hex::encode(signing::keccak256(&raw_tx))),
+            proposal_id: ctx.proposal_id,
+            status: EvmTxStatus::Pending,
+            created_at: now,
+            updated_at: now,
+            block_number: None,
+            gas_used: None,
+            effective_gas_price: None,
+            data,
+            gas_limit: Some(gas_limit),
+            max_fee_per_gas: Some(ctx.fees.max_fee_per_gas),
+            max_priority_fee_per_gas: Some(ctx.fees.max_priority_fee_per_gas),
+            replaced_by: None,
+        };
+
+        self.send_raw_tx_ethereum(evm_tx).await
+    }
+
+    /// Broadcasts a signed transaction and keeps it in the transaction log until its receipt is known.
+    /// Returns the transaction hash, which identifies the transfer in the log.
+    async fn send_raw_tx_ethereum(&self, evm_tx: EvmTransaction) -> Result<String, String> {
+        let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
+
+        let raw_tx_hex = evm_tx.raw_tx.trim_start_matches("0x").to_string();
+        let tx_hash = evm_tx.tx_hash.clone();
+        let tx_id = store::transactions::add_transaction(evm_tx);
+
+        let result = evm_providers::call_multi(&self.0, |rpc_services, rpc_config| async move {
+            evm_service
+                .eth_send_raw_transaction(&rpc_services, rpc_config, raw_tx_hex)
+                .await
+        })
+        .await;
+        let failure = match result {
+            Ok(multi_rpc_result) => match multi_rpc_result {
                 MultiRpcResult::Consistent(Ok(status)) => match status {
-                    SendRawTransactionStatus::Ok(Some(tx_hash)) => Ok(tx_hash.to_string()),
-                    SendRawTransactionStatus::Ok(None) => {
-                        Ok("Transaction sent, but hash is unavailable.".to_string())
+                    SendRawTransactionStatus::Ok(returned_hash) => {
+                        if let Some(returned_hash) = returned_hash {
+                            if !returned_hash.to_string().eq_ignore_ascii_case(&tx_hash) {
+                                log!(
+                                    INFO,
+                                    "RPC returned hash {} for transaction {} ({})",
+                                    returned_hash,
+                                    tx_id,
+                                    tx_hash
+                                );
+                            }
+                        }
+                        return Ok(tx_hash);
                     }
-                    _ => Err("Transaction failed".to_string()),
+                    status => format!("Transaction rejected: {:?}", status),
                 },
                 MultiRpcResult::Consistent(Err(rpc_error)) => {
                     ic_cdk::println!("Transaction failed with error: {:?}", rpc_error);
-                    Err(format!("Transaction failed with error: {:?}", rpc_error))
+                    format!("Transaction failed with error: {:?}", rpc_error)
                 }
-                MultiRpcResult::Inconsistent(_) => {
-                    ic_cdk::println!(
-                        "Transaction failed due to inconsistent results across services."
+                MultiRpcResult::Inconsistent(results) => {
+                    // A transaction accepted by one node propagates to the others, so a single
+                    // acceptance is a broadcast. Otherwise leave it pending and let the receipt
+                    // poller decide its final status.
+                    let accepted = results
+                        .iter()
+                        .any(|(_, result)| matches!(result, Ok(SendRawTransactionStatus::Ok(_))));
+                    log!(
+                        INFO,
+                        "Providers disagree on transaction {} ({}): {:?}",
+                        tx_id,
+                        tx_hash,
+                        results
                     );
-                    Err(
-                        "Transaction failed due to inconsistent results across services."
-                            .to_string(),
-                    )
+                    if accepted {
+                        return Ok(tx_hash);
+                    }
+                    return Err(format!(
+                        "Inconsistent results across services, transaction {} is being tracked",
+                        tx_hash
+                    ));
                 }
             },
             Err(e) => {
-                ic_cdk::println!("Error sending Ethereum transaction: {:?}", e);
-                Err(format!("Transaction failed: {:?}", e))
+                ic_cdk::println!("Error sending Ethereum transaction: {}", e);
+                format!("Transaction failed: {}", e)
             }
-        }
+        };
+
+        store::transactions::set_status(tx_id, EvmTxStatus::Failed(failure.clone()))?;
+        Err(failure)
     }
 
-    pub async fn resolve_account_nonce(
+    /// Fetches the confirmed transaction count of `wallet_address` and reserves the next free nonce.
+    pub async fn reserve_account_nonce(
         &self,
-        chain_type: &ChainType,
+        chain_id: u64,
         wallet_address: &str,
     ) -> Result<u128, String> {
-        let local_nonce = store::state::get_nonce(chain_type).unwrap_or(0) as u128;
+        let onchain_nonce = self.fetch_onchain_nonce(wallet_address).await?;
+        let nonce = store::nonces::reserve_nonce(chain_id, wallet_address, onchain_nonce as u64);
+        Ok(nonce as u128)
+    }
 
+    pub async fn fetch_onchain_nonce(&self, wallet_address: &str) -> Result<u128, String> {
         let evm_service = EvmService::new(EVM_RPC_CANISTER_ID)?;
-        let env = state::get_env();
-        let rpc_service = env.get_rpc_service();
+        let resp = evm_providers::request(&self.0, |rpc_service| {
+            evm_service.get_account_nonce(rpc_service, wallet_address)
+        })
+        .await
+        .map_err(|e| format!("Error fetching nonce: {}", e))?;
+        parse_nonce_hex(&extract_json_result(&resp)?)
+    }
 
-        match evm_service
-            .get_account_nonce(rpc_service, wallet_address)
-            .await
-        {
-            Ok((Ok(resp),)) => {
-                let result_hex = extract_json_result(&resp)?;
-                let onchain_nonce = parse_nonce_hex(&result_hex)?;
-
-                if onchain_nonce != local_nonce {
-                    store::state::set_nonce(chain_type.clone(), Some(onchain_nonce as u64))
-                        .map_err(|e| format!("Failed to update nonce: {}", e))?;
-                    Ok(onchain_nonce)
-                } else {
-                    Ok(local_nonce)
+    /// Compares the local nonce allocation of `address` with the chain and reports nonces that
+    /// were handed out but have no transaction in flight. Any such gap blocks all later nonces.
+    pub async fn nonce_report(&self, address: &str) -> Result<EvmNonceReport, String> {
+        let chain_id = self.evm_chain_id()?;
+        let onchain_nonce = self.fetch_onchain_nonce(address).await? as u64;
+        let nonce_state = store::nonces::get_nonce_state(chain_id, address);
+        let in_flight: Vec<u64> = store::transactions::list_in_flight_nonces(chain_id, address)
+            .into_iter()
+            .filter(|nonce| *nonce >= onchain_nonce)
+            .collect();
+
+        let gaps = (onchain_nonce..nonce_state.next_nonce)
+            .filter(|nonce| !in_flight.contains(nonce))
+            .collect();
+
+        Ok(EvmNonceReport {
+            chain_type: self.0.chain_type.clone(),
+            address: address.to_string(),
+            onchain_nonce,
+            next_nonce: nonce_state.next_nonce.max(onchain_nonce),
+            in_flight,
+            gaps,
+        })
+    }
+
+    /// Re-sends a pending transaction with the same nonce and bumped fees, so it replaces the
+    /// original in the mempool. `Cancel` replaces it with a zero-value transfer to itself.
+    pub async fn replace_transaction(
+        &self,
+        tx_id: u64,
+        kind: TxReplacement,
+    ) -> Result<String, String> {
+        let original = store::transactions::get_transaction(tx_id)
+            .ok_or_else(|| format!("Transaction {} not found", tx_id))?;
+
+        if !matches!(original.status, EvmTxStatus::Pending) {
+            return Err(format!(
+                "Only pending transactions can be replaced, transaction {} is {:?}",
+                tx_id, original.status
+            ));
+        }
+
+        let (Some(gas_limit), Some(max_fee), Some(max_priority_fee)) = (
+            original.gas_limit,
+            original.max_fee_per_gas,
+            original.max_priority_fee_per_gas,
+        ) else {
+            return Err(format!(
+                "Transaction {} was recorded without its fee parameters and cannot be replaced",
+                tx_id
+            ));
+        };
+
+        let fee_config = self.0.get_fee_config();
+        let current_fees = resolve_eip1559_fees(&self.0).await?;
+        let fees = bump_replacement_fees(
+            Eip1559Fees {
+                max_fee_per_gas: max_fee,
+                max_priority_fee_per_gas: max_priority_fee,
+            },
+            current_fees,
+            &fee_config,
+        )?;
+
+        let ctx = EvmTxContext {
+            chain_type: original.chain_type.clone(),
+            chain_id: original.chain_id,
+            nonce: original.nonce as u128,
+            fee_config,
+            fees,
+            proposal_id: original.proposal_id,
+        };
+
+        let tx = match kind {
+            TxReplacement::SpeedUp => {
+                let to = match &original.to {
+                    Some(to) => Some(
+                        Address::from_str(to)
+                            .map_err(|e| format!("Invalid recipient address: {:?}", e))?,
+                    ),
+                    None => None,
+                };
+                let data = match &original.data {
+                    Some(data) => hex::decode(data.trim_start_matches("0x"))
+                        .map_err(|e| format!("Invalid transaction data: {:?}", e))?,
+                    None => vec![],
+                };
+
+                EvmTxRequest {
+                    to,
+                    value: U256::from_dec_str(&original.value.0.to_string())
+                        .map_err(|e| format!("Invalid transaction value: {:?}", e))?,
+                    gas_limit,
+                    data,
+                    ..eip1559_tx(&ctx)
                 }
             }
-            Ok((Err(rpc_err),)) => Err(format!("RPC error fetching nonce: {:?}", rpc_err)),
-            Err((code, msg)) => Err(format!("Nonce fetch rejected: {:?}, {:?}", code, msg)),
+            TxReplacement::Cancel => EvmTxRequest {
+                to: Some(
+                    Address::from_str(&original.from)
+                        .map_err(|e| format!("Invalid sender address: {:?}", e))?,
+                ),
+                value: U256::zero(),
+                gas_limit: ETH_TRANSFER_GAS as u128,
+                ..eip1559_tx(&ctx)
+            },
+        };
+
+        let tx_hash = self.sign_and_send_eth_tx(&ctx, &original.from, tx).await?;
+
+        if let Some(replacement) = store::transactions::find_transaction_by_hash(&tx_hash) {
+            store::transactions::update_transaction(tx_id, |stored| {
+                stored.status = EvmTxStatus::Replaced;
+                stored.replaced_by = Some(replacement.id);
+            })?;
         }
+
+        Ok(tx_hash)
+    }
+
+    /// Fills every nonce gap of `address` with a zero-value self-transfer so later
+    /// transactions can be mined.
+    pub async fn fill_nonce_gaps(&self, address: &str) -> Result<Vec<String>, String> {
+        let report = self.nonce_report(address).await?;
+        if report.gaps.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let chain_id = self.evm_chain_id()?;
+        let fee_config = self.0.get_fee_config();
+        let fees = resolve_eip1559_fees(&self.0).await?;
+        let self_address =
+            Address::from_str(address).map_err(|e| format!("Invalid sender address: {:?}", e))?;
+
+        fill_gaps(chain_id, address, report.gaps, |nonce| {
+            let ctx = EvmTxContext {
+                chain_type: self.0.chain_type.clone(),
+                chain_id,
+                nonce: nonce as u128,
+                fee_config: fee_config.clone(),
+                fees,
+                proposal_id: None,
+            };
+
+            let tx = EvmTxRequest {
+                to: Some(self_address),
+                value: U256::zero(),
+                gas_limit: ETH_TRANSFER_GAS as u128,
+                ..eip1559_tx(&ctx)
+            };
+
+            async move { self.sign_and_send_eth_tx(&ctx, address, tx).await }
+        })
+        .await
+    }
+}
+
+/// Claims each gap nonce in turn and fills it with `send`. Stops at the first failure and
+/// releases that nonce again, so the gap is not lost and the next transfer reuses it.
+async fn fill_gaps<F, Fut>(
+    chain_id: u64,
+    address: &str,
+    gaps: Vec<u64>,
+    mut send: F,
+) -> Result<Vec<String>, String>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<String, String>>,
+{
+    let mut tx_hashes = vec![];
+    for nonce in gaps {
+        store::nonces::claim_released_nonce(chain_id, address, nonce);
+        match send(nonce).await {
+            Ok(tx_hash) => tx_hashes.push(tx_hash),
+            Err(e) => {
+                release_unused_nonce(chain_id, address, nonce);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(tx_hashes)
+}
+
+/// How a pending transaction is replaced by `replace_transaction`.
+pub enum TxReplacement {
+    SpeedUp,
+    Cancel,
+}
+
+/// Releases the nonce reserved in `ctx` unless a transaction using it is still being tracked,
+/// so a failed signing or broadcast does not leave a gap.
+fn release_nonce_if_unused(ctx: &EvmTxContext, wallet_address: &str) {
+    release_unused_nonce(ctx.chain_id, wallet_address, ctx.nonce as u64);
+}
+
+fn release_unused_nonce(chain_id: u64, wallet_address: &str, nonce: u64) {
+    let in_flight =
+        !store::transactions::list_open_transactions_by_nonce(chain_id, wallet_address, nonce)
+            .is_empty();
+
+    if !in_flight {
+        store::nonces::release_nonce(chain_id, wallet_address, nonce);
+    }
+}
+
+/// Fees for a transaction replacing one signed with `previous`. Nodes only accept a replacement
+/// whose fees are bumped by at least `EVM_TX_REPLACEMENT_FEE_BUMP_PERCENT`; the result also never
+/// undercuts the current estimate and stays within the chain's caps.
+fn bump_replacement_fees(
+    previous: Eip1559Fees,
+    current: Eip1559Fees,
+    fee_config: &FeeConfig,
+) -> Result<Eip1559Fees, String> {
+    let bump = |fee: u128| {
+        fee.saturating_mul(100 + EVM_TX_REPLACEMENT_FEE_BUMP_PERCENT as u128)
+            .div_ceil(100)
+    };
+
+    let max_priority_fee_per_gas =
+        bump(previous.max_priority_fee_per_gas).max(current.max_priority_fee_per_gas);
+    let max_fee_per_gas = bump(previous.max_fee_per_gas)
+        .max(current.max_fee_per_gas)
+        .max(max_priority_fee_per_gas);
+
+    if max_priority_fee_per_gas > fee_config.max_priority_fee_per_gas_cap as u128
+        || max_fee_per_gas > fee_config.max_fee_per_gas_cap as u128
+    {
+        return Err(format!(
+            "Replacement fees (max fee {} wei, tip {} wei) exceed the chain's fee caps",
+            max_fee_per_gas, max_priority_fee_per_gas
+        ));
+    }
+
+    Ok(Eip1559Fees {
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+    })
+}
+
+/// Describes ERC-20 `transfer`, `approve` and `transferFrom` calls in token units.
+fn describe_erc20_call(function: &Function, data: &[u8], token: &TokenConfig) -> Option<String> {
+    let tokens = function.decode_input(data.get(4..)?).ok()?;
+    let amount = |token_amount: &Token| match token_amount {
+        Token::Uint(value) => Some(format!(
+            "{} {}",
+            format_token_amount(&u256_to_nat(*value), token.decimal),
+            token.symbol
+        )),
+        _ => None,
+    };
+
+    match (function.name.as_str(), &tokens[..]) {
+        ("transfer", [to, value]) => Some(format!(
+            "transfer {} to {}",
+            amount(value)?,
+            format_token(to)
+        )),
+        ("approve", [spender, value]) => Some(format!(
+            "approve {} to spend {}",
+            format_token(spender),
+            amount(value)?
+        )),
+        ("transferFrom", [from, to, value]) => Some(format!(
+            "transfer {} from {} to {}",
+            amount(value)?,
+            format_token(from),
+            format_token(to)
+        )),
+        _ => None,
+    }
+}
+
+/// A type 2 transaction with the chain id, nonce and fees of `ctx`.
+fn eip1559_tx(ctx: &EvmTxContext) -> EvmTxRequest {
+    EvmTxRequest {
+        chain_id: ctx.chain_id,
+        nonce: ctx.nonce as u64,
+        gas_limit: 0,
+        to: None,
+        value: U256::zero(),
+        data: vec![],
+        fees: EvmTxFees::Eip1559 {
+            max_fee_per_gas: ctx.fees.max_fee_per_gas,
+            max_priority_fee_per_gas: ctx.fees.max_priority_fee_per_gas,
+            access_list: vec![],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EvmNonceState;
+    use std::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is not ready"),
+        }
+    }
+
+    #[test]
+    fn failed_gap_fill_releases_its_nonce() {
+        let address = "0x00000000000000000000000000000000000000aa";
+        store::state::with_mut(|s| {
+            s.evm_nonces.insert(
+                format!("1:{}", address),
+                EvmNonceState {
+                    next_nonce: 6,
+                    released: vec![2, 3, 4],
+                },
+            );
+        });
+
+        let result = block_on(fill_gaps(1, address, vec![2, 3, 4], |nonce| async move {
+            match nonce {
+                3 => Err("broadcast failed".to_string()),
+                _ => Ok(format!("0x{}", nonce)),
+            }
+        }));
+
+        assert_eq!(result, Err("broadcast failed".to_string()));
+        let nonce_state = store::nonces::get_nonce_state(1, address);
+        assert_eq!(nonce_state.released, vec![3, 4]);
+        assert_eq!(store::nonces::reserve_nonce(1, address, 2), 3);
     }
 }
@@ -0,0 +1,180 @@
+//! A stand-in for the ckETH minter used to exercise the DAO's bridge locally.
+//!
+//! Withdrawals are recorded and queued without burning anything; a controller moves them
+//! through the minter's states with `complete_withdrawal` and `reimburse_withdrawal`.
+//! Deposits are reported as credited once `set_scraped_block` passes their block.
+
+use candid::Nat;
+use ic_cdk_macros::*;
+use ic_govmind_types::cketh::{
+    EthTransaction, MinterInfo, RetrieveErc20Request, RetrieveEthRequest, RetrieveEthStatus,
+    TxFinalizedStatus, WithdrawErc20Arg, WithdrawErc20Error, WithdrawalArg, WithdrawalError,
+};
+use std::{cell::RefCell, collections::BTreeMap};
+
+#[derive(Default)]
+struct MinterState {
+    next_block_index: u64,
+    withdrawals: BTreeMap<u64, RetrieveEthStatus>,
+    minter_address: Option<String>,
+    helper_contract_address: Option<String>,
+    last_scraped_block_number: Option<Nat>,
+}
+
+thread_local! {
+    static STATE: RefCell<MinterState> = RefCell::new(MinterState::default());
+}
+
+fn is_controller() -> Result<(), String> {
+    if ic_cdk::api::is_controller(&ic_cdk::api::msg_caller()) {
+        Ok(())
+    } else {
+        Err("Caller is not a controller".to_string())
+    }
+}
+
+fn validate_recipient(recipient: &str) -> bool {
+    recipient.len() == 42
+        && recipient.starts_with("0x")
+        && recipient[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn next_block_index() -> u64 {
+    STATE.with(|s| {
+        let mut s = s.borrow_mut();
+        let block_index = s.next_block_index;
+        s.next_block_index += 1;
+        block_index
+    })
+}
+
+fn queue_withdrawal() -> u64 {
+    let block_index = next_block_index();
+    STATE.with(|s| {
+        s.borrow_mut()
+            .withdrawals
+            .insert(block_index, RetrieveEthStatus::Pending)
+    });
+    block_index
+}
+
+#[update]
+fn withdraw_eth(arg: WithdrawalArg) -> Result<RetrieveEthRequest, WithdrawalError> {
+    if !validate_recipient(&arg.recipient) {
+        return Err(WithdrawalError::RecipientAddressBlocked {
+            address: arg.recipient,
+        });
+    }
+    if arg.amount == 0u64 {
+        return Err(WithdrawalError::AmountTooLow {
+            min_withdrawal_amount: Nat::from(1u64),
+        });
+    }
+
+    Ok(RetrieveEthRequest {
+        block_index: Nat::from(queue_withdrawal()),
+    })
+}
+
+#[update]
+fn withdraw_erc20(arg: WithdrawErc20Arg) -> Result<RetrieveErc20Request, WithdrawErc20Error> {
+    if !validate_recipient(&arg.recipient) {
+        return Err(WithdrawErc20Error::RecipientAddressBlocked {
+            address: arg.recipient,
+        });
+    }
+
+    // The real minter burns ckETH and the ckERC20 token in two separate ledger blocks.
+    let cketh_block_index = queue_withdrawal();
+    Ok(RetrieveErc20Request {
+        cketh_block_index: Nat::from(cketh_block_index),
+        ckerc20_block_index: Nat::from(cketh_block_index),
+    })
+}
+
+#[query]
+fn retrieve_eth_status(block_index: u64) -> RetrieveEthStatus {
+    STATE.with(|s| {
+        s.borrow()
+            .withdrawals
+            .get(&block_index)
+            .cloned()
+            .unwrap_or(RetrieveEthStatus::NotFound)
+    })
+}
+
+#[query]
+fn get_minter_info() -> MinterInfo {
+    STATE.with(|s| {
+        let s = s.borrow();
+        MinterInfo {
+            minter_address: s.minter_address.clone(),
+            deposit_with_subaccount_helper_contract_address: s.helper_contract_address.clone(),
+            last_deposit_with_subaccount_scraped_block_number: s.last_scraped_block_number.clone(),
+            last_eth_scraped_block_number: s.last_scraped_block_number.clone(),
+            last_erc20_scraped_block_number: s.last_scraped_block_number.clone(),
+        }
+    })
+}
+
+#[update(guard = "is_controller")]
+fn set_minter_addresses(minter_address: Option<String>, helper_contract_address: Option<String>) {
+    STATE.with(|s| {
+        let mut s = s.borrow_mut();
+        s.minter_address = minter_address;
+        s.helper_contract_address = helper_contract_address;
+    })
+}
+
+#[update(guard = "is_controller")]
+fn set_scraped_block(block_number: u64) {
+    STATE.with(|s| s.borrow_mut().last_scraped_block_number = Some(Nat::from(block_number)))
+}
+
+#[update(guard = "is_controller")]
+fn send_withdrawal(block_index: u64, transaction_hash: String) -> Result<(), String> {
+    set_withdrawal_status(
+        block_index,
+        RetrieveEthStatus::TxSent(EthTransaction { transaction_hash }),
+    )
+}
+
+#[update(guard = "is_controller")]
+fn complete_withdrawal(block_index: u64, transaction_hash: String) -> Result<(), String> {
+    set_withdrawal_status(
+        block_index,
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Success {
+            transaction_hash,
+            effective_transaction_fee: None,
+        }),
+    )
+}
+
+#[update(guard = "is_controller")]
+fn reimburse_withdrawal(
+    block_index: u64,
+    transaction_hash: String,
+    reimbursed_amount: Nat,
+) -> Result<(), String> {
+    let reimbursed_in_block = Nat::from(next_block_index());
+    set_withdrawal_status(
+        block_index,
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Reimbursed {
+            transaction_hash,
+            reimbursed_amount,
+            reimbursed_in_block,
+        }),
+    )
+}
+
+fn set_withdrawal_status(block_index: u64, status: RetrieveEthStatus) -> Result<(), String> {
+    STATE.with(|s| match s.borrow_mut().withdrawals.get_mut(&block_index) {
+        Some(current) => {
+            *current = status;
+            Ok(())
+        }
+        None => Err(format!("Withdrawal {} not found", block_index)),
+    })
+}
+
+ic_cdk::export_candid!();
@@ -84,9 +84,9 @@ export default function DistributionTab({
                       <div className="flex justify-between">
                         <span className="text-slate-600">Status:</span>
                         <span className={`font-medium ${
-                          record.tx_result.startsWith('Success') ? 'text-green-600' : 'text-red-600'
+                          'Completed' in record.status ? 'text-green-600' : 'text-red-600'
                         }`}>
-                          {record.tx_result.startsWith('Success') ? 'Success' : 'Failed'}
+                          {'Completed' in record.status ? 'Success' : 'Failed'}
                         </span>
                       </div>
                     </div>
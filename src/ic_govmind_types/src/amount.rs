@@ -0,0 +1,152 @@
+//! Serde helpers for token amounts stored as `Nat`.
+//!
+//! Amounts used to be stored as `u128`, which CBOR encodes as a plain integer (or a bignum tag
+//! above `u64::MAX`). Candid encodes both `u128` and `Nat` as `nat`, so only the CBOR-encoded
+//! state needs to accept the legacy representation. These deserializers accept integers,
+//! decimal strings and the native `Nat` encoding.
+
+use candid::Nat;
+use num_bigint::BigUint;
+use serde::{
+    de::{value::SeqAccessDeserializer, Error, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{collections::HashMap, fmt, str::FromStr};
+
+struct CompatNat(Nat);
+
+impl<'de> Deserialize<'de> for CompatNat {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CompatNatVisitor;
+
+        impl<'de> Visitor<'de> for CompatNatVisitor {
+            type Value = CompatNat;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a non-negative integer")
+            }
+
+            fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(CompatNat(Nat::from(v)))
+            }
+
+            fn visit_u128<E: Error>(self, v: u128) -> Result<Self::Value, E> {
+                Ok(CompatNat(Nat::from(v)))
+            }
+
+            fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+                u64::try_from(v)
+                    .map(|v| CompatNat(Nat::from(v)))
+                    .map_err(|_| E::custom(format!("negative amount: {}", v)))
+            }
+
+            fn visit_i128<E: Error>(self, v: i128) -> Result<Self::Value, E> {
+                u128::try_from(v)
+                    .map(|v| CompatNat(Nat::from(v)))
+                    .map_err(|_| E::custom(format!("negative amount: {}", v)))
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+                Nat::from_str(v)
+                    .map(CompatNat)
+                    .map_err(|e| E::custom(format!("invalid amount {}: {}", v, e)))
+            }
+
+            // Candid hands `nat` values over as a tag byte followed by little-endian digits.
+            fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                match v.split_first() {
+                    Some((1, digits)) => Ok(CompatNat(Nat(BigUint::from_bytes_le(digits)))),
+                    _ => Err(E::custom("not nat")),
+                }
+            }
+
+            // CBOR holds `Nat` as its sequence of u32 digits.
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                Nat::deserialize(SeqAccessDeserializer::new(seq)).map(CompatNat)
+            }
+        }
+
+        deserializer.deserialize_any(CompatNatVisitor)
+    }
+}
+
+pub fn deserialize_nat<'de, D>(deserializer: D) -> Result<Nat, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    CompatNat::deserialize(deserializer).map(|v| v.0)
+}
+
+pub fn deserialize_opt_nat<'de, D>(deserializer: D) -> Result<Option<Nat>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<CompatNat>::deserialize(deserializer).map(|v| v.map(|v| v.0))
+}
+
+pub fn deserialize_nat_map<'de, D>(deserializer: D) -> Result<HashMap<String, Nat>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    HashMap::<String, CompatNat>::deserialize(deserializer)
+        .map(|map| map.into_iter().map(|(k, v)| (k, v.0)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_cbor_bytes;
+    use candid::{CandidType, Decode, Encode};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Legacy {
+        amount: u128,
+        rate: Option<u128>,
+        initial: HashMap<String, u128>,
+    }
+
+    #[derive(CandidType, Serialize, Deserialize, Debug, PartialEq)]
+    struct Current {
+        #[serde(deserialize_with = "deserialize_nat")]
+        amount: Nat,
+        #[serde(deserialize_with = "deserialize_opt_nat")]
+        rate: Option<Nat>,
+        #[serde(deserialize_with = "deserialize_nat_map")]
+        initial: HashMap<String, Nat>,
+    }
+
+    #[test]
+    fn test_legacy_cbor_amounts() {
+        let legacy = Legacy {
+            amount: u128::MAX,
+            rate: Some(42),
+            initial: HashMap::from([("a".to_string(), 7)]),
+        };
+        let data = to_cbor_bytes(&legacy);
+        let current: Current = ciborium::from_reader(&data[..]).unwrap();
+        assert_eq!(current.amount, Nat::from(u128::MAX));
+        assert_eq!(current.rate, Some(Nat::from(42u64)));
+        assert_eq!(current.initial.get("a"), Some(&Nat::from(7u64)));
+
+        let data = to_cbor_bytes(&current);
+        let decoded: Current = ciborium::from_reader(&data[..]).unwrap();
+        assert_eq!(decoded, current);
+    }
+
+    #[test]
+    fn test_candid_amounts() {
+        let amount = Nat::from(u128::MAX) * Nat::from(u128::MAX);
+        let current = Current {
+            amount: amount.clone(),
+            rate: None,
+            initial: HashMap::new(),
+        };
+        let data = Encode!(&current).unwrap();
+        let decoded = Decode!(&data, Current).unwrap();
+        assert_eq!(decoded, current);
+    }
+}
@@ -1,8 +1,18 @@
-use candid::{CandidType, Deserialize};
-use evm_rpc_types::RpcServices;
+use candid::{CandidType, Deserialize, Nat, Principal};
+use evm_rpc_types::{
+    ConsensusStrategy, EthMainnetService, EthSepoliaService, L2MainnetService, RpcApi, RpcService,
+    RpcServices,
+};
 use serde::Serialize;
 
-use crate::dao::ChainType;
+use crate::{
+    constants::{
+        ETH_DEFAULT_FEE_HISTORY_BLOCKS, ETH_DEFAULT_FEE_REWARD_PERCENTILE,
+        ETH_DEFAULT_GAS_LIMIT_MARGIN_PERCENT, ETH_DEFAULT_MAX_FEE_PER_GAS_CAP,
+        ETH_DEFAULT_MAX_PRIORITY_FEE_PER_GAS_CAP, ETH_MIN_PRIORITY_FEE_PER_GAS,
+    },
+    dao::ChainType,
+};
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize, Default)]
 pub enum Network {
@@ -47,6 +57,38 @@ pub struct RpcConfig {
     pub rpc_url: String,
     pub chain_id: Option<u64>,
     pub rpc_services: Option<RpcServices>,
+    #[serde(default)]
+    pub consensus: Option<ConsensusStrategy>, // how multi-provider results are reconciled
+}
+
+/// EIP-1559 fee parameters for an EVM chain. All fee values are in wei per gas.
+#[derive(CandidType, Clone, Deserialize, Debug, Serialize)]
+pub struct FeeConfig {
+    /// Never sign a transaction whose max fee per gas exceeds this cap.
+    pub max_fee_per_gas_cap: u64,
+    /// Upper bound for the tip paid to the block producer.
+    pub max_priority_fee_per_gas_cap: u64,
+    /// Lower bound for the tip, also used when the fee history has no rewards.
+    pub min_priority_fee_per_gas: u64,
+    /// Number of blocks requested from `eth_feeHistory`.
+    pub fee_history_block_count: u64,
+    /// Percentile of the per-block rewards used as the tip.
+    pub reward_percentile: u8,
+    /// Safety margin added on top of `eth_estimateGas`, in percent.
+    pub gas_limit_margin_percent: u64,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        FeeConfig {
+            max_fee_per_gas_cap: ETH_DEFAULT_MAX_FEE_PER_GAS_CAP,
+            max_priority_fee_per_gas_cap: ETH_DEFAULT_MAX_PRIORITY_FEE_PER_GAS_CAP,
+            min_priority_fee_per_gas: ETH_MIN_PRIORITY_FEE_PER_GAS,
+            fee_history_block_count: ETH_DEFAULT_FEE_HISTORY_BLOCKS,
+            reward_percentile: ETH_DEFAULT_FEE_REWARD_PERCENTILE,
+            gas_limit_margin_percent: ETH_DEFAULT_GAS_LIMIT_MARGIN_PERCENT,
+        }
+    }
 }
 
 #[derive(CandidType, Debug, Clone, Deserialize, Default, Serialize)]
@@ -57,10 +99,14 @@ pub struct BlockchainConfig {
     pub gas_price: Option<u64>,
     pub rpc_config: Option<RpcConfig>,
     pub supported_tokens: Vec<TokenConfig>,
+    pub fee_config: Option<FeeConfig>,
 }
 
 impl BlockchainConfig {
-    pub fn new_eth_config(
+    /// Config of an EVM chain. `rpc_services` selects providers of the EVM RPC canister; without
+    /// them, requests go to `rpc_url` as a custom provider.
+    pub fn new_evm_config(
+        chain_type: ChainType,
         rpc_url: String,
         chain_id: Option<u64>,
         rpc_services: Option<RpcServices>,
@@ -68,7 +114,7 @@ impl BlockchainConfig {
         supported_tokens: Vec<TokenConfig>,
     ) -> Self {
         BlockchainConfig {
-            chain_type: ChainType::Ethereum,
+            chain_type,
             signature_type: SignatureType::Secp256k1,
             nonce: None,
             gas_price,
@@ -76,11 +122,30 @@ impl BlockchainConfig {
                 rpc_url,
                 chain_id,
                 rpc_services,
+                consensus: None,
             }),
             supported_tokens,
+            fee_config: Some(FeeConfig::default()),
         }
     }
 
+    pub fn new_eth_config(
+        rpc_url: String,
+        chain_id: Option<u64>,
+        rpc_services: Option<RpcServices>,
+        gas_price: Option<u64>,
+        supported_tokens: Vec<TokenConfig>,
+    ) -> Self {
+        Self::new_evm_config(
+            ChainType::Ethereum,
+            rpc_url,
+            chain_id,
+            rpc_services,
+            gas_price,
+            supported_tokens,
+        )
+    }
+
     pub fn new_eth_sepolia_config(
         rpc_url: String,
         chain_id: Option<u64>,
@@ -88,18 +153,14 @@ impl BlockchainConfig {
         gas_price: Option<u64>,
         supported_tokens: Vec<TokenConfig>,
     ) -> Self {
-        BlockchainConfig {
-            chain_type: ChainType::EthSepolia,
-            signature_type: SignatureType::Secp256k1,
-            nonce: None,
+        Self::new_evm_config(
+            ChainType::EthSepolia,
+            rpc_url,
+            chain_id,
+            rpc_services,
             gas_price,
-            rpc_config: Some(RpcConfig {
-                rpc_url,
-                chain_id,
-                rpc_services,
-            }),
             supported_tokens,
-        }
+        )
     }
 
     pub fn new_eth_local_config(
@@ -109,18 +170,14 @@ impl BlockchainConfig {
         gas_price: Option<u64>,
         supported_tokens: Vec<TokenConfig>,
     ) -> Self {
-        BlockchainConfig {
-            chain_type: ChainType::EthLocal,
-            signature_type: SignatureType::Secp256k1,
-            nonce: None,
+        Self::new_evm_config(
+            ChainType::EthLocal,
+            rpc_url,
+            chain_id,
+            rpc_services,
             gas_price,
-            rpc_config: Some(RpcConfig {
-                rpc_url,
-                chain_id,
-                rpc_services,
-            }),
             supported_tokens,
-        }
+        )
     }
 
     pub fn new_official_config(
@@ -135,9 +192,106 @@ impl BlockchainConfig {
             gas_price: None,
             rpc_config: None,
             supported_tokens,
+            fee_config: None,
         }
     }
 
+    /// Whether this config is for `chain_type`. EVM chains match by chain id, so `Evm(1)`
+    /// resolves to the `Ethereum` config.
+    pub fn is_chain(&self, chain_type: &ChainType) -> bool {
+        &self.chain_type == chain_type
+            || (chain_type.is_evm() && self.chain_id() == chain_type.evm_chain_id())
+    }
+
+    /// EIP-155 chain id, from the RPC config or else implied by the chain type.
+    pub fn chain_id(&self) -> Option<u64> {
+        self.rpc_config
+            .as_ref()
+            .and_then(|rpc| rpc.chain_id)
+            .or_else(|| self.chain_type.evm_chain_id())
+    }
+
+    /// Providers of the EVM RPC canister used for multi-provider calls.
+    pub fn rpc_services(&self) -> Result<RpcServices, String> {
+        let rpc_config = self
+            .rpc_config
+            .as_ref()
+            .ok_or_else(|| format!("No RPC config for {:?}", self.chain_type))?;
+        if let Some(rpc_services) = &rpc_config.rpc_services {
+            return Ok(rpc_services.clone());
+        }
+
+        let chain_id = self
+            .chain_id()
+            .ok_or_else(|| format!("No chain id for {:?}", self.chain_type))?;
+        Ok(RpcServices::Custom {
+            chain_id,
+            services: vec![RpcApi {
+                url: rpc_config.rpc_url.clone(),
+                headers: None,
+            }],
+        })
+    }
+
+    /// How the responses of several providers are reconciled. Equality unless configured.
+    pub fn consensus(&self) -> ConsensusStrategy {
+        self.rpc_config
+            .as_ref()
+            .and_then(|rpc_config| rpc_config.consensus.clone())
+            .unwrap_or_default()
+    }
+
+    /// Every provider of `rpc_services`. A network without an explicit provider list uses all
+    /// the providers the EVM RPC canister knows for it.
+    pub fn rpc_providers(&self) -> Result<Vec<RpcService>, String> {
+        let providers: Vec<RpcService> = match self.rpc_services()? {
+            RpcServices::EthMainnet(services) => services
+                .unwrap_or_else(|| EthMainnetService::all().to_vec())
+                .into_iter()
+                .map(RpcService::EthMainnet)
+                .collect(),
+            RpcServices::EthSepolia(services) => services
+                .unwrap_or_else(|| EthSepoliaService::all().to_vec())
+                .into_iter()
+                .map(RpcService::EthSepolia)
+                .collect(),
+            RpcServices::ArbitrumOne(services) => services
+                .unwrap_or_else(|| L2MainnetService::all().to_vec())
+                .into_iter()
+                .map(RpcService::ArbitrumOne)
+                .collect(),
+            RpcServices::BaseMainnet(services) => services
+                .unwrap_or_else(|| L2MainnetService::all().to_vec())
+                .into_iter()
+                .map(RpcService::BaseMainnet)
+                .collect(),
+            RpcServices::OptimismMainnet(services) => services
+                .unwrap_or_else(|| L2MainnetService::all().to_vec())
+                .into_iter()
+                .map(RpcService::OptimismMainnet)
+                .collect(),
+            RpcServices::Custom { services, .. } => {
+                services.into_iter().map(RpcService::Custom).collect()
+            }
+        };
+
+        match providers.is_empty() {
+            true => Err(format!("No RPC provider for {:?}", self.chain_type)),
+            false => Ok(providers),
+        }
+    }
+
+    /// The chain's native currency, e.g. ETH or BNB.
+    pub fn native_token(&self) -> Option<&TokenConfig> {
+        self.supported_tokens
+            .iter()
+            .find(|t| matches!(t.standard, TokenStandard::Native))
+    }
+
+    pub fn get_fee_config(&self) -> FeeConfig {
+        self.fee_config.clone().unwrap_or_default()
+    }
+
     pub fn get_token_config(&self, token_name: &str) -> Result<&TokenConfig, String> {
         self.supported_tokens
             .iter()
@@ -145,6 +299,15 @@ impl BlockchainConfig {
             .ok_or_else(|| format!("Token {} not supported", token_name))
     }
 
+    /// Whether one of the supported tokens is the contract at `contract_address`.
+    pub fn has_contract(&self, contract_address: &str) -> bool {
+        self.supported_tokens.iter().any(|t| {
+            t.contract_address
+                .as_deref()
+                .is_some_and(|address| address.eq_ignore_ascii_case(contract_address))
+        })
+    }
+
     pub fn add_token_config(&mut self, token_config: TokenConfig) {
         if self
             .supported_tokens
@@ -157,3 +320,192 @@ impl BlockchainConfig {
         self.supported_tokens.push(token_config);
     }
 }
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub enum ChainConfigAction {
+    AddChain,
+    RemoveChain,
+    SetRpcConfig,
+    SetFeeConfig,
+    AddToken(String),
+    UpdateToken(String),
+    RemoveToken(String),
+}
+
+/// Call statistics of one RPC provider of an EVM chain since the last upgrade.
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct RpcProviderHealth {
+    pub chain_id: u64,
+    pub provider: String,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub average_latency_ms: u64, // over successful calls
+    pub last_error: Option<String>,
+    pub last_success_at: Option<u64>,
+    pub last_failure_at: Option<u64>,
+    pub excluded_until: Option<u64>, // seconds; the provider is skipped until then
+}
+
+/// An entry of the audit trail of chain registry changes.
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct ChainConfigChange {
+    pub id: u64,
+    pub chain_type: ChainType,
+    pub action: ChainConfigAction,
+    pub caller: Principal,
+    pub changed_at: u64, // seconds
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum EvmTxStatus {
+    Pending,
+    Confirmed,
+    Failed(String),
+    Dropped,
+    Replaced,
+}
+
+impl EvmTxStatus {
+    /// Whether the transaction is broadcast and may still be mined.
+    pub fn is_open(&self) -> bool {
+        matches!(self, EvmTxStatus::Pending | EvmTxStatus::Replaced)
+    }
+}
+
+/// An outgoing transaction signed by the DAO on an EVM chain.
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct EvmTransaction {
+    pub id: u64,
+    pub chain_type: ChainType,
+    pub chain_id: u64,
+    pub from: String,
+    pub to: Option<String>,
+    pub nonce: u64,
+    pub value: Nat,
+    pub raw_tx: String,  // 0x-prefixed signed transaction
+    pub tx_hash: String, // keccak256 of the signed transaction
+    pub proposal_id: Option<u64>,
+    pub status: EvmTxStatus,
+    pub created_at: u64, // seconds
+    pub updated_at: u64, // seconds
+    pub block_number: Option<u64>,
+    pub gas_used: Option<u128>,
+    pub effective_gas_price: Option<u128>,
+    pub data: Option<String>, // 0x-prefixed call data
+    pub gas_limit: Option<u128>,
+    pub max_fee_per_gas: Option<u128>,
+    pub max_priority_fee_per_gas: Option<u128>,
+    pub replaced_by: Option<u64>, // id of the speed-up or cancel transaction
+}
+
+/// A proposed EVM transaction decoded for voters and simulated against the latest block.
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct EvmTxPreview {
+    pub chain_type: ChainType,
+    pub from: String,
+    pub to: String,
+    pub value: Nat,
+    pub data: String,             // 0x-prefixed call data
+    pub function: Option<String>, // signature of the called function
+    pub description: String,      // e.g. "transfer 500 USDT to 0x…"
+    pub success: bool,
+    pub return_data: Option<String>,
+    pub revert_reason: Option<String>,
+    pub gas_estimate: Option<u128>,
+    pub simulated_at: u64, // seconds
+}
+
+/// An incoming ERC-20 transfer to a DAO address, recorded once it is deep enough to survive reorgs.
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct EvmDeposit {
+    pub id: u64,
+    pub chain_type: ChainType,
+    pub chain_id: u64,
+    pub token_name: String,
+    pub token_address: String,
+    pub from: String,
+    pub to: String,
+    pub amount: Nat,
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub block_number: u64,
+    pub member_id: Option<String>, // user_id of the member whose eth_address sent it
+    pub detected_at: u64,          // seconds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evm_config(chain_type: ChainType, chain_id: Option<u64>) -> BlockchainConfig {
+        BlockchainConfig::new_evm_config(
+            chain_type,
+            "https://rpc.example.com".to_string(),
+            chain_id,
+            None,
+            None,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn evm_chains_match_by_chain_id() {
+        let ethereum = evm_config(ChainType::Ethereum, None);
+        assert_eq!(ethereum.chain_id(), Some(1));
+        assert!(ethereum.is_chain(&ChainType::Ethereum));
+        assert!(ethereum.is_chain(&ChainType::Evm(1)));
+        assert!(!ethereum.is_chain(&ChainType::Evm(56)));
+        assert!(!ethereum.is_chain(&ChainType::Solana));
+
+        let base = evm_config(ChainType::Evm(8453), None);
+        assert_eq!(base.chain_id(), Some(8453));
+        assert!(base.is_chain(&ChainType::Evm(8453)));
+        assert!(!base.is_chain(&ChainType::Ethereum));
+    }
+
+    #[test]
+    fn configured_chain_id_overrides_the_chain_type() {
+        let local = evm_config(ChainType::EthLocal, Some(1_337));
+        assert_eq!(local.chain_id(), Some(1_337));
+        assert!(local.is_chain(&ChainType::Evm(1_337)));
+        assert!(!local.is_chain(&ChainType::Evm(31_337)));
+    }
+
+    #[test]
+    fn rpc_url_is_a_custom_provider_without_rpc_services() {
+        let config = evm_config(ChainType::BNBChain, None);
+        match config.rpc_services().unwrap() {
+            RpcServices::Custom { chain_id, services } => {
+                assert_eq!(chain_id, 56);
+                assert_eq!(services.len(), 1);
+                assert_eq!(services[0].url, "https://rpc.example.com");
+            }
+            services => panic!("unexpected services {:?}", services),
+        }
+        assert_eq!(config.rpc_providers().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn a_network_without_a_provider_list_uses_all_known_providers() {
+        let mut config = evm_config(ChainType::Ethereum, None);
+        config.rpc_config.as_mut().unwrap().rpc_services = Some(RpcServices::EthMainnet(None));
+        assert_eq!(
+            config.rpc_providers().unwrap().len(),
+            EthMainnetService::all().len()
+        );
+
+        config.rpc_config.as_mut().unwrap().rpc_services = Some(RpcServices::Custom {
+            chain_id: 1,
+            services: vec![],
+        });
+        assert!(config.rpc_providers().is_err());
+
+        let icp = BlockchainConfig::new_official_config(
+            ChainType::InternetComputer,
+            SignatureType::Secp256k1,
+            vec![],
+        );
+        assert!(icp.rpc_services().is_err());
+    }
+}
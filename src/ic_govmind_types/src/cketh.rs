@@ -0,0 +1,173 @@
+//! Types of the ckETH minter interface used for bridging between Ethereum and the IC.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use icrc_ledger_types::icrc1::account::Subaccount;
+use serde::Serialize;
+
+use crate::dao::ChainType;
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct WithdrawalArg {
+    pub recipient: String,
+    pub amount: Nat,
+    pub from_subaccount: Option<Subaccount>,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct RetrieveEthRequest {
+    pub block_index: Nat,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub enum WithdrawalError {
+    AmountTooLow { min_withdrawal_amount: Nat },
+    InsufficientFunds { balance: Nat },
+    InsufficientAllowance { allowance: Nat },
+    RecipientAddressBlocked { address: String },
+    TemporarilyUnavailable(String),
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct WithdrawErc20Arg {
+    pub amount: Nat,
+    pub ckerc20_ledger_id: Principal,
+    pub recipient: String,
+    pub from_cketh_subaccount: Option<Subaccount>,
+    pub from_ckerc20_subaccount: Option<Subaccount>,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct RetrieveErc20Request {
+    pub cketh_block_index: Nat,
+    pub ckerc20_block_index: Nat,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct Erc20Token {
+    pub ckerc20_token_symbol: String,
+    pub erc20_contract_address: String,
+    pub ledger_canister_id: Principal,
+    pub chain_id: Nat,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub enum LedgerError {
+    AmountTooLow {
+        minimum_burn_amount: Nat,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+        ledger_id: Principal,
+    },
+    InsufficientFunds {
+        balance: Nat,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+        ledger_id: Principal,
+    },
+    InsufficientAllowance {
+        allowance: Nat,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+        ledger_id: Principal,
+    },
+    TemporarilyUnavailable(String),
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub enum WithdrawErc20Error {
+    TokenNotSupported {
+        supported_tokens: Vec<Erc20Token>,
+    },
+    RecipientAddressBlocked {
+        address: String,
+    },
+    CkEthLedgerError {
+        error: LedgerError,
+    },
+    CkErc20LedgerError {
+        cketh_block_index: Nat,
+        error: LedgerError,
+    },
+    TemporarilyUnavailable(String),
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct EthTransaction {
+    pub transaction_hash: String,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub enum TxFinalizedStatus {
+    Success {
+        transaction_hash: String,
+        effective_transaction_fee: Option<Nat>,
+    },
+    PendingReimbursement(EthTransaction),
+    Reimbursed {
+        transaction_hash: String,
+        reimbursed_amount: Nat,
+        reimbursed_in_block: Nat,
+    },
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub enum RetrieveEthStatus {
+    NotFound,
+    Pending,
+    TxCreated,
+    TxSent(EthTransaction),
+    TxFinalized(TxFinalizedStatus),
+}
+
+/// The subset of the minter's `get_minter_info` response used to follow deposits.
+#[derive(CandidType, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MinterInfo {
+    pub minter_address: Option<String>,
+    pub deposit_with_subaccount_helper_contract_address: Option<String>,
+    pub last_deposit_with_subaccount_scraped_block_number: Option<Nat>,
+    pub last_eth_scraped_block_number: Option<Nat>,
+    pub last_erc20_scraped_block_number: Option<Nat>,
+}
+
+/// Where the DAO bridges through: the ckETH minter, its deposit helper contract and the ckETH ledger.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct CkEthConfig {
+    pub minter_id: Principal,
+    pub cketh_ledger_id: Principal,
+    pub helper_contract_address: String, // deposit helper supporting `depositEth` and `depositErc20`
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum BridgeDirection {
+    ToIc,  // ETH/ERC-20 deposited into the helper contract, minted as ckETH/ckERC20
+    ToEvm, // ckETH/ckERC20 burned by the minter and released on Ethereum
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum BridgeStatus {
+    Pending,          // deposit transactions broadcast, waiting for their receipts
+    AwaitingMinter,   // deposit mined, waiting for the minter to scrape its block
+    WithdrawalQueued, // burned on the IC, the minter has not sent the Ethereum transaction yet
+    WithdrawalSent,
+    Completed,
+    Reimbursed, // the minter's Ethereum transaction failed and the burned tokens were refunded
+    Failed(String),
+}
+
+/// A bridge operation between the DAO's EVM address and its IC principal.
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct BridgeOperation {
+    pub id: u64,
+    pub direction: BridgeDirection,
+    pub chain_type: ChainType,
+    pub token_name: String,
+    pub amount: Nat,
+    pub evm_address: String, // depositing address or withdrawal recipient
+    pub proposal_id: u64,
+    pub evm_tx_ids: Vec<u64>, // approve and deposit transactions of a deposit
+    pub ledger_block_index: Option<Nat>, // ckETH burn block of a withdrawal
+    pub eth_tx_hash: Option<String>, // deposit or minter withdrawal transaction
+    pub status: BridgeStatus,
+    pub created_at: u64, // seconds
+    pub updated_at: u64, // seconds
+}
@@ -6,10 +6,29 @@ pub const ICRC1_LEDGER_DEFAULT_DECIMAL: u8 = 8;
 pub const ICRC1_LEDGER_DEFAULT_FEE: u128 = 10_000;
 
 pub const ETH_DEFAULT_GAS_PRICE: u64 = 30_000_000_000;
+pub const ETH_DEFAULT_MAX_FEE_PER_GAS_CAP: u64 = 200_000_000_000; // 200 gwei
+pub const ETH_DEFAULT_MAX_PRIORITY_FEE_PER_GAS_CAP: u64 = 5_000_000_000; // 5 gwei
+pub const ETH_MIN_PRIORITY_FEE_PER_GAS: u64 = 1_500_000_000; // 1.5 gwei
+pub const ETH_DEFAULT_FEE_HISTORY_BLOCKS: u64 = 5;
+pub const ETH_DEFAULT_FEE_REWARD_PERCENTILE: u8 = 50;
+pub const ETH_DEFAULT_GAS_LIMIT_MARGIN_PERCENT: u64 = 20;
 pub const ETH_WRAPPED_ETHER: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"; // WETH
 pub const ETH_USDT_ADDRESS: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7"; // USDT
 pub const ETH_USDC_ADDRESS: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"; // USDC
+pub const EVM_TX_RECEIPT_POLL_INTERVAL_SECS: u64 = 30;
+pub const EVM_TX_PENDING_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+pub const EVM_TX_REPLACEMENT_FEE_BUMP_PERCENT: u64 = 12; // nodes require at least +10% to replace
+pub const BRIDGE_STATUS_POLL_INTERVAL_SECS: u64 = 60;
+pub const EVM_DEPOSIT_SCAN_INTERVAL_SECS: u64 = 120;
+pub const EVM_DEPOSIT_CONFIRMATIONS: u64 = 12; // blocks below the head before a log is indexed
+pub const EVM_DEPOSIT_MAX_BLOCK_RANGE: u64 = 500; // blocks per eth_getLogs request
 pub const EVM_CALL_DEFAULT_CYCLES: u128 = 2_000_000_000;
+pub const RPC_PROVIDER_MAX_CONSECUTIVE_FAILURES: u32 = 3; // before a provider is excluded
+pub const RPC_PROVIDER_EXCLUSION_SECS: u64 = 15 * 60;
+pub const VESTING_MAX_RELEASES: u64 = 1_500; // enough for daily releases over four years
+pub const XRC_CALL_CYCLES: u128 = 1_000_000_000;
+pub const PRICE_DEFAULT_TTL_SECS: u64 = 10 * 60;
+pub const PRICE_REFRESH_INTERVAL_SECS: u64 = 5 * 60;
 pub const ETH_TEST_USDT_ADDRESS: &str = "0x707b6ee872d2C7DA369200405CB3DBA4Cfe6332d"; // test USDT
 
 pub const TEST_LEDGER_CANISTER_ID: &str = "s57im-oyaaa-aaaas-akwma-cai";
@@ -23,6 +42,7 @@ pub const LEDGER_CANISTER_ID: &str = "ryjl3-tyaaa-aaaaa-aaaba-cai";
 pub const CKBTC_LEDGER_CANISTER_ID: &str = "mxzaz-hqaaa-aaaar-qaada-cai";
 pub const EVM_RPC_CANISTER_ID: &str = "myniu-wqaaa-aaaah-advna-cai";
 pub const EVM_RPC_OFFICIAL_CANISTER_ID: &str = "7hfb6-caaaa-aaaar-qadga-cai";
+pub const EXCHANGE_RATE_CANISTER_ID: &str = "uf6dk-hyaaa-aaaaq-qaaaq-cai";
 
 #[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
 pub enum CanisterType {
@@ -1,14 +1,21 @@
-use crate::icrc::CreateCanisterArg;
+use crate::{
+    amount::{deserialize_nat, deserialize_nat_map, deserialize_opt_nat},
+    chain::EvmTxPreview,
+    icrc::{ArchiveOptions, CreateCanisterArg},
+};
 use candid::{CandidType, Deserialize, Nat, Principal};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue,
     icrc1::account::{Account, Subaccount},
 };
 use serde::Serialize;
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, str::FromStr};
 
 pub const MINTING_SUBACCOUNT: Subaccount = [1u8; 32];
 pub const HOLDER_SUBACCOUNT: Subaccount = [2u8; 32];
+pub const VESTING_SUBACCOUNT: Subaccount = [3u8; 32];
+pub const AIRDROP_SUBACCOUNT: Subaccount = [4u8; 32];
+pub const REWARDS_SUBACCOUNT: Subaccount = [5u8; 32];
 pub const DAO_BASE_FEE: u64 = 100_000;
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +46,25 @@ pub enum ChainType {
     BNBChain,
     TON,
     Other(String),
+    Evm(u64), // any EVM chain, by EIP-155 chain id
+}
+
+impl ChainType {
+    /// EIP-155 chain id of an EVM chain, `None` for non-EVM chains.
+    pub fn evm_chain_id(&self) -> Option<u64> {
+        match self {
+            ChainType::Ethereum => Some(1),
+            ChainType::EthSepolia => Some(11_155_111),
+            ChainType::EthLocal => Some(31_337),
+            ChainType::BNBChain => Some(56),
+            ChainType::Evm(chain_id) => Some(*chain_id),
+            _ => None,
+        }
+    }
+
+    pub fn is_evm(&self) -> bool {
+        self.evm_chain_id().is_some()
+    }
 }
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
@@ -46,9 +72,12 @@ pub struct BaseToken {
     pub name: String,   // e.g. "GovMind Token"
     pub symbol: String, // e.g. "GOV"
     pub decimals: u8,   // e.g. 8
-    pub total_supply: u128,
+    #[serde(deserialize_with = "deserialize_nat")]
+    pub total_supply: Nat,
     pub distribution_model: Option<DistributionModel>,
     pub token_location: TokenLocation,
+    #[serde(default)]
+    pub supply_cap: Option<Nat>, // mints may not raise the ledger's supply above this
 }
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
@@ -60,19 +89,56 @@ pub struct TokenLocation {
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct DistributionModel {
-    pub initial_distribution: HashMap<String, u128>, // Initial allocation: address → token amount
-    pub emission_period: Option<u64>,                // emission period in SECONDS
-    pub emission_rate: Option<u128>, // Optional: number of tokens emitted per period (e.g., per day/week)
+    #[serde(deserialize_with = "deserialize_nat_map")]
+    pub initial_distribution: HashMap<String, Nat>, // Initial allocation: recipient → token amount, see DistributionRecipient
+    pub emission_period: Option<u64>, // emission period in SECONDS
+    #[serde(deserialize_with = "deserialize_opt_nat")]
+    pub emission_rate: Option<Nat>, // Optional: number of tokens emitted per period (e.g., per day/week)
     pub unlock_schedule: Option<Vec<UnlockScheduleItem>>, // Optional: unlock schedule as a list of (timestamp, amount) pairs
     pub initial_executed_at: Option<u64>,
     pub last_emission_time: Option<u64>,
+    #[serde(default)]
+    pub emission_schedule: Option<EmissionSchedule>, // how emission_rate is split and decays
+    #[serde(default)]
+    pub emission_rounds: Option<u64>, // emission rounds planned so far
+    #[serde(default)]
+    pub emitted_total: Option<Nat>, // emitted so far, including the treasury share
+}
+
+/// Shapes the emission of a distribution model. Each round emits `emission_rate` decayed by the
+/// number of past rounds, split by weight among recipients, until the initial allocations and
+/// the emission reach `BaseToken.total_supply`.
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct EmissionSchedule {
+    pub weights: Option<HashMap<String, Nat>>, // recipient → share weight; defaults to the initial allocations
+    pub decay: EmissionDecay,
+    pub treasury_share_bps: u16, // part of each round kept in the treasury instead of distributed
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub enum EmissionDecay {
+    Constant,
+    Halving { every_rounds: u64 },
+    Exponential { decay_bps: u16 }, // each round emits this much less than the previous one
+}
+
+/// Projected state of the emission after one round.
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct SupplyProjection {
+    pub round: u64,
+    pub timestamp: u64, // seconds
+    pub emission: Nat,
+    pub to_treasury: Nat,
+    pub emitted_total: Nat,
+    pub distributed_supply: Nat, // initial allocations plus emission outside the treasury
 }
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct UnlockScheduleItem {
     pub addr: String,
     pub timestamp: u64,
-    pub amount: u128,
+    #[serde(deserialize_with = "deserialize_nat")]
+    pub amount: Nat,
     pub executed: bool,
 }
 
@@ -101,7 +167,8 @@ pub enum MemberRole {
 pub struct DaoAsset {
     pub chain: ChainType,
     pub symbol: String, // e.g. "ckBTC"
-    pub amount: u128,
+    #[serde(deserialize_with = "deserialize_nat")]
+    pub amount: Nat,
     pub asset_type: AssetType,
     pub canister_id: Option<Principal>,   // for ICP assets
     pub external_address: Option<String>, // for ETH, Solana, etc.
@@ -125,6 +192,12 @@ pub struct Proposal {
     pub status: ProposalStatus,
     pub votes: Vec<Vote>,
     pub metadata: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub tx_previews: Option<Vec<EvmTxPreview>>, // treasury transactions executed if it passes
+    #[serde(default)]
+    pub reward_event_round: Option<u64>, // the reward round that paid for its votes
+    #[serde(default)]
+    pub action_hash: Option<String>, // hex sha256 of the treasury action it authorizes
 }
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
@@ -172,8 +245,11 @@ pub struct CreateBaseTokenArg {
     pub name: String,
     pub symbol: String,
     pub decimals: u8,
-    pub total_supply: u128,
+    pub total_supply: Nat,
     pub distribution_model: Option<DistributionModel>,
+    pub supply_cap: Option<Nat>,
+    pub transfer_fee: Option<Nat>,
+    pub archive_options: Option<ArchiveOptions>,
 }
 
 impl CreateBaseTokenArg {
@@ -192,7 +268,7 @@ impl CreateBaseTokenArg {
             subaccount: Some(HOLDER_SUBACCOUNT),
         };
 
-        let initial_balances = vec![(holder_account.clone(), Nat::from(self.total_supply))];
+        let initial_balances = vec![(holder_account.clone(), self.total_supply.clone())];
         let mut merged_controllers = controllers.unwrap_or_default();
         if !merged_controllers.contains(&current_canister) {
             merged_controllers.push(current_canister);
@@ -205,15 +281,64 @@ impl CreateBaseTokenArg {
             minting_account,
             logo,
             initial_balances,
+            transfer_fee: self.transfer_fee.clone(),
+            archive_options: self.archive_options.clone(),
         }
     }
 }
 
+/// Deploys the DAO's base token as an ERC-20 on an EVM chain, either from `bytecode` or through a
+/// factory exposing `createToken(string,string,uint256,address)`. The whole supply is minted to
+/// `wallet_address`, which also pays for the deployment.
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct CreateEvmBaseTokenArg {
+    pub chain_type: ChainType,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: Nat,
+    pub distribution_model: Option<DistributionModel>,
+    pub wallet_address: String,
+    pub factory_address: Option<String>, // defaults to the GovMind token factory
+    pub bytecode: Option<String>, // creation code with a (string,string,uint256,address) constructor
+    pub proposal_id: Option<u64>,
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub enum TokenDeploymentStatus {
+    Pending,
+    Deployed(String), // contract address
+    Failed(String),
+}
+
+/// An ERC-20 base token deployment waiting for its transaction to be mined.
 #[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct EvmTokenDeployment {
+    pub chain_type: ChainType,
+    pub chain_id: u64,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: Nat,
+    pub distribution_model: Option<DistributionModel>,
+    pub owner: String,
+    pub factory_address: Option<String>, // None for a direct deployment
+    pub evm_tx_id: u64,
+    pub tx_hash: String,
+    pub status: TokenDeploymentStatus,
+    pub created_at: u64, // seconds
+    pub updated_at: u64,
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug, PartialEq)]
 pub enum DistributionType {
-    Initial,   //  initial_distribution
-    Scheduled, //  unlock_schedule
-    Emission,  //  emission_rate
+    Initial,      //  initial_distribution
+    Scheduled,    //  unlock_schedule
+    Emission,     //  emission_rate
+    Vesting,      //  vesting grants
+    Airdrop,      //  airdrop claims
+    Unstake,      //  stakes returned to their owner
+    VotingReward, //  voting participation rewards
 }
 
 #[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
@@ -222,7 +347,33 @@ pub struct DistributionRecord {
     pub timestamp: u64,
     pub recipient: String, // recipient address
     pub amount: Nat,
-    pub tx_result: String, // transaction result or status
+    pub status: DistributionStatus, // Failed holds the ledger's error
+    pub block_index: Option<Nat>,
+    pub error_code: Option<String>, // e.g. "InsufficientFunds", the ledger's error variant
+    pub payout_id: Option<String>,  // None for records kept from before payouts
+    #[serde(default)]
+    pub tx_hash: Option<String>, // transaction of an EVM payout
+}
+
+/// Selects distribution records; unset fields match every record.
+#[derive(CandidType, Clone, Deserialize, Debug, Default)]
+pub struct DistributionRecordFilter {
+    pub recipient: Option<String>,
+    pub distribution_type: Option<DistributionType>,
+    pub status: Option<DistributionStatus>, // Failed matches every error
+    pub from: Option<u64>,                  // nanoseconds, inclusive
+    pub to: Option<u64>,                    // nanoseconds, exclusive
+}
+
+/// What a recipient received over the selected distribution records.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct DistributionTotal {
+    pub recipient: String,
+    pub received: Nat, // completed transfers only
+    pub transfers: u64,
+    pub failed_attempts: u64,
+    pub first_at: u64,
+    pub last_at: u64,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize, Serialize, PartialEq)]
@@ -232,6 +383,259 @@ pub enum DistributionStatus {
     Failed(String),
 }
 
+/// A transfer owed by the distribution model. Payouts are planned once and retried until the
+/// ledger accepts them; `memo` and `created_at_time` stay fixed so the ledger deduplicates retries.
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct DistributionPayout {
+    pub id: String, // e.g. "initial:{recipient}", "emission:{round}:{recipient}"
+    pub distribution_type: DistributionType,
+    pub recipient: String,
+    pub amount: Nat,
+    pub from_subaccount: Option<Subaccount>, // defaults to HOLDER_SUBACCOUNT
+    pub memo: Vec<u8>,
+    pub created_at_time: Option<u64>, // nanoseconds, set on the first attempt
+    pub status: DistributionStatus,
+    pub attempts: u32,
+    pub block_index: Option<Nat>,
+    pub last_error: Option<String>,
+    pub scheduled_at: u64, // seconds
+    pub updated_at: u64,
+    #[serde(default)]
+    pub evm_tx_hash: Option<String>, // ERC-20 transfer of an EVM payout, awaiting its receipt
+}
+
+/// Where a distribution payout goes. Recipients are keyed by their text form: an ICRC-1
+/// account, `evm:{chain_id}:{address}` or `solana:{address}`.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub enum DistributionRecipient {
+    Icrc1(Account),
+    Evm { chain_id: u64, address: String },
+    Solana(String),
+}
+
+impl FromStr for DistributionRecipient {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = s.strip_prefix("evm:") {
+            let (chain_id, address) = rest
+                .split_once(':')
+                .ok_or_else(|| format!("Invalid EVM recipient {}", s))?;
+            let chain_id = chain_id
+                .parse()
+                .map_err(|_| format!("Invalid chain id in EVM recipient {}", s))?;
+            return Ok(DistributionRecipient::Evm {
+                chain_id,
+                address: address.to_string(),
+            });
+        }
+        if let Some(address) = s.strip_prefix("solana:") {
+            return Ok(DistributionRecipient::Solana(address.to_string()));
+        }
+        Account::from_str(s)
+            .map(DistributionRecipient::Icrc1)
+            .map_err(|e| format!("Invalid recipient account {}: {:?}", s, e))
+    }
+}
+
+impl fmt::Display for DistributionRecipient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistributionRecipient::Icrc1(account) => write!(f, "{}", account),
+            DistributionRecipient::Evm { chain_id, address } => {
+                write!(f, "evm:{}:{}", chain_id, address)
+            }
+            DistributionRecipient::Solana(address) => write!(f, "solana:{}", address),
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct VestingGrantArg {
+    pub beneficiary: String, // ICRC-1 account
+    pub total_amount: Nat,
+    pub start: u64,      // seconds
+    pub cliff_secs: u64, // nothing vests before start + cliff
+    pub duration_secs: u64,
+    pub release_interval_secs: u64, // tokens vest in steps of this length
+    pub revocable: bool,
+    pub proposal_id: u64,
+}
+
+/// Tokens set aside in `VESTING_SUBACCOUNT` that vest linearly to a beneficiary after a cliff.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct VestingGrant {
+    pub id: u64,
+    pub beneficiary: String,
+    pub total_amount: Nat,
+    pub released: Nat, // handed to distribution payouts
+    pub start: u64,
+    pub cliff_secs: u64,
+    pub duration_secs: u64,
+    pub release_interval_secs: u64,
+    pub revocable: bool,
+    pub revoked_at: Option<u64>, // vesting stops here; the rest went back to the treasury
+    pub fee: Nat, // ledger fee charged per transfer, follows ledger fee changes
+    pub fee_reserve: Nat, // prefunded ledger fees not spent yet
+    pub proposal_id: u64,
+    pub created_at: u64,
+}
+
+#[derive(CandidType, Clone, Deserialize, Debug)]
+pub struct VestingGrantStatus {
+    pub grant: VestingGrant,
+    pub vested: Nat,
+    pub unvested: Nat,
+    pub releasable: Nat,
+}
+
+/// Bounds of stake dissolve delays and the voting power bonus they earn. A stake's bonus grows
+/// linearly with its remaining dissolve delay, up to `max_bonus_bps` at the maximum delay.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct StakingConfig {
+    pub min_dissolve_delay_secs: u64, // stakes dissolving sooner have no voting power
+    pub max_dissolve_delay_secs: u64,
+    pub max_bonus_bps: u16,
+}
+
+impl Default for StakingConfig {
+    fn default() -> Self {
+        Self {
+            min_dissolve_delay_secs: 7 * 24 * 60 * 60,
+            max_dissolve_delay_secs: 4 * 365 * 24 * 60 * 60,
+            max_bonus_bps: 10_000,
+        }
+    }
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub enum DissolveState {
+    Locked,                          // the full dissolve delay remains
+    Dissolving { dissolve_at: u64 }, // seconds
+}
+
+/// Base tokens a member locked in their staking subaccount of the DAO canister.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct StakePosition {
+    pub id: u64,
+    pub owner: Principal,
+    pub amount: Nat,
+    pub dissolve_delay_secs: u64,
+    pub dissolve_state: DissolveState,
+    pub created_at: u64,
+    pub unstaked_at: Option<u64>, // the tokens were handed to a payout back to the owner
+}
+
+#[derive(CandidType, Clone, Deserialize, Debug)]
+pub struct StakePositionStatus {
+    pub position: StakePosition,
+    pub remaining_delay_secs: u64,
+    pub voting_power: Nat,
+}
+
+/// A member's staked tokens next to the balance of their staking subaccount.
+#[derive(CandidType, Clone, Deserialize, Debug)]
+pub struct StakeReconciliation {
+    pub owner: Principal,
+    pub staked: Nat,
+    pub ledger_balance: Nat,
+}
+
+/// Voting participation rewards. At the end of every period, `amount_per_period` tokens of the
+/// rewards pool at `REWARDS_SUBACCOUNT` are split among the members by the voting weight they
+/// cast on the proposals finalized since the last reward event.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct VotingRewardsConfig {
+    pub period_secs: u64,
+    pub amount_per_period: Nat,
+    pub start_at: u64, // seconds, the first period ends at start_at + period_secs
+}
+
+/// One settled reward period, like the SNS `RewardEvent`.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct RewardEvent {
+    pub round: u64, // starts at 1, matching `Proposal.reward_event_round`
+    pub end_timestamp: u64,
+    pub settled_proposals: Vec<u64>,
+    pub total_weight: u64,
+    pub distributed: Nat, // including the ledger fees of its payouts
+    pub rolled_over: Nat, // nobody voted, so the amount carries over to the next round
+}
+
+/// Mints base tokens from the minting account, to the treasury unless `to` is given.
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct MintTokensArg {
+    pub to: Option<String>, // ICRC-1 account
+    pub amount: Nat,
+    pub proposal_id: u64,
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub enum SupplyChangeKind {
+    Mint,
+    Burn,
+}
+
+/// A governed change of the base token's supply, with the ledger's total supply right after it.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct SupplyChange {
+    pub id: u64,
+    pub kind: SupplyChangeKind,
+    pub account: String, // minted to or burned from
+    pub amount: Nat,
+    pub block_index: Nat,
+    pub recorded_supply: Nat, // `BaseToken.total_supply` after the change
+    pub ledger_supply: Option<Nat>, // `icrc1_total_supply` after the change, if it answered
+    pub proposal_id: u64,
+    pub created_at: u64,
+}
+
+/// The base token's recorded supply next to the ledger's. Transfer fees are burned by the
+/// ledger, so its supply may fall short of the recorded one without any governed burn.
+#[derive(CandidType, Clone, Deserialize, Debug)]
+pub struct SupplyReconciliation {
+    pub recorded_supply: Nat,
+    pub ledger_supply: Nat,
+    pub minted: Nat,         // through governed mints
+    pub burned: Nat,         // through governed burns
+    pub untracked_burn: Nat, // recorded supply missing from the ledger, e.g. burned fees
+    pub untracked_mint: Nat, // ledger supply that was never recorded
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct AirdropArg {
+    pub merkle_root: String, // hex, over the (account, amount) leaves of the recipients
+    pub total_amount: Nat,   // sum of the amounts in the tree
+    pub recipients: u64,     // number of leaves, to fund the fees of their claims
+    pub claim_deadline: u64, // seconds
+    pub proposal_id: u64,
+}
+
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug, PartialEq)]
+pub enum AirdropStatus {
+    Active,
+    Expired, // the unclaimed tokens went back to the treasury
+}
+
+/// Tokens set aside in `AIRDROP_SUBACCOUNT` that recipients claim with a Merkle proof of their
+/// allocation.
+#[derive(CandidType, Clone, Deserialize, Serialize, Debug)]
+pub struct Airdrop {
+    pub id: u64,
+    pub merkle_root: String,
+    pub total_amount: Nat,
+    pub recipients: u64,
+    pub claimed: Nat,
+    pub claims: u64,
+    pub fee: Nat, // ledger fee charged per transfer, follows ledger fee changes
+    pub fee_reserve: Nat, // prefunded ledger fees not spent yet
+    pub claim_deadline: u64,
+    pub status: AirdropStatus,
+    pub returned: Option<Nat>, // sent back to the treasury after the deadline
+    pub proposal_id: u64,
+    pub created_at: u64,
+}
+
 #[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
 pub struct JobSchedule {
     pub job_id: String,
@@ -353,3 +757,31 @@ impl CommitteeArg {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recipients_round_trip_through_text() {
+        let evm = "evm:1:0x00000000000000000000000000000000000000aa";
+        assert_eq!(
+            DistributionRecipient::from_str(evm),
+            Ok(DistributionRecipient::Evm {
+                chain_id: 1,
+                address: "0x00000000000000000000000000000000000000aa".to_string(),
+            })
+        );
+
+        let account = Account::from(Principal::anonymous()).to_string();
+        for text in [
+            evm,
+            "solana:11111111111111111111111111111111",
+            account.as_str(),
+        ] {
+            let recipient = DistributionRecipient::from_str(text).unwrap();
+            assert_eq!(recipient.to_string(), text);
+        }
+        assert!(DistributionRecipient::from_str("evm:mainnet:0x00").is_err());
+    }
+}
@@ -1,9 +1,11 @@
 use candid::{CandidType, Deserialize, Nat, Principal};
 use icrc_ledger_types::{icrc::generic_metadata_value::MetadataValue, icrc1::account::Account};
+use serde::Serialize;
+use serde_bytes::ByteBuf;
 
 use crate::constants::{ICRC1_LEDGER_DEFAULT_DECIMAL, ICRC1_LEDGER_DEFAULT_FEE};
 
-#[derive(CandidType, Deserialize)]
+#[derive(CandidType, Deserialize, Debug, Clone)]
 pub struct ArchiveOptions {
     pub num_blocks_to_archive: u64,
     pub max_transactions_per_response: Option<u64>,
@@ -28,6 +30,8 @@ pub struct CreateCanisterArg {
     pub minting_account: Account,
     pub initial_balances: Vec<(Account, Nat)>,
     pub logo: MetadataValue,
+    pub transfer_fee: Option<Nat>, // defaults to ICRC1_LEDGER_DEFAULT_FEE
+    pub archive_options: Option<ArchiveOptions>,
 }
 impl CreateCanisterArg {
     pub fn new(
@@ -45,6 +49,8 @@ impl CreateCanisterArg {
             minting_account,
             initial_balances,
             logo,
+            transfer_fee: None,
+            archive_options: None,
         }
     }
 }
@@ -103,7 +109,7 @@ impl ICRC1InitArgs {
     }
 }
 
-#[derive(CandidType, Deserialize)]
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone)]
 pub enum ChangeFeeCollector {
     SetTo(Account),
     Unset,
@@ -127,3 +133,28 @@ pub enum LedgerArgument {
     Upgrade(Option<UpgradeArgs>),
     Init(ICRC1InitArgs),
 }
+
+/// A governed upgrade of the DAO's ICRC-1 ledger. Unset fields keep their current value.
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct LedgerUpgradeArg {
+    pub proposal_id: u64,
+    pub wasm_module: Option<ByteBuf>, // defaults to the ledger bundled with the backend
+    pub transfer_fee: Option<Nat>,
+    pub logo: Option<MetadataValue>,
+    pub metadata: Option<Vec<(String, MetadataValue)>>, // merged into the current metadata
+    pub change_fee_collector: Option<ChangeFeeCollector>,
+    pub max_memo_length: Option<u16>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Debug, Clone)]
+pub struct LedgerUpgrade {
+    pub id: u64,
+    pub ledger_id: Principal,
+    pub proposal_id: u64,
+    pub wasm_hash: String, // hex sha256 of the installed module
+    pub transfer_fee: Option<Nat>,
+    pub metadata_keys: Vec<String>, // entries that were set, e.g. "icrc1:logo"
+    pub change_fee_collector: Option<ChangeFeeCollector>,
+    pub max_memo_length: Option<u16>,
+    pub upgraded_at: u64, // seconds
+}
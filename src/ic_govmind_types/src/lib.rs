@@ -3,12 +3,15 @@ use ciborium::into_writer;
 use num_traits::cast::ToPrimitive;
 use serde::Serialize;
 
+pub mod amount;
 pub mod chain;
+pub mod cketh;
 pub mod constants;
 pub mod dao;
 pub mod error;
 pub mod icrc;
 pub mod payment;
+pub mod price;
 pub mod status;
 pub mod user;
 
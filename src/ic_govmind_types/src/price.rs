@@ -0,0 +1,65 @@
+//! USD price feeds and treasury valuation.
+//!
+//! USD amounts are fixed-point with `USD_DECIMALS` decimals, so budgets and spending limits can
+//! be compared against them without floating point.
+
+use candid::{CandidType, Deserialize, Nat, Principal};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::dao::ChainType;
+
+pub const USD_DECIMALS: u8 = 8;
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub enum PriceSource {
+    /// An HTTP outcall to a JSON endpoint. `{symbol}` in the URL is replaced with the token
+    /// symbol and `json_path` is a dot-separated path to the USD price, e.g. `data.0.price`.
+    Http {
+        url: String,
+        json_path: String,
+        headers: Vec<(String, String)>,
+    },
+    /// The IC exchange-rate canister, quoting `base_symbol` (the token symbol by default) in USD.
+    ExchangeRateCanister {
+        canister_id: Principal,
+        base_symbol: Option<String>,
+    },
+    /// A price set by governance, e.g. 1 USD for a stablecoin.
+    Manual { price_usd: Nat },
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct PriceFeedConfig {
+    pub default_source: PriceSource,
+    pub sources: HashMap<String, PriceSource>, // symbol → source overriding the default
+    pub ttl_secs: u64,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct TokenPrice {
+    pub symbol: String,
+    pub price_usd: Nat, // USD per whole token
+    pub source: String,
+    pub updated_at: u64, // seconds
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct AssetValuation {
+    pub chain: ChainType,
+    pub symbol: String,
+    pub amount: Nat,
+    pub decimals: Option<u8>,
+    pub price: Option<TokenPrice>,
+    pub value_usd: Option<Nat>,
+    pub stale: bool, // the price is older than the feed's TTL
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, Serialize)]
+pub struct TreasuryValuation {
+    pub assets: Vec<AssetValuation>,
+    pub total_usd: Nat, // sum of the assets that could be valued
+    pub usd_decimals: u8,
+    pub complete: bool, // every asset has a fresh price
+    pub valued_at: u64, // seconds
+}